@@ -0,0 +1,530 @@
+use rocket::State;
+use rocket::http::Status;
+use rocket::request::{FromRequest, Outcome, Request};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::Key;
+use crate::app_state::{self, AppState};
+use crate::backend::{self, Interaction, User};
+use crate::scope::Scopes;
+use crate::transfer::{ConflictPolicy, TransferFormat};
+
+#[cfg(test)]
+mod test;
+
+// Board and key management over HTTP, for deployments without the CLI. Requests carry the
+// admin_key from config.json in x-admin-key, and the API is off while it is unset.
+pub struct Admin<'r> {
+    pub state: &'r State<Arc<AppState>>,
+}
+
+#[derive(Debug)]
+pub enum AdminKeyError {
+    Missing,
+    Invalid,
+    Disabled,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Admin<'r> {
+    type Error = AdminKeyError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let state = req.guard::<&State<Arc<AppState>>>().await;
+        if let Some(state) = state.succeeded() {
+            let admin_key = match state.config.lock().unwrap().admin_key.clone() {
+                Some(v) if !v.is_empty() => v,
+                _ => return Outcome::Error((Status::Forbidden, AdminKeyError::Disabled)),
+            };
+
+            return match req.headers().get_one("x-admin-key") {
+                None => Outcome::Error((Status::BadRequest, AdminKeyError::Missing)),
+                Some(key) if same_key(key, &admin_key) => Outcome::Success(Admin { state: state }),
+                Some(_) => Outcome::Error((Status::Unauthorized, AdminKeyError::Invalid)),
+            };
+        }
+
+        return Outcome::Error((Status::InternalServerError, AdminKeyError::Invalid));
+    }
+}
+
+// Compares every byte, so how long it takes doesn't say how much of the key was right.
+fn same_key(given: &str, key: &str) -> bool {
+    given.len() == key.len()
+        && given
+            .bytes()
+            .zip(key.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+// code is 0 when the change was made and -1 when it wasn't, with why in message.
+#[derive(Serialize)]
+struct AdminResponse<T: Serialize> {
+    code: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<T>,
+}
+
+fn respond<T: Serialize>(code: i64, message: String, result: Option<T>) -> Result<String, Status> {
+    Ok(serde_json::to_string(&AdminResponse {
+        code: code,
+        message: message,
+        result: result,
+    })
+    .unwrap())
+}
+
+fn done(message: String) -> Result<String, Status> {
+    respond::<()>(0, message, None)
+}
+
+fn failed(message: String) -> Result<String, Status> {
+    respond::<()>(-1, message, None)
+}
+
+fn read_only(board: &str) -> Result<String, Status> {
+    failed(format!(
+        "Board {board} is read-only until its quarantined save is retried."
+    ))
+}
+
+fn requested_scopes(write: Option<bool>, scopes: Option<Scopes>) -> Option<Scopes> {
+    scopes.or(write.map(Scopes::from_write))
+}
+
+fn parse<'a, T: Deserialize<'a>>(dat: &'a str) -> Result<T, Status> {
+    serde_json::from_str::<T>(dat).map_err(|_| Status::BadRequest)
+}
+
+#[derive(Deserialize)]
+struct BoardReq {
+    board: String,
+}
+
+#[derive(Deserialize)]
+struct OptionalBoardReq {
+    #[serde(default)]
+    board: Option<String>,
+}
+
+// Keys are given "scopes", or "write" for what it allowed before scopes.
+#[derive(Deserialize)]
+struct NewKeyReq {
+    key: String,
+    board: String,
+    #[serde(default)]
+    write: Option<bool>,
+    #[serde(default)]
+    scopes: Option<Scopes>,
+}
+
+#[derive(Deserialize)]
+struct KeyReq {
+    key: String,
+}
+
+#[derive(Deserialize)]
+struct SetWriteReq {
+    key: String,
+    #[serde(default)]
+    write: Option<bool>,
+    #[serde(default)]
+    scopes: Option<Scopes>,
+}
+
+#[derive(Deserialize)]
+struct SetPlayerReq {
+    key: String,
+    // unbinds the key when null
+    player: Option<Key>,
+}
+
+#[derive(Deserialize)]
+struct CapReq {
+    board: String,
+    // removes the cap when null
+    cap: Option<usize>,
+    // whether entries beyond the new cap are removed right away
+    #[serde(default)]
+    trim: bool,
+}
+
+#[derive(Serialize)]
+struct BoardSummary {
+    name: String,
+    size: usize,
+    cap: Option<usize>,
+    read_only: bool,
+}
+
+#[derive(Deserialize)]
+struct GrantReq {
+    key: String,
+    // a board name, or a pattern with * in it
+    board: String,
+    // takes the board back when neither is given
+    #[serde(default)]
+    write: Option<bool>,
+    #[serde(default)]
+    scopes: Option<Scopes>,
+}
+
+#[derive(Serialize)]
+struct KeySummary {
+    key: String,
+    board: String,
+    scopes: Scopes,
+    boards: HashMap<String, Scopes>,
+    player: Option<Key>,
+}
+
+pub fn execute_boards(admin: &Admin, _: String) -> Result<String, Status> {
+    let mut names: Vec<String> = admin.state.boards.read().unwrap().keys().cloned().collect();
+    names.sort_unstable();
+
+    let boards: Vec<BoardSummary> = names
+        .into_iter()
+        .filter_map(|name| {
+            let binding = admin.state.get_board(&name)?;
+            let board = binding.read().unwrap();
+            Some(BoardSummary {
+                size: board.get_size(),
+                cap: board.get_size_cap(),
+                read_only: admin.state.is_read_only(&name),
+                name: name,
+            })
+        })
+        .collect();
+
+    respond(0, format!("{} boards.", boards.len()), Some(boards))
+}
+
+pub fn execute_new_board(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: BoardReq = parse(&dat)?;
+    if json.board.is_empty() {
+        return failed("Board names can't be empty.".to_string());
+    }
+    if let Some(c) = app_state::invalid_name_char(&json.board) {
+        return failed(format!("Invalid character \"{c}\" in board name."));
+    }
+
+    match admin.state.create_board(json.board.clone()) {
+        Ok(true) => done(format!("Created board {}.", json.board)),
+        Ok(false) => failed(format!("Board {} already exists.", json.board)),
+        Err(err) => failed(err),
+    }
+}
+
+pub fn execute_del_board(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: BoardReq = parse(&dat)?;
+    match admin.state.delete_board(&json.board) {
+        true => done(format!("Deleted board {}.", json.board)),
+        false => failed(format!("No board named {}.", json.board)),
+    }
+}
+
+pub fn execute_keys(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: OptionalBoardReq = parse(&dat)?;
+    if let Some(board) = &json.board
+        && admin.state.get_board(board).is_none()
+    {
+        return failed(format!("No board named {board}."));
+    }
+
+    let users = admin.state.api_keys.lock().unwrap();
+    let mut keys: Vec<KeySummary> = users
+        .iter()
+        .filter(|(_, user)| {
+            json.board
+                .as_ref()
+                .is_none_or(|board| user.access(board).is_some())
+        })
+        .map(|(key, user)| KeySummary {
+            key: key.clone(),
+            board: user.board.clone(),
+            scopes: user.scopes,
+            boards: user.boards.clone(),
+            player: user.player,
+        })
+        .collect();
+    let _ = drop(users);
+    keys.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+
+    respond(0, format!("{} keys.", keys.len()), Some(keys))
+}
+
+pub fn execute_new_key(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: NewKeyReq = parse(&dat)?;
+    if json.key.is_empty() {
+        return failed("API keys can't be empty.".to_string());
+    }
+    if admin.state.get_board(&json.board).is_none() {
+        return failed(format!("No board named {}.", json.board));
+    }
+    let scopes = requested_scopes(json.write, json.scopes).ok_or(Status::BadRequest)?;
+
+    match admin
+        .state
+        .create_key(json.key.clone(), json.board.clone(), scopes)
+    {
+        true => done(format!(
+            "Added API key {} to board {}, with {}.",
+            json.key,
+            json.board,
+            scopes.names()
+        )),
+        false => failed(format!("API key {} already exists.", json.key)),
+    }
+}
+
+pub fn execute_del_key(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: KeyReq = parse(&dat)?;
+    match admin.state.delete_key(&json.key) {
+        true => done(format!("Removed API key {}.", json.key)),
+        false => failed(format!("No API key {}.", json.key)),
+    }
+}
+
+pub fn execute_set_write(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: SetWriteReq = parse(&dat)?;
+    let scopes = requested_scopes(json.write, json.scopes).ok_or(Status::BadRequest)?;
+    match admin.state.set_key_scopes(&json.key, scopes) {
+        true => done(format!("API key {} now has {}.", json.key, scopes.names())),
+        false => failed(format!("No API key {}.", json.key)),
+    }
+}
+
+pub fn execute_set_player(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: SetPlayerReq = parse(&dat)?;
+    if !admin.state.set_key_player(&json.key, json.player) {
+        return failed(format!("No API key {}.", json.key));
+    }
+    match json.player {
+        Some(player) => done(format!("API key {} now reads player {player}.", json.key)),
+        None => done(format!(
+            "API key {} is no longer bound to a player.",
+            json.key
+        )),
+    }
+}
+
+pub fn execute_grant(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: GrantReq = parse(&dat)?;
+    if json.board.is_empty() {
+        return failed("Board patterns can't be empty.".to_string());
+    }
+    if let Some(c) = app_state::invalid_pattern_char(&json.board) {
+        return failed(format!("Invalid character \"{c}\" in board pattern."));
+    }
+
+    let scopes = requested_scopes(json.write, json.scopes);
+    if !admin
+        .state
+        .set_key_board_access(&json.key, &json.board, scopes)
+    {
+        return failed(format!("No API key {}.", json.key));
+    }
+    match scopes {
+        Some(scopes) => done(format!(
+            "API key {} can use {}, with {}.",
+            json.key,
+            json.board,
+            scopes.names()
+        )),
+        None => done(format!(
+            "Removed {} from the boards API key {} can use.",
+            json.board, json.key
+        )),
+    }
+}
+
+pub fn execute_cap(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: CapReq = parse(&dat)?;
+    if json.trim && json.cap.is_some() && admin.state.is_read_only(&json.board) {
+        return read_only(&json.board);
+    }
+    let cap = match json.cap {
+        Some(v) => v,
+        None => match admin.state.rem_board_cap(&json.board) {
+            true => return done(format!("Removed the size cap of board {}.", json.board)),
+            false => return failed(format!("No board named {}.", json.board)),
+        },
+    };
+
+    let (beyond, trimmed) = match json.trim {
+        true => match admin.state.set_board_cap_trimmed(&json.board, cap) {
+            Some(trimmed) => (0, trimmed),
+            None => return failed(format!("No board named {}.", json.board)),
+        },
+        false => {
+            if !admin.state.set_board_cap(&json.board, cap) {
+                return failed(format!("No board named {}.", json.board));
+            }
+            match admin.state.get_board(&json.board) {
+                Some(binding) => (binding.read().unwrap().get_size().saturating_sub(cap), 0),
+                None => (0, 0),
+            }
+        }
+    };
+    if trimmed > 0 {
+        admin.state.notify_change(&json.board);
+    }
+
+    let mut message = format!("Set the size cap of board {} to {cap}.", json.board);
+    if trimmed > 0 {
+        message += &format!(" Trimmed {trimmed} entries beyond it.");
+    }
+    if beyond > 0 {
+        message += &format!(" {beyond} entries are beyond it, trim to remove them.");
+    }
+    done(message)
+}
+
+pub fn execute_trim(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: BoardReq = parse(&dat)?;
+    if admin.state.get_board(&json.board).is_none() {
+        return failed(format!("No board named {}.", json.board));
+    }
+    if admin.state.is_read_only(&json.board) {
+        return read_only(&json.board);
+    }
+
+    let trimmed = trim_board(admin, &json.board);
+    respond(
+        0,
+        format!("Trimmed {trimmed} entries from board {}.", json.board),
+        Some(trimmed),
+    )
+}
+
+// Removes the entries beyond the board's cap and returns how many there were.
+fn trim_board(admin: &Admin, name: &String) -> usize {
+    let binding = match admin.state.get_board(name) {
+        Some(v) => v,
+        None => return 0,
+    };
+    let mut board = binding.write().unwrap();
+    let removed = board.trim_after_cap();
+    admin.state.journal_removed(name, &removed);
+    let trimmed = removed.get_size();
+    let _ = drop(board);
+
+    if trimmed > 0 {
+        admin.state.notify_change(name);
+    }
+    trimmed
+}
+
+pub fn execute_clear(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: BoardReq = parse(&dat)?;
+    if admin.state.is_read_only(&json.board) {
+        return read_only(&json.board);
+    }
+    match backend::clear_board(admin.state, &json.board) {
+        true => {
+            admin.state.notify_change(&json.board);
+            done(format!("Cleared board {}.", json.board))
+        }
+        false => failed(format!("No board named {}.", json.board)),
+    }
+}
+
+// Acts on the named board with every scope, for what the admin API shares with the CLI.
+fn board_interaction<'r>(admin: &Admin<'r>, board: &str) -> Option<Interaction<'r>> {
+    let board = board.to_string();
+    admin.state.get_board(&board)?;
+    Some(Interaction {
+        user: User {
+            board: board,
+            scopes: Scopes::all(),
+            boards: HashMap::new(),
+            player: None,
+        },
+        state: admin.state,
+    })
+}
+
+pub fn execute_export(
+    admin: &Admin,
+    board: &str,
+    format: TransferFormat,
+) -> Result<Vec<u8>, Status> {
+    let interaction = board_interaction(admin, board).ok_or(Status::NotFound)?;
+
+    let mut body = Vec::new();
+    if backend::export_board(&interaction, format, &mut body).is_err() {
+        // a reload or /admin/del_board can unload the board before the export reads it
+        if admin.state.get_board(&interaction.user.board).is_none() {
+            return Err(Status::NotFound);
+        }
+        return Err(Status::InternalServerError);
+    }
+    Ok(body)
+}
+
+pub fn execute_import(
+    admin: &Admin,
+    board: &str,
+    dat: &[u8],
+    format: TransferFormat,
+    policy: ConflictPolicy,
+) -> Result<String, Status> {
+    let interaction = match board_interaction(admin, board) {
+        Some(v) => v,
+        None => return failed(format!("No board named {board}.")),
+    };
+    if admin.state.is_read_only(&interaction.user.board) {
+        return read_only(board);
+    }
+
+    match backend::import_board(&interaction, dat, format, policy) {
+        Ok(summary) => Ok(serde_json::to_string(&summary).unwrap()),
+        Err(err) => failed(format!("Failed to import: {err}")),
+    }
+}
+
+// Saves one board, or all of them when none is given. The boards that failed are the result.
+pub fn execute_save(admin: &Admin, dat: String) -> Result<String, Status> {
+    let json: OptionalBoardReq = parse(&dat)?;
+    let state = admin.state.inner();
+
+    match json.board {
+        Some(board) => {
+            if state.get_board(&board).is_none() {
+                return failed(format!("No board named {board}."));
+            }
+            let failed = backend::save_boards(state, &state.saves_path, vec![board.clone()]);
+            if !failed.is_empty() {
+                return respond(-1, format!("Failed to save board {board}."), Some(failed));
+            }
+            done(format!("Saved board {board}."))
+        }
+        None => {
+            let failed = backend::save(state, &state.saves_path);
+            if !failed.is_empty() {
+                return respond(
+                    -1,
+                    format!("Failed to save {} boards.", failed.len()),
+                    Some(failed),
+                );
+            }
+            done("Saved all boards.".to_string())
+        }
+    }
+}
+
+pub fn execute_reload(admin: &Admin, _: String) -> Result<String, Status> {
+    let state = admin.state.inner();
+    match backend::reload(state, &state.saves_path) {
+        Ok(changes) => respond(
+            0,
+            format!("Reloaded with {} changes.", changes.len()),
+            Some(changes),
+        ),
+        Err(err) => failed(err),
+    }
+}
@@ -0,0 +1,326 @@
+use rocket::http::{Header, Status};
+use rocket::local::blocking::Client;
+
+use super::*;
+use crate::Val;
+use crate::app_state::test::{TEST_CONFIG, temp_state, update};
+
+const ADMIN_CONFIG: &str = r#"{"port": 0, "save_interval": 600, "lock_save": false, "cache_len": 5, "admin_key": "secret"}"#;
+
+const BOARDS: &str = r#"{
+    "main": {"keys": {"k": {"write": true}}, "cap": null},
+    "other": {"keys": {}, "cap": null}
+}"#;
+
+#[rocket::post("/new_board", data = "<data>")]
+fn new_board(admin: Admin, data: String) -> Result<String, Status> {
+    execute_new_board(&admin, data)
+}
+
+#[rocket::post("/cap", data = "<data>")]
+fn cap(admin: Admin, data: String) -> Result<String, Status> {
+    execute_cap(&admin, data)
+}
+
+#[rocket::post("/trim", data = "<data>")]
+fn trim(admin: Admin, data: String) -> Result<String, Status> {
+    execute_trim(&admin, data)
+}
+
+#[rocket::post("/clear", data = "<data>")]
+fn clear(admin: Admin, data: String) -> Result<String, Status> {
+    execute_clear(&admin, data)
+}
+
+#[rocket::post("/del_key", data = "<data>")]
+fn del_key(admin: Admin, data: String) -> Result<String, Status> {
+    execute_del_key(&admin, data)
+}
+
+#[rocket::post("/set_player", data = "<data>")]
+fn set_player(admin: Admin, data: String) -> Result<String, Status> {
+    execute_set_player(&admin, data)
+}
+
+#[rocket::get("/export?<board>")]
+fn export(admin: Admin, board: &str) -> Result<Vec<u8>, Status> {
+    execute_export(&admin, board, TransferFormat::Json)
+}
+
+#[rocket::post("/import?<board>", data = "<data>")]
+fn import(admin: Admin, board: &str, data: String) -> Result<String, Status> {
+    execute_import(
+        &admin,
+        board,
+        data.as_bytes(),
+        TransferFormat::Json,
+        ConflictPolicy::Replace,
+    )
+}
+
+fn admin_client(name: &str, config: &str) -> (Client, Arc<AppState>) {
+    let (state, _) = temp_state(name, config, BOARDS);
+    let state = Arc::new(state);
+    let rocket = rocket::build().manage(state.clone()).mount(
+        "/",
+        rocket::routes![
+            new_board, cap, trim, clear, del_key, set_player, export, import
+        ],
+    );
+    (Client::tracked(rocket).unwrap(), state)
+}
+
+// Posts with the admin key and returns the JSON the route answered with.
+fn post(client: &Client, path: &str, body: &str) -> serde_json::Value {
+    let response = client
+        .post(path)
+        .header(Header::new("x-admin-key", "secret"))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    serde_json::from_str(&response.into_string().unwrap()).unwrap()
+}
+
+fn size(state: &AppState, board: &str) -> usize {
+    state
+        .get_board(&board.to_string())
+        .unwrap()
+        .read()
+        .unwrap()
+        .get_size()
+}
+
+#[test]
+fn test_same_key() {
+    assert!(same_key("secret", "secret"));
+    assert!(same_key("", ""));
+    assert!(!same_key("secreT", "secret"));
+    assert!(!same_key("secret", "secret2"));
+    assert!(!same_key("secret2", "secret"));
+    assert!(!same_key("", "secret"));
+}
+
+#[test]
+fn test_admin_key() {
+    let body = r#"{"board": "new"}"#;
+
+    // no admin_key in config.json turns the API off
+    let (client, _) = admin_client("admin_disabled", TEST_CONFIG);
+    let response = client
+        .post("/new_board")
+        .header(Header::new("x-admin-key", "secret"))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+
+    // and so does an empty one
+    let config =
+        r#"{"port": 0, "save_interval": 600, "lock_save": false, "cache_len": 5, "admin_key": ""}"#;
+    let (client, _) = admin_client("admin_empty", config);
+    let response = client
+        .post("/new_board")
+        .header(Header::new("x-admin-key", ""))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+
+    let (client, state) = admin_client("admin_key", ADMIN_CONFIG);
+    let response = client.post("/new_board").body(body).dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response = client
+        .post("/new_board")
+        .header(Header::new("x-admin-key", "secreT"))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    // an API key isn't an admin key
+    let response = client
+        .post("/new_board")
+        .header(Header::new("x-admin-key", "k"))
+        .body(body)
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    assert!(state.get_board(&"new".to_string()).is_none());
+
+    assert_eq!(post(&client, "/new_board", body)["code"], 0);
+    assert!(state.get_board(&"new".to_string()).is_some());
+}
+
+#[test]
+fn test_new_board() {
+    let (client, state) = admin_client("admin_new_board", ADMIN_CONFIG);
+
+    let json = post(&client, "/new_board", r#"{"board": "new"}"#);
+    assert_eq!(json["code"], 0);
+    assert_eq!(json["message"], "Created board new.");
+    assert!(json.get("result").is_none());
+    assert!(state.get_board(&"new".to_string()).is_some());
+
+    let json = post(&client, "/new_board", r#"{"board": "new"}"#);
+    assert_eq!(json["code"], -1);
+    assert_eq!(json["message"], "Board new already exists.");
+
+    let json = post(&client, "/new_board", r#"{"board": ""}"#);
+    assert_eq!(json["code"], -1);
+    assert_eq!(json["message"], "Board names can't be empty.");
+
+    let json = post(&client, "/new_board", r#"{"board": "a/b"}"#);
+    assert_eq!(json["code"], -1);
+    assert_eq!(json["message"], "Invalid character \"/\" in board name.");
+    assert!(state.get_board(&"a/b".to_string()).is_none());
+
+    let response = client
+        .post("/new_board")
+        .header(Header::new("x-admin-key", "secret"))
+        .body(r#"{"name": "new"}"#)
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn test_cap() {
+    let (client, state) = admin_client("admin_cap", ADMIN_CONFIG);
+    for key in 0..5 {
+        update(&state, "main", key, key as Val);
+    }
+
+    // without trim the entries beyond it stay
+    let json = post(&client, "/cap", r#"{"board": "main", "cap": 3}"#);
+    assert_eq!(json["code"], 0);
+    assert_eq!(
+        json["message"],
+        "Set the size cap of board main to 3. 2 entries are beyond it, trim to remove them."
+    );
+    assert_eq!(size(&state, "main"), 5);
+
+    let json = post(
+        &client,
+        "/cap",
+        r#"{"board": "main", "cap": 2, "trim": true}"#,
+    );
+    assert_eq!(json["code"], 0);
+    assert_eq!(
+        json["message"],
+        "Set the size cap of board main to 2. Trimmed 3 entries beyond it."
+    );
+    assert_eq!(size(&state, "main"), 2);
+    let binding = state.get_board(&"main".to_string()).unwrap();
+    let board = binding.read().unwrap();
+    assert_eq!(board.get_size_cap(), Some(2));
+    assert!(board.get_entry(&4).is_some());
+    assert!(board.get_entry(&0).is_none());
+    let _ = drop(board);
+
+    let json = post(&client, "/cap", r#"{"board": "main", "cap": null}"#);
+    assert_eq!(json["code"], 0);
+    assert_eq!(json["message"], "Removed the size cap of board main.");
+    assert_eq!(binding.read().unwrap().get_size_cap(), None);
+
+    let json = post(
+        &client,
+        "/cap",
+        r#"{"board": "none", "cap": 2, "trim": true}"#,
+    );
+    assert_eq!(json["code"], -1);
+    assert_eq!(json["message"], "No board named none.");
+}
+
+#[test]
+fn test_read_only() {
+    let (client, state) = admin_client("admin_read_only", ADMIN_CONFIG);
+    for key in 0..5 {
+        update(&state, "main", key, key as Val);
+    }
+    state.read_only.lock().unwrap().insert("main".to_string());
+
+    // what removes entries waits for the quarantined save
+    let message = "Board main is read-only until its quarantined save is retried.";
+    for (path, body) in [
+        ("/trim", r#"{"board": "main"}"#),
+        ("/clear", r#"{"board": "main"}"#),
+        ("/cap", r#"{"board": "main", "cap": 2, "trim": true}"#),
+    ] {
+        let json = post(&client, path, body);
+        assert_eq!(json["code"], -1);
+        assert_eq!(json["message"], message);
+    }
+    assert_eq!(size(&state, "main"), 5);
+    let binding = state.get_board(&"main".to_string()).unwrap();
+    assert_eq!(binding.read().unwrap().get_size_cap(), None);
+
+    // a cap alone still applies
+    let json = post(&client, "/cap", r#"{"board": "main", "cap": 2}"#);
+    assert_eq!(json["code"], 0);
+    assert_eq!(size(&state, "main"), 5);
+}
+
+#[test]
+fn test_del_key() {
+    let (client, state) = admin_client("admin_del_key", ADMIN_CONFIG);
+
+    let json = post(&client, "/del_key", r#"{"key": "k"}"#);
+    assert_eq!(json["code"], 0);
+    assert_eq!(json["message"], "Removed API key k.");
+    assert!(!state.api_keys.lock().unwrap().contains_key("k"));
+
+    let json = post(&client, "/del_key", r#"{"key": "k"}"#);
+    assert_eq!(json["code"], -1);
+    assert_eq!(json["message"], "No API key k.");
+}
+
+#[test]
+fn test_set_player() {
+    let (client, state) = admin_client("admin_set_player", ADMIN_CONFIG);
+
+    let json = post(&client, "/set_player", r#"{"key": "k", "player": 4}"#);
+    assert_eq!(json["code"], 0);
+    assert_eq!(json["message"], "API key k now reads player 4.");
+    assert_eq!(state.api_keys.lock().unwrap()["k"].player, Some(4));
+
+    let json = post(&client, "/set_player", r#"{"key": "k", "player": null}"#);
+    assert_eq!(json["code"], 0);
+    assert_eq!(json["message"], "API key k is no longer bound to a player.");
+    assert_eq!(state.api_keys.lock().unwrap()["k"].player, None);
+
+    let json = post(&client, "/set_player", r#"{"key": "none", "player": 4}"#);
+    assert_eq!(json["code"], -1);
+    assert_eq!(json["message"], "No API key none.");
+}
+
+#[test]
+fn test_export_import() {
+    let (client, state) = admin_client("admin_transfer", ADMIN_CONFIG);
+    for key in 0..3 {
+        update(&state, "main", key, key as Val);
+    }
+
+    // export and import name the board, an API key doesn't get in
+    let response = client
+        .get("/export?board=main")
+        .header(Header::new("x-api-key", "k"))
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response = client
+        .get("/export?board=none")
+        .header(Header::new("x-admin-key", "secret"))
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response = client
+        .get("/export?board=main")
+        .header(Header::new("x-admin-key", "secret"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let exported = response.into_string().unwrap();
+
+    let json = post(&client, "/import?board=other", &exported);
+    assert_eq!(json["added"], 3);
+    assert_eq!(size(&state, "other"), 3);
+
+    let json = post(&client, "/import?board=none", &exported);
+    assert_eq!(json["code"], -1);
+    assert_eq!(json["message"], "No board named none.");
+}
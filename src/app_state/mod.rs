@@ -0,0 +1,1433 @@
+#[cfg(feature = "indicatif")]
+use indicatif::{ProgressBar, ProgressStyle};
+use rocket::tokio::sync::watch;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Seek, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Instant;
+
+use crate::backend::User;
+#[cfg(feature = "indicatif")]
+use crate::board::SavedEntries;
+use crate::board::{AnyIndex, Board, Entry, IndexKind, RankCheckpoint};
+use crate::webhook::{self, ConfigWebhook, Webhook};
+use crate::scope::Scopes;
+use crate::persist::{
+    Backup, BackupRetention, Compression, FsyncPolicy, Journal, JournalRecord, QuarantineMode,
+    Quarantined,
+};
+use crate::{Key, Val, persist, util};
+
+#[cfg(test)]
+pub(crate) mod test;
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigBoard {
+    pub keys: HashMap<String, ConfigUser>,
+    pub cap: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rank_checkpoint: Option<RankCheckpoint>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<ConfigWebhook>,
+    // the structure the entries are kept in, the AVL tree if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<IndexKind>,
+    // overrides the compression in config.json for this board's saves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Compression>,
+    // overrides the save_interval in config.json, in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save_interval: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ConfigUser {
+    #[serde(flatten)]
+    pub access: ConfigAccess,
+    // other boards the key can name in requests, exactly or by a pattern with * in it
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub boards: HashMap<String, ConfigAccess>,
+    // the player the key reads with read_own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub player: Option<Key>,
+}
+
+// What a key can do on a board, as "scopes": ["read", "submit", ...]. Keys from before scopes
+// have "write" instead, which is kept while their scopes are still what it stood for.
+#[derive(Serialize, Deserialize)]
+pub struct ConfigAccess {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub write: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Scopes>,
+}
+
+impl ConfigAccess {
+    // read only if neither is set
+    fn scopes(&self) -> Scopes {
+        match self.scopes {
+            Some(scopes) => scopes,
+            None => Scopes::from_write(self.write.unwrap_or(false)),
+        }
+    }
+
+    fn from_scopes(scopes: Scopes) -> Self {
+        for write in [true, false] {
+            if scopes == Scopes::from_write(write) {
+                return Self {
+                    write: Some(write),
+                    scopes: None,
+                };
+            }
+        }
+        Self {
+            write: None,
+            scopes: Some(scopes),
+        }
+    }
+}
+
+impl ConfigUser {
+    fn to_user(&self, board: &String) -> User {
+        User {
+            board: board.clone(),
+            scopes: self.access.scopes(),
+            boards: self
+                .boards
+                .iter()
+                .map(|(pattern, access)| (pattern.clone(), access.scopes()))
+                .collect(),
+            player: self.player,
+        }
+    }
+
+    fn from_user(user: &User) -> Self {
+        Self {
+            access: ConfigAccess::from_scopes(user.scopes),
+            boards: user
+                .boards
+                .iter()
+                .map(|(pattern, scopes)| (pattern.clone(), ConfigAccess::from_scopes(*scopes)))
+                .collect(),
+            player: user.player,
+        }
+    }
+}
+
+#[derive(PartialEq, Serialize, Deserialize)]
+pub struct Config {
+    pub port: usize,
+    pub save_interval: u64,
+    pub lock_save: Option<bool>,
+    pub cache_len: f64,
+    // "always", "never" or {"interval": seconds}, every second if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub journal_fsync: Option<FsyncPolicy>,
+    // seconds between full saves, saves in between only write the entries that changed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compact_interval: Option<u64>,
+    // "none", "lz4" or {"zstd": level} for full saves, uncompressed if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compression: Option<Compression>,
+    // {"count": backups, "max_age": seconds} kept of each full save, none are kept if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backups: Option<BackupRetention>,
+    // "empty" or "read_only", how a board starts when its save can't be read, empty if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quarantine: Option<QuarantineMode>,
+    // the address to bind to, 0.0.0.0 if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    // the boards file and saves folder, relative to this file, beside the executable if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub boards: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub saves: Option<PathBuf>,
+    // seconds between checks of this file and the boards file for changes to reload, off if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watch_interval: Option<u64>,
+    // sent as x-admin-key to use the /admin API, which is off if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_key: Option<String>,
+}
+
+// The parts of config.json that a reload applies without a restart.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Settings {
+    pub save_interval: u64,
+    pub lock_save: bool,
+    pub cache_len: f64,
+    pub compact_interval: u64,
+    pub compression: Compression,
+    pub backups: Option<BackupRetention>,
+    pub quarantine_mode: QuarantineMode,
+    pub watch_interval: Option<u64>,
+}
+
+impl Settings {
+    fn from_config(json: &Config) -> Self {
+        Self {
+            save_interval: json.save_interval,
+            lock_save: json.lock_save.unwrap_or(false),
+            cache_len: json.cache_len,
+            compact_interval: json.compact_interval.unwrap_or(DEFAULT_COMPACT_INTERVAL),
+            compression: json.compression.unwrap_or_default(),
+            backups: json.backups,
+            quarantine_mode: json.quarantine.unwrap_or_default(),
+            watch_interval: json.watch_interval,
+        }
+    }
+}
+
+// an hour
+const DEFAULT_COMPACT_INTERVAL: u64 = 3600;
+
+pub type ServerBoard = Board<Key, Val, AnyIndex<Entry<Key, Val>>>;
+pub type SharedBoard = Arc<RwLock<ServerBoard>>;
+
+pub struct AppState {
+    // the registry lock is only held to look boards up, each board has its own lock
+    pub boards: RwLock<HashMap<String, SharedBoard>>,
+    pub api_keys: Mutex<HashMap<String, User>>,
+    pub webhooks: Mutex<HashMap<String, Vec<Webhook>>>,
+    pub port: usize,
+    pub settings: RwLock<Settings>,
+    // config.json as it was last read, to tell what a reload changes
+    pub config: Mutex<Config>,
+    pub config_path: PathBuf,
+    pub boards_file: Mutex<File>,
+    pub boards_path: PathBuf,
+    pub saves_path: PathBuf,
+    pub save_locker: Mutex<()>,
+    // held over a change to the boards or keys and its write of boards.json, and over a reload
+    // reading and applying the files, so neither sees the other half done
+    pub config_locker: Mutex<()>,
+    // bumped after each mutation of a board, for streaming subscribers
+    pub board_changes: Mutex<HashMap<String, watch::Sender<u64>>>,
+    // appended to while the board's write lock is held, so records keep the board's order
+    pub journals: RwLock<HashMap<String, Mutex<Journal>>>,
+    pub journal_fsync: FsyncPolicy,
+    // when each board was last saved in full, boards missing from it are due
+    pub compacted: Mutex<HashMap<String, Instant>>,
+    // boards set to compress differently from the rest
+    pub board_compression: Mutex<HashMap<String, Compression>>,
+    // boards saved on their own schedule instead of every save_interval
+    pub board_save_interval: Mutex<HashMap<String, u64>>,
+    // when each board was last saved, boards missing from it are due
+    pub saved: Mutex<HashMap<String, Instant>>,
+    // boards whose save is in quarantine that API keys can't change, they aren't saved either
+    pub read_only: Mutex<HashSet<String>>,
+}
+
+// Reads config.json, writing the default config into it first if it is empty.
+pub fn read_config(mut file: &std::fs::File) -> Config {
+    let content;
+
+    if file.metadata().unwrap().len() == 0 {
+        content = include_str!("../default_config.json").to_string();
+        let _ = file.write_all(content.as_bytes());
+    } else {
+        content = util::read_file(&file).expect("Failed to read config file content");
+    }
+
+    file.rewind().expect("Failed to read config file content");
+
+    serde_json::from_str::<Config>(content.as_str())
+        .expect("Invalid config file, delete to return to default config.")
+}
+
+impl AppState {
+    pub fn new(
+        json: Config,
+        config_path: &PathBuf,
+        mut boards_file: std::fs::File,
+        boards_path: &PathBuf,
+        saves_path: &PathBuf,
+    ) -> Self {
+        let board_content;
+
+        if boards_file.metadata().unwrap().len() == 0 {
+            board_content = include_str!("../default_boards.json").to_string();
+            let _ = boards_file.write_all(board_content.as_bytes());
+        } else {
+            board_content =
+                util::read_file(&boards_file).expect("Failed to read board file content");
+        }
+
+        boards_file
+            .rewind()
+            .expect("Failed to read board file content");
+
+        let board_json =
+            serde_json::from_str::<HashMap<String, ConfigBoard>>(&board_content.as_str())
+                .expect("Invalid boards file, delete to return to default config.");
+
+        let mut boards = HashMap::new();
+        let mut keys = HashMap::new();
+        let mut webhooks = HashMap::new();
+        let mut journals = HashMap::new();
+        let mut compacted = HashMap::new();
+        let mut board_compression = HashMap::new();
+        let mut board_save_interval = HashMap::new();
+        let mut saved = HashMap::new();
+        let mut read_only = HashSet::new();
+        let journal_fsync = json.journal_fsync.unwrap_or_default();
+        let quarantine_mode = json.quarantine.unwrap_or_default();
+
+        for (name, json_board) in board_json {
+            let mut board: ServerBoard = match load_save(saves_path, &name) {
+                Ok(Some(board)) => board,
+                Ok(None) => {
+                    let _ = writeln!(
+                        &mut io::stdout().lock(),
+                        "Failed to find .board file for board {name}. Using empty board."
+                    );
+                    Board::new()
+                }
+                Err(err) => {
+                    // a save that can't be moved out of the way must not be saved over
+                    let starts_read_only =
+                        quarantine_unreadable(saves_path, &name, &err, quarantine_mode)
+                            .unwrap_or_else(|err| {
+                                let _ = writeln!(
+                                    &mut io::stderr().lock(),
+                                    "{err}\nBoard {name} starts empty and read-only, its save is left in place."
+                                );
+                                true
+                            });
+                    if starts_read_only {
+                        read_only.insert(name.clone());
+                    }
+                    Board::new()
+                }
+            };
+
+            if let Some(cap) = json_board.cap {
+                board.set_size_cap(cap);
+            } else {
+                board.remove_size_cap();
+            }
+
+            board.set_index_kind(json_board.index.unwrap_or_default());
+            if let Some(journal) = replay_changes(&mut board, saves_path, &name, journal_fsync) {
+                journals.insert(name.clone(), Mutex::new(journal));
+            }
+            compacted.insert(name.clone(), Instant::now());
+            saved.insert(name.clone(), Instant::now());
+            if let Some(compression) = json_board.compression {
+                board_compression.insert(name.clone(), compression);
+            }
+            if let Some(interval) = json_board.save_interval {
+                board_save_interval.insert(name.clone(), interval);
+            }
+            board.set_rank_checkpoint(json_board.rank_checkpoint);
+            if json_board.rank_checkpoint.is_some() {
+                load_previous_ranks(&mut board, saves_path, &name);
+            }
+
+            if !json_board.webhooks.is_empty() {
+                let board_webhooks: Vec<Webhook> = json_board
+                    .webhooks
+                    .into_iter()
+                    .map(|config| Webhook::spawn(&name, config))
+                    .collect();
+                board.set_event_tracking(webhook::event_depth(&board_webhooks));
+                webhooks.insert(name.clone(), board_webhooks);
+            }
+
+            boards.insert(name.clone(), Arc::new(RwLock::new(board)));
+            for (key, user) in json_board.keys.iter() {
+                keys.insert(key.clone(), user.to_user(&name));
+            }
+        }
+
+        Self {
+            boards: RwLock::new(boards),
+            api_keys: Mutex::new(keys),
+            webhooks: Mutex::new(webhooks),
+            port: json.port,
+            settings: RwLock::new(Settings::from_config(&json)),
+            config: Mutex::new(json),
+            config_path: config_path.clone(),
+            boards_file: Mutex::new(boards_file),
+            boards_path: boards_path.clone(),
+            saves_path: saves_path.clone(),
+            save_locker: Mutex::new(()),
+            config_locker: Mutex::new(()),
+            board_changes: Mutex::new(HashMap::new()),
+            journals: RwLock::new(journals),
+            journal_fsync: journal_fsync,
+            compacted: Mutex::new(compacted),
+            board_compression: Mutex::new(board_compression),
+            board_save_interval: Mutex::new(board_save_interval),
+            saved: Mutex::new(saved),
+            read_only: Mutex::new(read_only),
+        }
+    }
+
+    fn write_boards_json(&self) {
+        let mut json: HashMap<String, ConfigBoard> = HashMap::new();
+        let users = self.api_keys.lock().unwrap();
+        let boards = self.boards.read().unwrap();
+        let webhooks = self.webhooks.lock().unwrap();
+        let board_compression = self.board_compression.lock().unwrap();
+        let board_save_interval = self.board_save_interval.lock().unwrap();
+        let webhook_configs = |name: &String| -> Vec<ConfigWebhook> {
+            match webhooks.get(name) {
+                Some(v) => v.iter().map(|hook| hook.config.clone()).collect(),
+                None => Vec::new(),
+            }
+        };
+
+        for (k, user) in users.iter() {
+            let board_name = user.board.clone();
+            let actual_board = boards.get(&board_name).map(|v| v.read().unwrap());
+            let cap = match &actual_board {
+                None => None,
+                Some(v) => v.get_size_cap(),
+            };
+            let index = actual_board.as_ref().and_then(|v| config_index(v));
+            let rank_checkpoint = actual_board.and_then(|v| v.get_rank_checkpoint());
+            if !json.contains_key(&board_name) {
+                let board = ConfigBoard {
+                    keys: HashMap::new(),
+                    cap: cap,
+                    rank_checkpoint: rank_checkpoint,
+                    webhooks: webhook_configs(&board_name),
+                    index: index,
+                    compression: board_compression.get(&board_name).copied(),
+                    save_interval: board_save_interval.get(&board_name).copied(),
+                };
+                json.insert(board_name.clone(), board);
+            }
+            let json_board = json.get_mut(&board_name).unwrap();
+            json_board.cap = cap;
+            json_board.rank_checkpoint = rank_checkpoint;
+            json_board.index = index;
+            json_board
+                .keys
+                .insert(k.to_string(), ConfigUser::from_user(user));
+        }
+
+        for (board_name, board) in boards.iter() {
+            if !json.contains_key(board_name) {
+                let board = board.read().unwrap();
+                let board = ConfigBoard {
+                    keys: HashMap::new(),
+                    cap: board.get_size_cap(),
+                    rank_checkpoint: board.get_rank_checkpoint(),
+                    webhooks: webhook_configs(board_name),
+                    index: config_index(&board),
+                    compression: board_compression.get(board_name).copied(),
+                    save_interval: board_save_interval.get(board_name).copied(),
+                };
+                json.insert(board_name.clone(), board);
+            }
+        }
+
+        let _ = drop(board_save_interval);
+        let _ = drop(board_compression);
+        let _ = drop(webhooks);
+        let _ = drop(boards);
+
+        let mut file = self
+            .boards_file
+            .lock()
+            .expect("Could not update the boards file.");
+        let _ = file.set_len(0);
+        file.write_all(serde_json::to_string_pretty(&json).unwrap().as_bytes())
+            .expect("Could not update the boards file.");
+        file.rewind().expect("Could not update the boards file.");
+    }
+
+    pub fn settings(&self) -> Settings {
+        *self.settings.read().unwrap()
+    }
+
+    pub fn get_board(&self, name: &String) -> Option<SharedBoard> {
+        return self.boards.read().unwrap().get(name).cloned();
+    }
+
+    // Err if a save for this name exists but can't be read.
+    pub fn create_board(&self, name: String) -> Result<bool, String> {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let created = self.load_board(name, None)?;
+        if created {
+            self.write_boards_json();
+        }
+        return Ok(created);
+    }
+
+    // Adds the board without listing it in boards.json. The cap is set before the journal is
+    // replayed, so the replayed entries keep to it.
+    fn load_board(&self, name: String, cap: Option<usize>) -> Result<bool, String> {
+        if self.boards.read().unwrap().contains_key(&name) {
+            return Ok(false);
+        }
+
+        // a save left behind by a deleted board with this name is picked up again
+        let mut read_only = false;
+        let mut board: ServerBoard = match load_save(&self.saves_path, &name) {
+            Ok(v) => v.unwrap_or_else(Board::new),
+            Err(err) => {
+                read_only = quarantine_unreadable(
+                    &self.saves_path,
+                    &name,
+                    &err,
+                    self.settings().quarantine_mode,
+                )?;
+                Board::new()
+            }
+        };
+
+        if let Some(cap) = cap {
+            board.set_size_cap(cap);
+        } else {
+            board.remove_size_cap();
+        }
+
+        let journal = replay_changes(&mut board, &self.saves_path, &name, self.journal_fsync);
+
+        let mut boards = self.boards.write().unwrap();
+        if boards.contains_key(&name) {
+            return Ok(false);
+        }
+        if let Some(journal) = journal {
+            self.journals
+                .write()
+                .unwrap()
+                .insert(name.clone(), Mutex::new(journal));
+        }
+        self.compacted
+            .lock()
+            .unwrap()
+            .insert(name.clone(), Instant::now());
+        self.saved
+            .lock()
+            .unwrap()
+            .insert(name.clone(), Instant::now());
+        if read_only {
+            self.read_only.lock().unwrap().insert(name.clone());
+        }
+        boards.insert(name, Arc::new(RwLock::new(board)));
+
+        return Ok(true);
+    }
+
+    pub fn subscribe_changes(&self, board: &String) -> watch::Receiver<u64> {
+        let mut changes = self.board_changes.lock().unwrap();
+        match changes.get(board) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = watch::channel(0);
+                changes.insert(board.clone(), sender);
+                receiver
+            }
+        }
+    }
+
+    pub fn notify_change(&self, board: &String) {
+        let mut changes = self.board_changes.lock().unwrap();
+        if let Some(sender) = changes.get(board) {
+            if sender.receiver_count() == 0 {
+                changes.remove(board);
+            } else {
+                sender.send_modify(|version| *version += 1);
+            }
+        }
+    }
+
+    pub fn is_compaction_due(&self, board: &String) -> bool {
+        match self.compacted.lock().unwrap().get(board) {
+            Some(time) => time.elapsed().as_secs() >= self.settings().compact_interval,
+            None => true,
+        }
+    }
+
+    // How full saves of the board are compressed.
+    pub fn board_compression(&self, board: &String) -> Compression {
+        match self.board_compression.lock().unwrap().get(board) {
+            Some(compression) => *compression,
+            None => self.settings().compression,
+        }
+    }
+
+    // Seconds between saves of the board.
+    pub fn board_save_interval(&self, board: &String) -> u64 {
+        match self.board_save_interval.lock().unwrap().get(board) {
+            Some(interval) => *interval,
+            None => self.settings().save_interval,
+        }
+    }
+
+    // The boards whose save interval has passed since they were last saved.
+    pub fn due_saves(&self) -> Vec<String> {
+        let names: Vec<String> = self.boards.read().unwrap().keys().cloned().collect();
+        let save_interval = self.settings().save_interval;
+        let saved = self.saved.lock().unwrap();
+        let board_save_interval = self.board_save_interval.lock().unwrap();
+        names
+            .into_iter()
+            .filter(|name| {
+                let interval = board_save_interval
+                    .get(name)
+                    .copied()
+                    .unwrap_or(save_interval);
+                match saved.get(name) {
+                    Some(time) => time.elapsed().as_secs() >= interval,
+                    None => true,
+                }
+            })
+            .collect()
+    }
+
+    // Puts boards with changes that aren't saved yet first, keeping the order otherwise.
+    pub fn dirty_first(&self, names: &mut [String]) {
+        names.sort_by_cached_key(|name| {
+            !self
+                .get_board(name)
+                .is_some_and(|board| board.read().unwrap().is_dirty())
+        });
+    }
+
+    pub fn set_saved(&self, board: &String) {
+        self.saved
+            .lock()
+            .unwrap()
+            .insert(board.clone(), Instant::now());
+    }
+
+    pub fn set_compacted(&self, board: &String) {
+        self.compacted
+            .lock()
+            .unwrap()
+            .insert(board.clone(), Instant::now());
+    }
+
+    // Call with the board's write lock held, right after the change.
+    pub fn journal(&self, board: &String, records: &[JournalRecord<Key, Val>]) {
+        let journals = self.journals.read().unwrap();
+        if let Some(journal) = journals.get(board)
+            && let Err(err) = journal.lock().unwrap().append_all(records)
+        {
+            let _ = writeln!(
+                &mut io::stderr().lock(),
+                "Failed to journal a change to board {board}, it will only be kept from the next save.\n{err}"
+            );
+        }
+    }
+
+    // Journals the entries split or trimmed off the board as removed, with its write lock held.
+    pub fn journal_removed(&self, board: &String, removed: &ServerBoard) {
+        if removed.get_size() == 0 {
+            return;
+        }
+        let records: Vec<JournalRecord<Key, Val>> = removed
+            .iter()
+            .map(|(_, entry)| JournalRecord::Remove(entry.key))
+            .collect();
+        self.journal(board, &records);
+    }
+
+    // Syncs the journals whose fsync interval has passed with records still unsynced.
+    pub fn sync_journals(&self) {
+        let journals = self.journals.read().unwrap();
+        for (board, journal) in journals.iter() {
+            if let Err(err) = journal.lock().unwrap().sync_due() {
+                let _ = writeln!(&mut io::stderr().lock(), "Journal of board {board}: {err}");
+            }
+        }
+    }
+
+    // Where the board's journal ends, taken with the board locked for a save.
+    pub fn journal_mark(&self, board: &String) -> Option<u64> {
+        let journals = self.journals.read().unwrap();
+        journals
+            .get(board)
+            .map(|journal| journal.lock().unwrap().mark())
+    }
+
+    // Drops what the finished save covers from the board's journal.
+    pub fn truncate_journal(&self, board: &String, mark: u64) {
+        let journals = self.journals.read().unwrap();
+        if let Some(journal) = journals.get(board)
+            && let Err(err) = journal.lock().unwrap().truncate_to(mark)
+        {
+            let _ = writeln!(
+                &mut io::stderr().lock(),
+                "Failed to truncate the journal of board {board}.\n{err}"
+            );
+        }
+    }
+
+    pub fn set_board_cap(&self, board: &String, cap: usize) -> bool {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let board = match self.get_board(board) {
+            Some(v) => v,
+            None => {
+                return false;
+            }
+        };
+        let mut board = board.write().unwrap();
+        board.set_size_cap(cap);
+        let _ = drop(board);
+        self.write_boards_json();
+        return true;
+    }
+
+    // Sets the cap and removes the entries beyond it under one write guard, so no update lands
+    // between the two. Returns how many entries were trimmed, None without such a board.
+    pub fn set_board_cap_trimmed(&self, board: &String, cap: usize) -> Option<usize> {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let shared_board = self.get_board(board)?;
+        let mut locked = shared_board.write().unwrap();
+        locked.set_size_cap(cap);
+        let removed = locked.trim_after_cap();
+        self.journal_removed(board, &removed);
+        let _ = drop(locked);
+        self.write_boards_json();
+        return Some(removed.get_size());
+    }
+
+    pub fn rem_board_cap(&self, board: &String) -> bool {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let board = match self.get_board(board) {
+            Some(v) => v,
+            None => {
+                return false;
+            }
+        };
+        let mut board = board.write().unwrap();
+        board.remove_size_cap();
+        let _ = drop(board);
+        self.write_boards_json();
+        return true;
+    }
+
+    pub fn set_board_rank_checkpoint(&self, board: &String, mode: Option<RankCheckpoint>) -> bool {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let board = match self.get_board(board) {
+            Some(v) => v,
+            None => {
+                return false;
+            }
+        };
+        let mut board = board.write().unwrap();
+        board.set_rank_checkpoint(mode);
+        let _ = drop(board);
+        self.write_boards_json();
+        return true;
+    }
+
+    pub fn set_board_index_kind(&self, board: &String, kind: IndexKind) -> bool {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let board = match self.get_board(board) {
+            Some(v) => v,
+            None => {
+                return false;
+            }
+        };
+        let mut board = board.write().unwrap();
+        board.set_index_kind(kind);
+        let _ = drop(board);
+        self.write_boards_json();
+        return true;
+    }
+
+    // Swaps the backup's entries into the live board and returns how many there are. The board
+    // keeps its settings, and its next save is a full one.
+    pub fn restore_board(&self, name: &String, backup: &Backup) -> Result<usize, String> {
+        let shared_board = match self.get_board(name) {
+            Some(v) => v,
+            None => return Err(format!("No board named {name}.")),
+        };
+        let (saved, _) = persist::read_entries_at(&backup.path, name)?;
+        let restored: ServerBoard = Board::from_saved(saved);
+
+        let mut board = shared_board.write().unwrap();
+        let size = self.swap_entries(name, &mut board, restored);
+        let _ = drop(board);
+
+        self.notify_change(name);
+        return Ok(size);
+    }
+
+    pub fn is_read_only(&self, board: &String) -> bool {
+        self.read_only.lock().unwrap().contains(board)
+    }
+
+    // Reads a quarantined save back into its board and returns how many entries the board has
+    // after. Entries changed since the board started are newer, so they win over the save's, but
+    // entries removed since come back. The files leave quarantine and the board takes writes again.
+    pub fn retry_quarantined(&self, quarantined: &Quarantined) -> Result<usize, String> {
+        let name = &quarantined.name;
+        let shared_board = match self.get_board(name) {
+            Some(v) => v,
+            None => return Err(format!("No board named {name}.")),
+        };
+
+        let mut restored: ServerBoard = match &quarantined.board {
+            Some(path) => Board::from_saved(persist::read_entries_at(path, name)?.0),
+            None => Board::new(),
+        };
+        if let Some(path) = &quarantined.delta {
+            persist::read_deltas_at(path, name, |delta| restored.apply_delta(delta))?;
+        }
+
+        let mut board = shared_board.write().unwrap();
+        for (_, entry) in board.iter() {
+            let _ = restored.set_entry(entry.clone());
+        }
+        let size = self.swap_entries(name, &mut board, restored);
+        let _ = drop(board);
+
+        self.read_only.lock().unwrap().remove(name);
+        self.notify_change(name);
+
+        persist::release_quarantined(&self.saves_path, quarantined)?;
+        return Ok(size);
+    }
+
+    // Puts the entries of another board in place of the board's own, journaled like any other
+    // change so a crash before the next save still keeps them. The board keeps its settings, and
+    // its next save is a full one.
+    fn swap_entries(&self, name: &String, board: &mut ServerBoard, entries: ServerBoard) -> usize {
+        board.replace_entries(entries);
+        let _ = board.trim_after_cap();
+
+        let mut records = vec![JournalRecord::Clear];
+        records.extend(
+            board
+                .iter()
+                .rev()
+                .map(|(_, entry)| JournalRecord::Update(entry.clone())),
+        );
+        self.journal(name, &records);
+        self.compacted.lock().unwrap().remove(name);
+
+        board.get_size()
+    }
+
+    pub fn delete_board(&self, name: &String) -> bool {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let mut users = self.api_keys.lock().unwrap();
+        users.retain(|_k, usr| -> bool { usr.board != *name });
+        // grants by pattern stay, they can still match other boards
+        for usr in users.values_mut() {
+            usr.boards.remove(name);
+        }
+        let _ = drop(users);
+
+        if !self.unload_board(name) {
+            return false;
+        }
+
+        let journal_path = persist::journal_path(&self.saves_path, name);
+        if journal_path.exists() {
+            let _ = std::fs::remove_file(journal_path);
+        }
+        let _ = persist::remove_deltas(&self.saves_path, name);
+
+        let save_path = self.saves_path.join(format!("{name}.board"));
+        if save_path.exists() {
+            let _ = std::fs::remove_file(save_path);
+        }
+
+        let ranks_path = self.saves_path.join(format!("{name}.ranks"));
+        if ranks_path.exists() {
+            let _ = std::fs::remove_file(ranks_path);
+        }
+        let _ = persist::remove_backups(&self.saves_path, name);
+
+        self.write_boards_json();
+        return true;
+    }
+
+    // Drops the board from memory and leaves its files, so adding it again picks them up.
+    fn unload_board(&self, name: &String) -> bool {
+        let mut boards = self.boards.write().unwrap();
+        if !boards.contains_key(name) {
+            return false;
+        }
+
+        let v = boards.remove(name);
+
+        let _ = drop(boards);
+        let _ = drop(v);
+
+        self.webhooks.lock().unwrap().remove(name);
+        // dropping the sender ends any open streams on the board
+        self.board_changes.lock().unwrap().remove(name);
+        if let Some(journal) = self.journals.write().unwrap().remove(name)
+            && let Err(err) = journal.into_inner().unwrap().sync()
+        {
+            let _ = writeln!(&mut io::stderr().lock(), "{err}");
+        }
+        self.compacted.lock().unwrap().remove(name);
+        self.board_compression.lock().unwrap().remove(name);
+        self.board_save_interval.lock().unwrap().remove(name);
+        self.saved.lock().unwrap().remove(name);
+        self.read_only.lock().unwrap().remove(name);
+        return true;
+    }
+
+    pub fn create_key(&self, api_key: String, board: String, scopes: Scopes) -> bool {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let mut users = self.api_keys.lock().unwrap();
+        if users.contains_key(&api_key) {
+            return false;
+        }
+        users.insert(
+            api_key,
+            User {
+                board: board,
+                scopes: scopes,
+                boards: HashMap::new(),
+                player: None,
+            },
+        );
+        let _ = drop(users);
+        self.write_boards_json();
+        return true;
+    }
+
+    pub fn delete_key(&self, api_key: &String) -> bool {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let mut users = self.api_keys.lock().unwrap();
+        if !users.contains_key(api_key) {
+            return false;
+        }
+        users.remove(api_key);
+        let _ = drop(users);
+        self.write_boards_json();
+        return true;
+    }
+
+    // Lets the key use another board, or a pattern of them, or stops it if scopes is None.
+    pub fn set_key_board_access(
+        &self,
+        api_key: &String,
+        board: &String,
+        scopes: Option<Scopes>,
+    ) -> bool {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let mut users = self.api_keys.lock().unwrap();
+        let user = match users.get_mut(api_key) {
+            Some(v) => v,
+            None => return false,
+        };
+        match scopes {
+            Some(scopes) => user.boards.insert(board.clone(), scopes),
+            None => user.boards.remove(board),
+        };
+        let _ = drop(users);
+        self.write_boards_json();
+        return true;
+    }
+
+    pub fn set_key_scopes(&self, api_key: &String, scopes: Scopes) -> bool {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let mut users = self.api_keys.lock().unwrap();
+        if !users.contains_key(api_key) {
+            return false;
+        }
+        users.get_mut(api_key).unwrap().scopes = scopes;
+        let _ = drop(users);
+        self.write_boards_json();
+        return true;
+    }
+
+    // Binds the key to the player it reads with read_own, or unbinds it with None.
+    pub fn set_key_player(&self, api_key: &String, player: Option<Key>) -> bool {
+        let _config_locker = self.config_locker.lock().unwrap();
+        let mut users = self.api_keys.lock().unwrap();
+        if !users.contains_key(api_key) {
+            return false;
+        }
+        users.get_mut(api_key).unwrap().player = player;
+        let _ = drop(users);
+        self.write_boards_json();
+        return true;
+    }
+
+    // Reads config.json and boards.json as they are now, Err with why if either can't be used.
+    // Hold config_locker until the files are applied.
+    pub fn read_config_files(&self) -> Result<(Config, HashMap<String, ConfigBoard>), String> {
+        let config_content = std::fs::read_to_string(&self.config_path)
+            .map_err(|err| format!("Failed to read the config file.\n{err}"))?;
+        let json = serde_json::from_str::<Config>(&config_content)
+            .map_err(|err| format!("Invalid config file, nothing was reloaded.\n{err}"))?;
+
+        // held so a write of boards.json isn't read halfway through
+        let boards_file = self.boards_file.lock().unwrap();
+        let board_content = std::fs::read_to_string(&self.boards_path)
+            .map_err(|err| format!("Failed to read the boards file.\n{err}"))?;
+        let _ = drop(boards_file);
+        let board_json = serde_json::from_str::<HashMap<String, ConfigBoard>>(&board_content)
+            .map_err(|err| format!("Invalid boards file, nothing was reloaded.\n{err}"))?;
+
+        let mut key_boards: HashMap<&String, &String> = HashMap::new();
+        for (name, json_board) in board_json.iter() {
+            if name.is_empty() {
+                return Err(
+                    "A board in the boards file has no name, nothing was reloaded.".to_string(),
+                );
+            }
+            if let Some(c) = invalid_name_char(name) {
+                return Err(format!(
+                    "Invalid character \"{c}\" in board name \"{name}\", nothing was reloaded."
+                ));
+            }
+            for (key, user) in json_board.keys.iter() {
+                if let Some(other) = key_boards.insert(key, name) {
+                    return Err(format!(
+                        "API key {key} is on both boards {other} and {name}, nothing was reloaded."
+                    ));
+                }
+                for pattern in user.boards.keys() {
+                    if let Some(c) = invalid_pattern_char(pattern) {
+                        return Err(format!(
+                            "Invalid character \"{c}\" in board pattern \"{pattern}\" of API key {key}, nothing was reloaded."
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok((json, board_json))
+    }
+
+    // Brings the running boards, keys and settings in line with the files read by
+    // read_config_files, and says what changed. Boards no longer listed are unloaded with their
+    // saves left in place, so save them first. Called with config_locker still held.
+    pub fn apply_config_files(
+        &self,
+        json: Config,
+        board_json: HashMap<String, ConfigBoard>,
+    ) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        let mut config = self.config.lock().unwrap();
+        for (field, changed, live) in [
+            (
+                "save_interval",
+                config.save_interval != json.save_interval,
+                true,
+            ),
+            ("lock_save", config.lock_save != json.lock_save, true),
+            ("cache_len", config.cache_len != json.cache_len, true),
+            (
+                "compact_interval",
+                config.compact_interval != json.compact_interval,
+                true,
+            ),
+            ("compression", config.compression != json.compression, true),
+            ("backups", config.backups != json.backups, true),
+            ("quarantine", config.quarantine != json.quarantine, true),
+            (
+                "watch_interval",
+                config.watch_interval != json.watch_interval,
+                true,
+            ),
+            ("admin_key", config.admin_key != json.admin_key, true),
+            ("port", config.port != json.port, false),
+            ("address", config.address != json.address, false),
+            ("boards", config.boards != json.boards, false),
+            ("saves", config.saves != json.saves, false),
+            (
+                "journal_fsync",
+                config.journal_fsync != json.journal_fsync,
+                false,
+            ),
+        ] {
+            if !changed {
+                continue;
+            }
+            changes.push(match live {
+                true => format!("Changed {field} in config.json."),
+                false => format!("Changed {field} in config.json, it applies after a restart."),
+            });
+        }
+        *self.settings.write().unwrap() = Settings::from_config(&json);
+        *config = json;
+        let _ = drop(config);
+
+        let names: Vec<String> = self.boards.read().unwrap().keys().cloned().collect();
+        for name in names {
+            if !board_json.contains_key(&name) && self.unload_board(&name) {
+                changes.push(format!("Removed board {name}, its saves are kept."));
+            }
+        }
+
+        let mut board_json: Vec<(String, ConfigBoard)> = board_json.into_iter().collect();
+        board_json.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut keys = HashMap::new();
+        for (name, json_board) in board_json {
+            let added = match self.get_board(&name) {
+                Some(_) => false,
+                None => match self.load_board(name.clone(), json_board.cap) {
+                    Ok(_) => true,
+                    Err(err) => {
+                        changes.push(format!(
+                            "Failed to add board {name}, its keys are left out.\n{err}"
+                        ));
+                        continue;
+                    }
+                },
+            };
+
+            let mut board_changes = Vec::new();
+            self.apply_board_config(&name, &json_board, &mut board_changes);
+            if added {
+                changes.push(format!("Added board {name}."));
+            } else {
+                changes.extend(board_changes);
+            }
+
+            for (key, user) in json_board.keys.iter() {
+                keys.insert(key.clone(), user.to_user(&name));
+            }
+        }
+
+        let mut users = self.api_keys.lock().unwrap();
+        let mut removed: Vec<&String> = users
+            .keys()
+            .filter(|key| !keys.contains_key(*key))
+            .collect();
+        removed.sort_unstable();
+        for key in removed {
+            changes.push(format!(
+                "Removed API key {key} from board {}.",
+                users[key].board
+            ));
+        }
+
+        let mut listed: Vec<(&String, &User)> = keys.iter().collect();
+        listed.sort_unstable_by_key(|(key, _)| *key);
+        for (key, user) in listed {
+            let access = user.scopes.names();
+            match users.get(key) {
+                None => {
+                    changes.push(format!(
+                        "Added API key {key} to board {}, with {access}.",
+                        user.board
+                    ));
+                }
+                Some(old) if old.board != user.board || old.scopes != user.scopes => {
+                    changes.push(format!(
+                        "API key {key} is now on board {}, with {access}.",
+                        user.board
+                    ));
+                }
+                Some(old) if old.boards != user.boards => {
+                    changes.push(format!("Changed the other boards API key {key} can use."));
+                }
+                Some(old) if old.player != user.player => {
+                    changes.push(match user.player {
+                        Some(player) => format!("API key {key} now reads player {player}."),
+                        None => format!("API key {key} is no longer bound to a player."),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+        *users = keys;
+        let _ = drop(users);
+
+        if let Err(err) = self.reopen_boards_file() {
+            changes.push(err);
+        }
+        return changes;
+    }
+
+    // Sets the board up as its entry in boards.json says.
+    fn apply_board_config(
+        &self,
+        name: &String,
+        json_board: &ConfigBoard,
+        changes: &mut Vec<String>,
+    ) {
+        let shared_board = match self.get_board(name) {
+            Some(v) => v,
+            None => return,
+        };
+        let mut board = shared_board.write().unwrap();
+
+        if board.get_size_cap() != json_board.cap {
+            match json_board.cap {
+                Some(cap) => {
+                    board.set_size_cap(cap);
+                    changes.push(format!("Set the size cap of board {name} to {cap}."));
+                    if board.get_size() > cap {
+                        changes.push(format!(
+                            "Board {name} has {} entries beyond its cap, use 'trim' to remove them.",
+                            board.get_size() - cap
+                        ));
+                    }
+                }
+                None => {
+                    board.remove_size_cap();
+                    changes.push(format!("Removed the size cap of board {name}."));
+                }
+            }
+        }
+
+        let index = json_board.index.unwrap_or_default();
+        if board.get_index_kind() != index {
+            board.set_index_kind(index);
+            changes.push(format!("Changed the index of board {name}."));
+        }
+
+        if board.get_rank_checkpoint() != json_board.rank_checkpoint {
+            board.set_rank_checkpoint(json_board.rank_checkpoint);
+            changes.push(format!("Changed the rank tracking of board {name}."));
+        }
+
+        let mut webhooks = self.webhooks.lock().unwrap();
+        let current: Vec<&ConfigWebhook> = match webhooks.get(name) {
+            Some(v) => v.iter().map(|hook| &hook.config).collect(),
+            None => Vec::new(),
+        };
+        if serde_json::to_value(&current).ok() != serde_json::to_value(&json_board.webhooks).ok() {
+            let board_webhooks: Vec<Webhook> = json_board
+                .webhooks
+                .iter()
+                .map(|config| Webhook::spawn(name, config.clone()))
+                .collect();
+            board.set_event_tracking(webhook::event_depth(&board_webhooks));
+            if board_webhooks.is_empty() {
+                webhooks.remove(name);
+            } else {
+                webhooks.insert(name.clone(), board_webhooks);
+            }
+            changes.push(format!("Changed the webhooks of board {name}."));
+        }
+        let _ = drop(webhooks);
+        let _ = drop(board);
+
+        let mut board_compression = self.board_compression.lock().unwrap();
+        if board_compression.get(name).copied() != json_board.compression {
+            match json_board.compression {
+                Some(compression) => board_compression.insert(name.clone(), compression),
+                None => board_compression.remove(name),
+            };
+            changes.push(format!("Changed the compression of board {name}."));
+        }
+        let _ = drop(board_compression);
+
+        let mut board_save_interval = self.board_save_interval.lock().unwrap();
+        if board_save_interval.get(name).copied() != json_board.save_interval {
+            match json_board.save_interval {
+                Some(interval) => board_save_interval.insert(name.clone(), interval),
+                None => board_save_interval.remove(name),
+            };
+            changes.push(format!("Changed the save interval of board {name}."));
+        }
+    }
+
+    // Editors often save by writing a new file over the old one, which would leave boards.json
+    // written to through a file that is no longer there. Takes the new file over if so.
+    #[cfg(unix)]
+    fn reopen_boards_file(&self) -> Result<(), String> {
+        use fs2::FileExt;
+        use std::os::unix::fs::MetadataExt;
+
+        let mut file = self.boards_file.lock().unwrap();
+        let replaced = match (std::fs::metadata(&self.boards_path), file.metadata()) {
+            (Ok(on_disk), Ok(open)) => on_disk.ino() != open.ino() || on_disk.dev() != open.dev(),
+            _ => false,
+        };
+        if !replaced {
+            return Ok(());
+        }
+
+        let new_file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.boards_path)
+            .and_then(|new_file| new_file.try_lock_exclusive().map(|_| new_file))
+            .map_err(|err| format!("Failed to open the new boards file, changes made from here on are not written to it.\n{err}"))?;
+        *file = new_file;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn reopen_boards_file(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+// The first character not allowed in board patterns, which are board names with * in them.
+pub fn invalid_pattern_char(pattern: &str) -> Option<char> {
+    invalid_name_char(&pattern.replace('*', ""))
+}
+
+// The first character not allowed in board names, which are also file names in saves.
+pub fn invalid_name_char(name: &str) -> Option<char> {
+    name.chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '_' || *c == '-' || *c == '.'))
+}
+
+// Only boards that don't use the default index list it in boards.json.
+fn config_index(board: &ServerBoard) -> Option<IndexKind> {
+    Some(board.get_index_kind()).filter(|kind| *kind != IndexKind::default())
+}
+
+// Reads the board's save and the deltas saved on top of it, None if it has neither.
+fn load_save(saves_path: &PathBuf, name: &String) -> Result<Option<ServerBoard>, String> {
+    let mut loaded = load_saved_board(saves_path, name)?;
+    if loaded.is_none() && !persist::delta_path(saves_path, name).exists() {
+        return Ok(None);
+    }
+
+    let board = loaded.get_or_insert_with(Board::new);
+    let count = persist::load_deltas(saves_path, name, |delta| board.apply_delta(delta))?;
+    if count > 0 {
+        let _ = writeln!(
+            &mut io::stdout().lock(),
+            "Applied {count} saved deltas onto board {name}."
+        );
+    }
+    // the deltas are already saved, only journaled changes still need saving
+    let _ = board.take_delta();
+
+    Ok(loaded)
+}
+
+// Moves a save that couldn't be read into quarantine, so the board can start empty without it.
+// Ok(true) if the board should start read-only, Err if the save couldn't be moved.
+fn quarantine_unreadable(
+    saves_path: &PathBuf,
+    name: &String,
+    err: &String,
+    mode: QuarantineMode,
+) -> Result<bool, String> {
+    let quarantined = persist::quarantine_save(saves_path, name, err)
+        .map_err(|move_err| format!("{err}\n{move_err}"))?;
+
+    let read_only = mode == QuarantineMode::ReadOnly;
+    let _ = writeln!(
+        &mut io::stderr().lock(),
+        "{err}\nMoved the save of board {name} to quarantine as {}, it starts empty{}. Use 'retry {name} {}' to load it again.",
+        quarantined.id,
+        if read_only { " and read-only" } else { "" },
+        quarantined.id
+    );
+    Ok(read_only)
+}
+
+// Applies the changes journaled since the last save, and opens the journal to record new ones. A
+// board whose journal can't be opened still runs, its changes are just only kept by saves.
+fn replay_changes(
+    board: &mut ServerBoard,
+    saves_path: &PathBuf,
+    name: &String,
+    policy: FsyncPolicy,
+) -> Option<Journal> {
+    match persist::replay_journal(saves_path, name, |record| record.apply(board)) {
+        Ok(0) => {}
+        Ok(count) => {
+            let _ = writeln!(
+                &mut io::stdout().lock(),
+                "Replayed {count} journaled changes onto board {name}."
+            );
+        }
+        Err(err) => {
+            let _ = writeln!(&mut io::stderr().lock(), "{err}");
+        }
+    }
+
+    match Journal::open(saves_path, name, policy) {
+        Ok(journal) => Some(journal),
+        Err(err) => {
+            let _ = writeln!(
+                &mut io::stderr().lock(),
+                "{err}\nChanges to board {name} will only be kept by saves."
+            );
+            None
+        }
+    }
+}
+
+// The previous ranks are kept beside the .board file; losing them only resets movement, so failures aren't fatal.
+fn load_previous_ranks(board: &mut ServerBoard, saves_path: &PathBuf, name: &String) {
+    match persist::load_previous_ranks(saves_path, name) {
+        Ok(Some(ranks)) => board.set_previous_ranks(ranks),
+        Ok(None) => {}
+        Err(err) => {
+            let _ = writeln!(
+                &mut io::stderr().lock(),
+                "Failed to read previous ranks for board {name}, movement will restart from the next checkpoint.\n{err}"
+            );
+        }
+    }
+}
+
+#[cfg(feature = "indicatif")]
+fn load_saved_board(
+    saves_path: &PathBuf,
+    name: &String,
+) -> Result<Option<ServerBoard>, String> {
+    if !persist::board_path(saves_path, name).exists()
+        && !persist::part_path(saves_path, name).exists()
+    {
+        return Ok(None);
+    }
+
+    let mut bar = None;
+
+    if let Ok(style) = ProgressStyle::default_spinner().template("{spinner:.green} {msg}") {
+        let new_bar = ProgressBar::new_spinner();
+        new_bar.set_style(style);
+        new_bar.set_message(format!("Preliminary read from {name}.board..."));
+        bar = Some(new_bar);
+    } else {
+        let _ = writeln!(
+            &mut io::stdout().lock(),
+            "Preliminary read from {name}.board..."
+        );
+    }
+
+    let saved: SavedEntries<Key, Val> = match persist::load_entries(saves_path, name)? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+
+    if let Some(b) = bar {
+        b.finish_with_message(format!("Read from {name}.board"));
+    }
+
+    bar = None;
+    if let Ok(style) = ProgressStyle::default_bar()
+        .template("{spinner:.green} {msg}\n[{wide_bar:.cyan/blue}] {pos}/{len} ({eta})")
+    {
+        let new_bar = ProgressBar::new(saved.0.len() as u64);
+        new_bar.set_style(style);
+        new_bar.set_message(format!("Constructing board \"{name}\"..."));
+        bar = Some(new_bar);
+    }
+
+    if let Some(b) = bar {
+        let board = Board::from_saved_prog(saved, |amount| {
+            b.inc(amount as u64);
+        });
+
+        b.finish_with_message(format!("Constructed tree \"{name}\"."));
+        return Ok(Some(board));
+    }
+
+    Ok(Some(Board::from_saved(saved)))
+}
+
+#[cfg(not(feature = "indicatif"))]
+fn load_saved_board(
+    saves_path: &PathBuf,
+    name: &String,
+) -> Result<Option<ServerBoard>, String> {
+    let _ = writeln!(&mut io::stdout().lock(), "Reading {name}.board...");
+    persist::load_board(saves_path, name)
+}
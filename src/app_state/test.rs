@@ -0,0 +1,352 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+
+use super::*;
+
+pub(crate) const TEST_CONFIG: &str =
+    r#"{"port": 0, "save_interval": 600, "lock_save": false, "cache_len": 5}"#;
+
+// A state over a fresh folder holding the config.json and boards.json given, returned with the
+// folder.
+pub(crate) fn temp_state(name: &str, config: &str, boards: &str) -> (AppState, PathBuf) {
+    let dir = env::temp_dir().join(format!("leaderboard_state_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("saves")).unwrap();
+
+    let config_path = dir.join("config.json");
+    let boards_path = dir.join("boards.json");
+    fs::write(&config_path, config).unwrap();
+    fs::write(&boards_path, boards).unwrap();
+
+    let config = read_config(&File::open(&config_path).unwrap());
+    let boards_file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&boards_path)
+        .unwrap();
+    let state = AppState::new(
+        config,
+        &config_path,
+        boards_file,
+        &boards_path,
+        &dir.join("saves"),
+    );
+    (state, dir)
+}
+
+// Updates an entry the way a request does, waking the streams on the board.
+pub(crate) fn update(state: &AppState, board: &str, key: Key, points: Val) {
+    let board = board.to_string();
+    let _ = state
+        .get_board(&board)
+        .unwrap()
+        .write()
+        .unwrap()
+        .update_entry(key, points);
+    state.notify_change(&board);
+}
+
+fn sorted(mut names: Vec<String>) -> Vec<String> {
+    names.sort();
+    names
+}
+
+#[test]
+fn test_due_saves() {
+    let (state, _) = temp_state(
+        "due_saves",
+        TEST_CONFIG,
+        r#"{"a": {"keys": {}, "cap": null}, "b": {"keys": {}, "cap": null, "save_interval": 0}}"#,
+    );
+
+    // b overrides the 600 seconds of config.json
+    assert_eq!(state.board_save_interval(&"b".to_string()), 0);
+    assert_eq!(state.due_saves(), vec!["b".to_string()]);
+
+    state.saved.lock().unwrap().insert(
+        "a".to_string(),
+        Instant::now() - std::time::Duration::from_secs(600),
+    );
+    assert_eq!(sorted(state.due_saves()), vec!["a", "b"]);
+
+    state.set_saved(&"a".to_string());
+    assert_eq!(state.due_saves(), vec!["b".to_string()]);
+
+    // a board that was never saved is due
+    state.saved.lock().unwrap().remove("a");
+    assert_eq!(sorted(state.due_saves()), vec!["a", "b"]);
+}
+
+#[test]
+fn test_dirty_first() {
+    let (state, _) = temp_state(
+        "dirty_first",
+        TEST_CONFIG,
+        r#"{"a": {"keys": {}, "cap": null}, "b": {"keys": {}, "cap": null}, "c": {"keys": {}, "cap": null}}"#,
+    );
+    update(&state, "c", 1, 1.0);
+    update(&state, "b", 1, 1.0);
+
+    let mut names = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    state.dirty_first(&mut names);
+    assert_eq!(names, vec!["b", "c", "a"]);
+}
+
+#[test]
+fn test_save_failure() {
+    let (state, dir) = temp_state(
+        "save_failure",
+        r#"{"port": 0, "save_interval": 0, "lock_save": false, "cache_len": 5}"#,
+        r#"{"a": {"keys": {}, "cap": null}, "b": {"keys": {}, "cap": null}}"#,
+    );
+    let state = Arc::new(state);
+    let saves = dir.join("saves");
+    update(&state, "a", 1, 1.0);
+    update(&state, "b", 1, 1.0);
+
+    // a folder where a's deltas go makes its save fail
+    fs::create_dir_all(persist::delta_path(&saves, "a")).unwrap();
+    state.saved.lock().unwrap().clear();
+    let failed =
+        crate::backend::save_boards(&state, &saves, vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(failed, vec!["a".to_string()]);
+
+    // b is still saved after a fails, and both wait for their interval again
+    assert!(
+        state
+            .get_board(&"a".to_string())
+            .unwrap()
+            .read()
+            .unwrap()
+            .is_dirty()
+    );
+    assert!(
+        !state
+            .get_board(&"b".to_string())
+            .unwrap()
+            .read()
+            .unwrap()
+            .is_dirty()
+    );
+    assert!(persist::delta_path(&saves, "b").is_file());
+    assert!(state.saved.lock().unwrap().contains_key("a"));
+    assert!(state.saved.lock().unwrap().contains_key("b"));
+}
+
+fn write_files(dir: &PathBuf, config: &str, boards: &str) {
+    fs::write(dir.join("config.json"), config).unwrap();
+    fs::write(dir.join("boards.json"), boards).unwrap();
+}
+
+#[test]
+fn test_read_config_files() {
+    let boards = r#"{"a": {"keys": {"k1": {"write": true}}, "cap": null}}"#;
+    let (state, dir) = temp_state("read_config_files", TEST_CONFIG, boards);
+    assert!(state.read_config_files().is_ok());
+
+    for (config, boards, err) in [
+        ("{", boards, "Invalid config file"),
+        (TEST_CONFIG, r#"{"a": {"keys": {}}"#, "Invalid boards file"),
+        (
+            TEST_CONFIG,
+            r#"{"": {"keys": {}, "cap": null}}"#,
+            "has no name",
+        ),
+        (
+            TEST_CONFIG,
+            r#"{"a/b": {"keys": {}, "cap": null}}"#,
+            "Invalid character \"/\" in board name",
+        ),
+        (
+            TEST_CONFIG,
+            r#"{"a": {"keys": {"k1": {}}, "cap": null}, "b": {"keys": {"k1": {}}, "cap": null}}"#,
+            "API key k1 is on both boards",
+        ),
+        (
+            TEST_CONFIG,
+            r#"{"a": {"keys": {"k1": {"boards": {"game *": {}}}}, "cap": null}}"#,
+            "Invalid character \" \" in board pattern \"game *\"",
+        ),
+        (
+            TEST_CONFIG,
+            r#"{"a": {"keys": {"k1": {"scopes": ["write"]}}, "cap": null}}"#,
+            "Invalid boards file",
+        ),
+    ] {
+        write_files(&dir, config, boards);
+        let found = state.read_config_files().err().unwrap();
+        assert!(found.contains(err), "{found}");
+        assert!(found.contains("nothing was reloaded"), "{found}");
+    }
+}
+
+#[test]
+fn test_apply_config_files() {
+    let (state, dir) = temp_state(
+        "apply_config_files",
+        TEST_CONFIG,
+        r#"{"a": {"keys": {"k1": {"write": true}}, "cap": null}}"#,
+    );
+
+    write_files(
+        &dir,
+        r#"{"port": 1, "save_interval": 60, "lock_save": false, "cache_len": 5}"#,
+        r#"{
+            "a": {"keys": {"k1": {"scopes": ["read"]}}, "cap": 10},
+            "b": {"keys": {"k2": {"scopes": ["read", "submit"]}}, "cap": null}
+        }"#,
+    );
+    let (json, board_json) = state.read_config_files().unwrap();
+    let changes = state.apply_config_files(json, board_json);
+    assert_eq!(
+        changes,
+        vec![
+            "Changed save_interval in config.json.",
+            "Changed port in config.json, it applies after a restart.",
+            "Set the size cap of board a to 10.",
+            "Added board b.",
+            "API key k1 is now on board a, with read.",
+            "Added API key k2 to board b, with read,submit.",
+        ]
+    );
+    assert_eq!(state.settings().save_interval, 60);
+    assert!(state.get_board(&"b".to_string()).is_some());
+    assert_eq!(
+        state.api_keys.lock().unwrap()["k2"].scopes,
+        Scopes::parse("read,submit").unwrap()
+    );
+
+    // the same files again change nothing
+    let (json, board_json) = state.read_config_files().unwrap();
+    assert!(state.apply_config_files(json, board_json).is_empty());
+
+    write_files(
+        &dir,
+        r#"{"port": 1, "save_interval": 60, "lock_save": false, "cache_len": 5}"#,
+        r#"{"b": {"keys": {"k2": {"scopes": ["read", "submit"], "boards": {"a*": {}}}}, "cap": null}}"#,
+    );
+    let (json, board_json) = state.read_config_files().unwrap();
+    let changes = state.apply_config_files(json, board_json);
+    assert_eq!(
+        changes,
+        vec![
+            "Removed board a, its saves are kept.",
+            "Removed API key k1 from board a.",
+            "Changed the other boards API key k2 can use.",
+        ]
+    );
+    assert!(state.get_board(&"a".to_string()).is_none());
+    assert!(!state.api_keys.lock().unwrap().contains_key("k1"));
+}
+
+#[test]
+fn test_add_board_cap() {
+    let boards = r#"{"a": {"keys": {}, "cap": null}}"#;
+    let (state, dir) = temp_state("add_board_cap", TEST_CONFIG, boards);
+
+    let name = "a".to_string();
+    for key in 0..5 {
+        let entry = Entry {
+            key: key,
+            points: key as Val,
+            timestamp: 0.0,
+        };
+        let _ = state
+            .get_board(&name)
+            .unwrap()
+            .write()
+            .unwrap()
+            .set_entry(entry.clone());
+        state.journal(&name, &[JournalRecord::Update(entry)]);
+    }
+
+    write_files(&dir, TEST_CONFIG, "{}");
+    let (json, board_json) = state.read_config_files().unwrap();
+    let _ = state.apply_config_files(json, board_json);
+
+    // the journal is replayed onto the board with its cap already set
+    write_files(&dir, TEST_CONFIG, r#"{"a": {"keys": {}, "cap": 2}}"#);
+    let (json, board_json) = state.read_config_files().unwrap();
+    let changes = state.apply_config_files(json, board_json);
+    assert_eq!(changes, vec!["Added board a."]);
+    let board = state.get_board(&name).unwrap();
+    assert_eq!(board.read().unwrap().get_size(), 2);
+    assert!(board.read().unwrap().get_entry(&4).is_some());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_reopen_boards_file() {
+    let boards = r#"{"a": {"keys": {"k1": {"write": true}}, "cap": null}}"#;
+    let (state, dir) = temp_state("reopen_boards_file", TEST_CONFIG, boards);
+
+    // saved the way editors do, as a new file renamed over the old one
+    fs::write(dir.join("boards.json.new"), boards).unwrap();
+    fs::rename(dir.join("boards.json.new"), dir.join("boards.json")).unwrap();
+
+    let (json, board_json) = state.read_config_files().unwrap();
+    assert!(state.apply_config_files(json, board_json).is_empty());
+
+    assert!(state.create_key("k2".to_string(), "a".to_string(), Scopes::from_write(false)));
+    let on_disk = fs::read_to_string(dir.join("boards.json")).unwrap();
+    assert!(on_disk.contains("\"k2\""));
+}
+
+#[test]
+fn test_delete_board_grants() {
+    let (state, dir) = temp_state(
+        "delete_board_grants",
+        TEST_CONFIG,
+        r#"{
+            "a": {"keys": {"k1": {"write": true}}, "cap": null},
+            "b": {"keys": {"k2": {"write": false, "boards": {"a": {"write": true}, "a*": {"write": false}}}}, "cap": null}
+        }"#,
+    );
+
+    assert!(state.delete_board(&"a".to_string()));
+
+    // keys of the board go with it, and grants naming it are taken back from the rest
+    let users = state.api_keys.lock().unwrap();
+    assert!(!users.contains_key("k1"));
+    let k2 = users.get("k2").unwrap();
+    assert!(!k2.boards.contains_key("a"));
+    assert!(k2.boards.contains_key("a*"));
+    let _ = drop(users);
+
+    let on_disk = fs::read_to_string(dir.join("boards.json")).unwrap();
+    assert!(!on_disk.contains("\"a\":"));
+    assert!(on_disk.contains("\"a*\""));
+
+    // a board made again under the name only gets what the pattern gives
+    assert_eq!(state.create_board("a".to_string()), Ok(true));
+    let users = state.api_keys.lock().unwrap();
+    assert_eq!(
+        users.get("k2").unwrap().access("a"),
+        Some(Scopes::from_write(false))
+    );
+    let _ = drop(users);
+}
+
+#[test]
+fn test_key_player() {
+    let boards = r#"{"a": {"keys": {"k1": {"scopes": ["read_own"]}}, "cap": null}}"#;
+    let (state, dir) = temp_state("key_player", TEST_CONFIG, boards);
+
+    assert!(state.set_key_player(&"k1".to_string(), Some(7)));
+    assert!(!state.set_key_player(&"k2".to_string(), Some(7)));
+    let on_disk: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(dir.join("boards.json")).unwrap()).unwrap();
+    assert_eq!(on_disk["a"]["keys"]["k1"]["player"], 7);
+
+    // read back as it was written, and a reload names the change
+    let (json, board_json) = state.read_config_files().unwrap();
+    assert!(state.apply_config_files(json, board_json).is_empty());
+
+    write_files(&dir, TEST_CONFIG, boards);
+    let (json, board_json) = state.read_config_files().unwrap();
+    assert_eq!(
+        state.apply_config_files(json, board_json),
+        vec!["API key k1 is no longer bound to a player."]
+    );
+    assert_eq!(state.api_keys.lock().unwrap()["k1"].player, None);
+}
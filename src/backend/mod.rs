@@ -0,0 +1,1341 @@
+use rocket::State;
+use rocket::http::uri::Origin;
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome, Request};
+use rocket::response::status::Custom;
+use rocket::response::{self, Responder};
+use rocket::tokio;
+use serde::ser::SerializeTuple;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::app_state::{AppState, ServerBoard, SharedBoard};
+use crate::board::{BoardEvent, Entry, RankCheckpoint, RankMovement, SavedEntries};
+use crate::persist::JournalRecord;
+use crate::scope::{Scope, Scopes};
+use crate::transfer::{self, ConflictPolicy, ImportRow, TransferFormat};
+use crate::{Key, Val, persist};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Clone)]
+pub struct User {
+    // the board requests go to when they don't name one
+    pub board: String,
+    pub scopes: Scopes,
+    // other boards the key can name, exactly or by a pattern with * in it, and what it can do
+    // on them
+    pub boards: HashMap<String, Scopes>,
+    // the one player a key with read_own but not read can look up, on any board it can use
+    pub player: Option<Key>,
+}
+
+impl User {
+    // The key's scopes on the board, None if it can't use it at all. An exact name wins over
+    // patterns, and the longest pattern that matches wins over the rest.
+    pub fn access(&self, board: &str) -> Option<Scopes> {
+        if self.board == board {
+            return Some(self.scopes);
+        }
+        if let Some(scopes) = self.boards.get(board) {
+            return Some(*scopes);
+        }
+        self.boards
+            .iter()
+            .filter(|(pattern, _)| pattern.contains('*') && matches_pattern(pattern, board))
+            .max_by_key(|(pattern, _)| pattern.len())
+            .map(|(_, scopes)| *scopes)
+    }
+
+    // The key as it acts on the board, None if it can't use it.
+    pub fn on_board(&self, board: &str) -> Option<User> {
+        let scopes = self.access(board)?;
+        Some(User {
+            board: board.to_string(),
+            scopes: scopes,
+            boards: self.boards.clone(),
+            player: self.player,
+        })
+    }
+}
+
+// Whether the name fits the pattern, where each * stands for any run of characters.
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let mut rest = match name.strip_prefix(parts.next().unwrap_or("")) {
+        Some(v) => v,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    let (last, middle) = match parts.split_last() {
+        Some(v) => v,
+        None => return rest.is_empty(),
+    };
+
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+// The board a request names in its path as /boards/{name}/..., set by route_board_path.
+pub struct PathBoard(pub Option<String>);
+
+// Sends /boards/{name}/... on to the route the rest of the path names, keeping the board for the
+// API key guard. Every route can be reached for any board this way.
+pub fn route_board_path(req: &mut Request<'_>) {
+    let path = req.uri().path().as_str();
+    let (board, rest) = match path
+        .strip_prefix("/boards/")
+        .and_then(|rest| rest.split_once('/'))
+    {
+        Some(v) => v,
+        None => return,
+    };
+    if board.is_empty() {
+        return;
+    }
+
+    let board = board.to_string();
+    let mut uri = format!("/{rest}");
+    if let Some(query) = req.uri().query() {
+        uri += "?";
+        uri += query.as_str();
+    }
+    if let Ok(origin) = Origin::parse_owned(uri) {
+        req.set_uri(origin);
+        req.local_cache(|| PathBoard(Some(board)));
+    }
+}
+
+pub struct Interaction<'r> {
+    pub user: User,
+    pub state: &'r rocket::State<Arc<AppState>>,
+}
+
+#[derive(Debug)]
+pub enum ApiKeyError {
+    Missing,
+    Invalid,
+    // the key can't use the board the request names
+    NoAccess,
+    UnknownBoard,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Interaction<'r> {
+    type Error = ApiKeyError;
+
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        let state = req.guard::<&State<Arc<AppState>>>().await;
+        if let Some(state) = state.succeeded() {
+            let keys = state.api_keys.lock().unwrap();
+
+            return match req.headers().get_one("x-api-key") {
+                None => Outcome::Error((Status::BadRequest, ApiKeyError::Missing)),
+                Some(key) if keys.contains_key(key) => {
+                    let key_user = keys.get(key).unwrap();
+                    let user = match &req.local_cache(|| PathBoard(None)).0 {
+                        None => key_user.clone(),
+                        Some(board) => match key_user.on_board(board) {
+                            Some(v) => v,
+                            None => {
+                                return Outcome::Error((Status::Forbidden, ApiKeyError::NoAccess));
+                            }
+                        },
+                    };
+                    // patterns can match boards that don't exist
+                    if state.get_board(&user.board).is_none() {
+                        return Outcome::Error((Status::NotFound, ApiKeyError::UnknownBoard));
+                    }
+                    Outcome::Success(Interaction {
+                        user: user,
+                        state: state,
+                    })
+                }
+                Some(_) => Outcome::Error((Status::Unauthorized, ApiKeyError::Invalid)),
+            };
+        }
+
+        return Outcome::Error((Status::InternalServerError, ApiKeyError::Invalid));
+    }
+}
+
+// An error status, with a JSON body saying why when the status alone doesn't.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: Status,
+    pub body: Option<String>,
+}
+
+impl From<Status> for ApiError {
+    fn from(status: Status) -> Self {
+        ApiError {
+            status: status,
+            body: None,
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        match self.body {
+            Some(body) => Custom(self.status, (ContentType::JSON, body)).respond_to(req),
+            None => Err(self.status),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Denied<'a> {
+    code: i64,
+    message: String,
+    board: &'a String,
+    // the scope the request needs, missing if the board is read-only instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    missing_scope: Option<Scope>,
+    scopes: Scopes,
+}
+
+fn denied(interaction: &Interaction, message: String, missing: Option<Scope>) -> ApiError {
+    ApiError {
+        status: Status::Forbidden,
+        body: Some(
+            serde_json::to_string(&Denied {
+                code: -1,
+                message: message,
+                board: &interaction.user.board,
+                missing_scope: missing,
+                scopes: interaction.user.scopes,
+            })
+            .unwrap(),
+        ),
+    }
+}
+
+// Err with a 403 naming the scope if the key doesn't have it on the board.
+pub fn require_scope(interaction: &Interaction, scope: Scope) -> Result<(), ApiError> {
+    if interaction.user.scopes.allows(scope) {
+        return Ok(());
+    }
+    Err(denied(
+        interaction,
+        format!(
+            "This API key needs the {} scope on board {}, it has {}.",
+            scope.name(),
+            interaction.user.board,
+            interaction.user.scopes.names()
+        ),
+        Some(scope),
+    ))
+}
+
+// The same, for requests that change the board, which a board waiting on its quarantined save
+// doesn't take.
+pub fn require_change(interaction: &Interaction, scope: Scope) -> Result<(), ApiError> {
+    require_scope(interaction, scope)?;
+    if interaction.state.is_read_only(&interaction.user.board) {
+        return Err(denied(
+            interaction,
+            format!(
+                "Board {} is read-only until its quarantined save is retried.",
+                interaction.user.board
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+// Err with a 403 unless the key can read the player, with read or with read_own while bound to
+// them.
+pub fn require_player(interaction: &Interaction, id: Key) -> Result<(), ApiError> {
+    if interaction.user.scopes.allows(Scope::Read) || interaction.user.player == Some(id) {
+        return Ok(());
+    }
+    let message = match interaction.user.player {
+        Some(own) => format!(
+            "This API key can only read player {own} on board {}, other players need the read scope.",
+            interaction.user.board
+        ),
+        None => format!(
+            "This API key isn't bound to a player, it needs the read scope on board {}.",
+            interaction.user.board
+        ),
+    };
+    Err(denied(interaction, message, Some(Scope::Read)))
+}
+
+pub fn save(state_arc: &Arc<AppState>, saves_path: &PathBuf) -> Vec<String> {
+    let queue_lock = state_arc.boards.read().unwrap();
+
+    let mut queue = Vec::with_capacity(queue_lock.len());
+    for (name, _board) in queue_lock.iter() {
+        queue.push(name.clone());
+    }
+    drop(queue_lock);
+
+    save_boards(state_arc, saves_path, queue)
+}
+
+// Boards with unsaved changes go first, so a save cut short loses as little as it can. A board
+// that fails is reported and the rest are still saved. Returns the boards that failed.
+pub fn save_boards(
+    state_arc: &Arc<AppState>,
+    saves_path: &PathBuf,
+    mut queue: Vec<String>,
+) -> Vec<String> {
+    let save_locker = state_arc.save_locker.lock().unwrap();
+    let start = Instant::now();
+
+    let stdout = io::stdout();
+    let _ = writeln!(&mut stdout.lock(), "Starting backup");
+
+    state_arc.dirty_first(&mut queue);
+
+    let mut saved = 0;
+    let mut failed = Vec::new();
+    for name in queue.iter() {
+        let result = save_queued_board(state_arc, saves_path, name);
+        // marked after the attempt, so a board that fails waits out its interval before the next
+        state_arc.set_saved(name);
+
+        match result {
+            Ok(true) => saved += 1,
+            Ok(false) => {}
+            Err(err) => {
+                let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                failed.push(name.clone());
+            }
+        }
+    }
+
+    let _ = writeln!(
+        &mut stdout.lock(),
+        "Saved {saved} of {} boards in {:.2}s.",
+        queue.len(),
+        start.elapsed().as_secs_f64()
+    );
+
+    let _ = drop(save_locker);
+    failed
+}
+
+// Saves one board of save_boards, Ok(false) if it was skipped.
+fn save_queued_board(
+    state_arc: &Arc<AppState>,
+    saves_path: &PathBuf,
+    name: &String,
+) -> Result<bool, String> {
+    let stdout = io::stdout();
+
+    if state_arc.is_read_only(name) {
+        let _ = writeln!(
+            &mut stdout.lock(),
+            "Skipping {name}, it is read-only until its quarantined save is retried."
+        );
+        return Ok(false);
+    }
+
+    let shared_board = match state_arc.get_board(name) {
+        Some(v) => v,
+        None => return Ok(false),
+    };
+    let mut board = shared_board.write().unwrap();
+
+    let full = state_arc.is_compaction_due(name);
+    // a full save also folds in the deltas, so it is worth doing for a clean board
+    let folds_deltas = full && persist::delta_path(saves_path, name).exists();
+    if !board.is_dirty() && !folds_deltas {
+        let _ = writeln!(&mut stdout.lock(), "Skipping {name}, nothing changed.");
+        return Ok(false);
+    }
+
+    let _ = writeln!(&mut stdout.lock(), "Saving {name}...");
+
+    if board.get_rank_checkpoint() == Some(RankCheckpoint::Save) {
+        board.take_rank_checkpoint();
+    }
+    let ranks = board
+        .get_rank_checkpoint()
+        .map(|_| board.get_previous_ranks());
+    // the journal up to here is covered by this save
+    let journal_mark = state_arc.journal_mark(name);
+    let delta = board.take_delta();
+    let compression = state_arc.board_compression(name);
+
+    let result;
+
+    if !full {
+        let _ = drop(board);
+
+        result = persist::append_delta(&delta, saves_path, name);
+    } else if state_arc.settings().lock_save {
+        result = persist::save_board(&*board, saves_path, name, compression);
+
+        let _ = drop(board);
+    } else {
+        let snapshot = board.get_map_snapshot();
+
+        let _ = drop(board);
+
+        let mut saved = SavedEntries::from_map(&snapshot.get_lock());
+
+        let _ = drop(snapshot);
+
+        saved.sort();
+
+        result = persist::save_entries(&saved, saves_path, name, compression);
+    }
+
+    if let Err(err) = result {
+        shared_board.write().unwrap().restore_delta(delta);
+        return Err(err);
+    }
+
+    // Stale deltas on top of the new save are only put right by the journal, so it is
+    // kept until they are gone.
+    if full {
+        persist::remove_deltas(saves_path, name)?;
+        state_arc.set_compacted(name);
+
+        if let Some(retention) = state_arc.settings().backups
+            && let Err(err) = persist::backup_board(saves_path, name)
+                .and_then(|_| persist::prune_backups(saves_path, name, retention))
+        {
+            let _ = writeln!(&mut io::stderr().lock(), "{err}");
+        }
+    }
+
+    if let Some(mark) = journal_mark {
+        state_arc.truncate_journal(name, mark);
+    }
+
+    if let Some(ranks) = ranks
+        && let Err(err) = persist::save_previous_ranks(&ranks, saves_path, name)
+    {
+        let _ = writeln!(
+            &mut io::stderr().lock(),
+            "Failed to save previous ranks for {name}.\n{}",
+            err
+        );
+    }
+
+    Ok(true)
+}
+
+pub async fn checkpoint_loop(state_arc: Arc<AppState>) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+
+        let queue: Vec<String> = state_arc.boards.read().unwrap().keys().cloned().collect();
+
+        for name in queue.iter() {
+            if let Some(board) = state_arc.get_board(name)
+                && board.read().unwrap().is_rank_checkpoint_due()
+            {
+                board.write().unwrap().take_rank_checkpoint();
+            }
+        }
+    }
+}
+
+// Each board is saved on its own schedule, checked every second.
+pub async fn save_loop(state_arc: Arc<AppState>, saves_path: &PathBuf) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        state_arc.sync_journals();
+        let due = state_arc.due_saves();
+        if due.is_empty() {
+            continue;
+        }
+        save_boards(&state_arc, saves_path, due);
+        #[cfg(feature = "cli")]
+        crate::cli::put_cli_prompt();
+    }
+}
+
+// Applies config.json and boards.json as they are on disk and returns what changed. Boards no
+// longer listed are saved before they are unloaded.
+pub fn reload(state_arc: &Arc<AppState>, saves_path: &PathBuf) -> Result<Vec<String>, String> {
+    // one reload runs at a time, and boards and keys can't change while the files are applied
+    let _config_locker = state_arc.config_locker.lock().unwrap();
+    let (json, board_json) = state_arc.read_config_files()?;
+
+    let removed: Vec<String> = state_arc
+        .boards
+        .read()
+        .unwrap()
+        .keys()
+        .filter(|name| !board_json.contains_key(*name))
+        .cloned()
+        .collect();
+    if !removed.is_empty() {
+        save_boards(state_arc, saves_path, removed);
+    }
+
+    Ok(state_arc.apply_config_files(json, board_json))
+}
+
+// Reloads when config.json or boards.json is modified, checked every watch_interval seconds.
+pub async fn watch_loop(state_arc: Arc<AppState>, saves_path: &PathBuf) {
+    let modified = |path: &PathBuf| -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    };
+    let mut seen = (
+        modified(&state_arc.config_path),
+        modified(&state_arc.boards_path),
+    );
+
+    loop {
+        let interval = state_arc.settings().watch_interval;
+        tokio::time::sleep(Duration::from_secs(interval.unwrap_or(1).max(1))).await;
+
+        let current = (
+            modified(&state_arc.config_path),
+            modified(&state_arc.boards_path),
+        );
+        if interval.is_none() || current == seen {
+            seen = current;
+            continue;
+        }
+        seen = current;
+
+        match reload(&state_arc, saves_path) {
+            Ok(changes) => {
+                if changes.is_empty() {
+                    continue;
+                }
+                let _ = writeln!(
+                    &mut io::stdout().lock(),
+                    "Reloaded:\n{}",
+                    changes.join("\n")
+                );
+            }
+            Err(err) => {
+                let _ = writeln!(&mut io::stderr().lock(), "{err}");
+            }
+        }
+        #[cfg(feature = "cli")]
+        crate::cli::put_cli_prompt();
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ActionType {
+    Update,
+    Remove,
+    Get,
+    Info,
+    Board,
+    AtRank,
+    Top,
+    Bottom,
+    After,
+    Before,
+    Around,
+    Range,
+    Clear,
+}
+
+pub fn execute_action(
+    action: ActionType,
+    interaction: &Interaction,
+    dat: String,
+) -> Result<String, ApiError> {
+    match action {
+        ActionType::Update => execute_update(interaction, dat),
+        ActionType::Remove => execute_remove(interaction, dat),
+        ActionType::Get => execute_get(interaction, dat),
+        ActionType::Info => execute_info(interaction, dat),
+        ActionType::Board => execute_board(interaction, dat),
+        ActionType::AtRank => execute_at_rank(interaction, dat),
+        ActionType::Top => execute_top(interaction, dat),
+        ActionType::Bottom => execute_bottom(interaction, dat),
+        ActionType::After => execute_after(interaction, dat),
+        ActionType::Before => execute_before(interaction, dat),
+        ActionType::Around => execute_around(interaction, dat),
+        ActionType::Range => execute_range(interaction, dat),
+        ActionType::Clear => execute_clear(interaction, dat),
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct UpdReq {
+    id: Key,
+    value: Val,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BasicReq {
+    id: Key,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AtRankReq {
+    rank: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EdgeReq {
+    count: usize,
+    no_cache: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AfterBeforeReq {
+    count: usize,
+    id: Key,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AroundReq {
+    before: usize,
+    after: usize,
+    id: Key,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RangeReq {
+    start: usize,
+    end: usize,
+}
+
+// A (rank, entry) pair, followed by the entry's movement when the board tracks previous ranks.
+pub struct RankedEntry(pub usize, pub Entry<Key, Val>, pub Option<RankMovement>);
+
+impl Serialize for RankedEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut tuple = serializer.serialize_tuple(if self.2.is_some() { 3 } else { 2 })?;
+        tuple.serialize_element(&self.0)?;
+        tuple.serialize_element(&self.1)?;
+        if let Some(movement) = &self.2 {
+            tuple.serialize_element(movement)?;
+        }
+        tuple.end()
+    }
+}
+
+fn with_movement(
+    board: &ServerBoard,
+    entries: Vec<(usize, Entry<Key, Val>)>,
+) -> Vec<RankedEntry> {
+    entries
+        .into_iter()
+        .map(|(rank, entry)| {
+            let movement = board.get_rank_movement(&entry.key, rank);
+            RankedEntry(rank, entry, movement)
+        })
+        .collect()
+}
+
+#[derive(Serialize)]
+struct Response {
+    code: i64,
+    message: String,
+    entry: Option<Entry<Key, Val>>,
+    rank: Option<usize>,
+    entries: Option<Vec<RankedEntry>>,
+    #[serde(flatten)]
+    movement: Option<RankMovement>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BoardResponse {
+    cap: Option<usize>,
+    size: usize,
+    min: Option<Val>,
+}
+
+pub fn execute_update(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_change(interaction, Scope::Submit)?;
+
+    let json_res = serde_json::from_str::<UpdReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+    match update_entry(interaction, json.id, json.value)? {
+        Ok(b) => match b {
+            true => Ok(serde_json::to_string(&Response {
+                code: 0,
+                message: format!("Successfully updated {0}.", json.id),
+                entry: None,
+                rank: None,
+                entries: None,
+                movement: None,
+            })
+            .unwrap()),
+            false => Ok(serde_json::to_string(&Response {
+                code: 1,
+                message: format!("Added player {0} and updated.", json.id),
+                entry: None,
+                rank: None,
+                entries: None,
+                movement: None,
+            })
+            .unwrap()),
+        },
+        Err(v) => Ok(serde_json::to_string(&Response {
+            code: -1,
+            message: format!("Failed to add player {0}: {1}", json.id, v),
+            entry: None,
+            rank: None,
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+    }
+}
+
+pub fn execute_remove(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_change(interaction, Scope::Remove)?;
+
+    let json_res = serde_json::from_str::<BasicReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+    match remove_entry(interaction, json.id)? {
+        Some(v) => Ok(serde_json::to_string(&Response {
+            code: 0,
+            message: format!("Successfully removed {0}.", json.id),
+            entry: Some(v),
+            rank: None,
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+        None => Ok(serde_json::to_string(&Response {
+            code: 1,
+            message: format!("User {0} was already not in the board.", json.id),
+            entry: None,
+            rank: None,
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+    }
+}
+
+pub fn execute_get(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_scope(interaction, Scope::ReadOwn)?;
+
+    let json_res = serde_json::from_str::<BasicReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+    require_player(interaction, json.id)?;
+    match get_entry(interaction, &json.id)? {
+        Some(v) => Ok(serde_json::to_string(&Response {
+            code: 0,
+            message: format!("Found user {0}.", json.id),
+            entry: Some(v),
+            rank: None,
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+        None => Ok(serde_json::to_string(&Response {
+            code: -1,
+            message: format!("User {0} was not in the board.", json.id),
+            entry: None,
+            rank: None,
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+    }
+}
+
+pub fn execute_info(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_scope(interaction, Scope::ReadOwn)?;
+
+    let json_res = serde_json::from_str::<BasicReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+    require_player(interaction, json.id)?;
+    match get_entry_and_rank(interaction, &json.id)? {
+        Some(v) => Ok(serde_json::to_string(&Response {
+            code: 0,
+            message: format!("Found user {0}.", json.id),
+            entry: Some(v.1),
+            rank: Some(v.0),
+            entries: None,
+            movement: v.2,
+        })
+        .unwrap()),
+        None => Ok(serde_json::to_string(&Response {
+            code: -1,
+            message: format!("User {0} was not in the board.", json.id),
+            entry: None,
+            rank: None,
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+    }
+}
+
+pub fn execute_board(interaction: &Interaction, _: String) -> Result<String, ApiError> {
+    require_scope(interaction, Scope::Read)?;
+
+    let res = board_info(interaction)?;
+    Ok(serde_json::to_string(&res).unwrap())
+}
+
+pub fn execute_at_rank(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_scope(interaction, Scope::Read)?;
+
+    let json_res = serde_json::from_str::<AtRankReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+
+    if json.rank == 0 {
+        return Err(Status::BadRequest.into());
+    }
+
+    match at_rank(interaction, json.rank)? {
+        Some(v) => Ok(serde_json::to_string(&Response {
+            code: 0,
+            message: format!("Found user {0} with rank {1}.", v.key, json.rank),
+            entry: Some(v),
+            rank: Some(json.rank),
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+        None => Ok(serde_json::to_string(&Response {
+            code: -1,
+            message: format!("No user with rank {0}.", json.rank),
+            entry: None,
+            rank: Some(json.rank),
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+    }
+}
+
+pub fn execute_top(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_scope(interaction, Scope::Read)?;
+
+    let json_res = serde_json::from_str::<EdgeReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+
+    Ok(serde_json::to_string(&get_top(
+        interaction,
+        json.count,
+        json.no_cache.is_some_and(|v| v),
+    )?)
+    .unwrap())
+}
+
+pub fn execute_bottom(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_scope(interaction, Scope::Read)?;
+
+    let json_res = serde_json::from_str::<EdgeReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+
+    Ok(serde_json::to_string(&get_bottom(
+        interaction,
+        json.count,
+        json.no_cache.is_some_and(|v| v),
+    )?)
+    .unwrap())
+}
+
+pub fn execute_after(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_scope(interaction, Scope::Read)?;
+
+    let json_res = serde_json::from_str::<AfterBeforeReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+
+    match get_after(interaction, &json.id, json.count)? {
+        Some(v) => Ok(serde_json::to_string(&Response {
+            code: 0,
+            message: format!("Retrieved {0} entries after {1}.", v.len(), json.id),
+            entry: None,
+            rank: None,
+            entries: Some(v),
+            movement: None,
+        })
+        .unwrap()),
+        None => Ok(serde_json::to_string(&Response {
+            code: -1,
+            message: format!("User {0} was not in the board.", json.id),
+            entry: None,
+            rank: None,
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+    }
+}
+
+pub fn execute_before(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_scope(interaction, Scope::Read)?;
+
+    let json_res = serde_json::from_str::<AfterBeforeReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+
+    match get_before(interaction, &json.id, json.count)? {
+        Some(v) => Ok(serde_json::to_string(&Response {
+            code: 0,
+            message: format!("Retrieved {0} entries before {1}.", v.len(), json.id),
+            entry: None,
+            rank: None,
+            entries: Some(v),
+            movement: None,
+        })
+        .unwrap()),
+        None => Ok(serde_json::to_string(&Response {
+            code: -1,
+            message: format!("User {0} was not in the board.", json.id),
+            entry: None,
+            rank: None,
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+    }
+}
+
+pub fn execute_around(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_scope(interaction, Scope::Read)?;
+
+    let json_res = serde_json::from_str::<AroundReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+
+    match get_around(interaction, &json.id, json.before, json.after)? {
+        Some(v) => Ok(serde_json::to_string(&Response {
+            code: 0,
+            message: format!("Retrieved {0} entries around {1}.", v.len(), json.id),
+            entry: None,
+            rank: None,
+            entries: Some(v),
+            movement: None,
+        })
+        .unwrap()),
+        None => Ok(serde_json::to_string(&Response {
+            code: -1,
+            message: format!("User {0} was not in the board.", json.id),
+            entry: None,
+            rank: None,
+            entries: None,
+            movement: None,
+        })
+        .unwrap()),
+    }
+}
+
+pub fn execute_range(interaction: &Interaction, dat: String) -> Result<String, ApiError> {
+    require_scope(interaction, Scope::Read)?;
+
+    let json_res = serde_json::from_str::<RangeReq>(dat.as_str());
+    if json_res.is_err() {
+        return Err(Status::BadRequest.into());
+    }
+    let json = json_res.unwrap();
+
+    if json.start == 0 || json.end == 0 {
+        return Err(Status::BadRequest.into())
+    }
+
+    Ok(serde_json::to_string(&get_range(interaction, json.start, json.end)?).unwrap())
+}
+
+pub fn execute_clear(interaction: &Interaction, _: String) -> Result<String, ApiError> {
+    require_change(interaction, Scope::Clear)?;
+
+    clear(interaction)?;
+    interaction.state.notify_change(&interaction.user.board);
+    Ok(serde_json::to_string(&Response {
+        code: 0,
+        message: format!("Cleared board {}.", interaction.user.board),
+        entry: None,
+        rank: None,
+        entries: None,
+        movement: None,
+    })
+    .unwrap())
+}
+
+// The board a request is on. A reload or /admin/del_board can unload it after the request got
+// past its guard, so it isn't there for the handler.
+fn interaction_board(interaction: &Interaction) -> Result<SharedBoard, ApiError> {
+    match interaction.state.get_board(&interaction.user.board) {
+        Some(v) => Ok(v),
+        None => Err(Status::NotFound.into()),
+    }
+}
+
+// The inner result is whether the board took the update.
+pub fn update_entry(
+    interaction: &Interaction,
+    id: Key,
+    value: Val,
+) -> Result<Result<bool, String>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let mut board = binding.write().unwrap();
+    let result = board.update_entry(id, value);
+    if result.is_ok()
+        && let Some(entry) = board.get_entry(&id)
+    {
+        interaction
+            .state
+            .journal(&interaction.user.board, &[JournalRecord::Update(entry)]);
+    }
+    let events = board.take_events();
+    let _ = drop(board);
+
+    if result.is_ok() {
+        interaction.state.notify_change(&interaction.user.board);
+    }
+    notify_webhooks(interaction, events);
+    Ok(result)
+}
+
+// Hands events to the board's webhooks. Called after the board lock is released.
+fn notify_webhooks(interaction: &Interaction, events: Vec<BoardEvent<Key, Val>>) {
+    if events.is_empty() {
+        return;
+    }
+
+    let webhooks = interaction.state.webhooks.lock().unwrap();
+    if let Some(board_webhooks) = webhooks.get(&interaction.user.board) {
+        for event in events.iter() {
+            for webhook in board_webhooks.iter() {
+                webhook.notify(&interaction.user.board, event);
+            }
+        }
+    }
+}
+
+// Entries are applied and journaled this many at a time, so a long import doesn't hold the
+// board's lock throughout.
+const IMPORT_BATCH: usize = 10_000;
+
+#[derive(Serialize, Default, Debug)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub replaced: usize,
+    // already on the board and kept by the conflict policy
+    pub skipped: usize,
+    // points that aren't a number, or too low to fall into the size cap
+    pub rejected: usize,
+}
+
+// Writes the board in rank order and returns how many entries there were. Updates wait until
+// the export is written.
+pub fn export_board<W: Write>(
+    interaction: &Interaction,
+    format: TransferFormat,
+    writer: &mut W,
+) -> Result<usize, String> {
+    let binding = match interaction.state.get_board(&interaction.user.board) {
+        Some(v) => v,
+        None => return Err(format!("No board named {}.", interaction.user.board)),
+    };
+    let board = binding.read().unwrap();
+    transfer::write_entries(board.iter(), format, writer)
+}
+
+// Streams entries from reader into the board. Entries already applied stay when a later row
+// can't be read.
+pub fn import_board<R: BufRead>(
+    interaction: &Interaction,
+    reader: R,
+    format: TransferFormat,
+    policy: ConflictPolicy,
+) -> Result<ImportSummary, String> {
+    let mut summary = ImportSummary::default();
+    let mut rows = Vec::with_capacity(IMPORT_BATCH);
+
+    let result = transfer::read_rows(reader, format, |row| {
+        rows.push(row);
+        if rows.len() >= IMPORT_BATCH {
+            apply_import_rows(interaction, &mut rows, policy, &mut summary)?;
+        }
+        Ok(())
+    })
+    .and_then(|_| apply_import_rows(interaction, &mut rows, policy, &mut summary));
+
+    match result {
+        Ok(_) => Ok(summary),
+        Err(err) => Err(format!(
+            "{err}\n{} entries were imported before it.",
+            summary.added + summary.replaced
+        )),
+    }
+}
+
+fn apply_import_rows(
+    interaction: &Interaction,
+    rows: &mut Vec<ImportRow>,
+    policy: ConflictPolicy,
+    summary: &mut ImportSummary,
+) -> Result<(), String> {
+    if rows.is_empty() {
+        return Ok(());
+    }
+
+    let binding = match interaction.state.get_board(&interaction.user.board) {
+        Some(v) => v,
+        None => return Err(format!("No board named {}.", interaction.user.board)),
+    };
+    let mut board = binding.write().unwrap();
+    let mut records = Vec::with_capacity(rows.len());
+    let mut events = Vec::new();
+
+    for row in rows.drain(..) {
+        if row.points.is_nan() {
+            summary.rejected += 1;
+            continue;
+        }
+
+        let existing = board.get_entry(&row.key);
+        if let Some(existing) = &existing
+            && policy.keeps(existing, &row)
+        {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let result = match row.timestamp {
+            Some(timestamp) => board.set_entry(Entry {
+                key: row.key,
+                points: row.points,
+                timestamp: timestamp,
+            }),
+            None => board.update_entry(row.key, row.points),
+        };
+        events.append(&mut board.take_events());
+
+        match result.ok().and_then(|_| board.get_entry(&row.key)) {
+            Some(entry) => {
+                records.push(JournalRecord::Update(entry));
+                match existing {
+                    Some(_) => summary.replaced += 1,
+                    None => summary.added += 1,
+                }
+            }
+            None => summary.rejected += 1,
+        }
+    }
+
+    if !records.is_empty() {
+        interaction.state.journal(&interaction.user.board, &records);
+    }
+    let _ = drop(board);
+
+    if !records.is_empty() {
+        interaction.state.notify_change(&interaction.user.board);
+    }
+    notify_webhooks(interaction, events);
+    Ok(())
+}
+
+pub fn board_info(interaction: &Interaction) -> Result<BoardResponse, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    Ok(BoardResponse {
+        cap: board.get_size_cap(),
+        size: board.get_size(),
+        min: board.get_min(),
+    })
+}
+
+pub fn remove_entry(
+    interaction: &Interaction,
+    id: Key,
+) -> Result<Option<Entry<Key, Val>>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let mut board = binding.write().unwrap();
+    let result = board.remove_entry(&id);
+    if result.is_some() {
+        interaction
+            .state
+            .journal(&interaction.user.board, &[JournalRecord::Remove(id)]);
+    }
+    let events = board.take_events();
+    let _ = drop(board);
+
+    if result.is_some() {
+        interaction.state.notify_change(&interaction.user.board);
+    }
+    notify_webhooks(interaction, events);
+    Ok(result)
+}
+
+pub fn get_points(interaction: &Interaction, id: &Key) -> Result<Option<Val>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    Ok(board.get_entry(id).map(|entry| entry.points))
+}
+
+pub fn get_entry(
+    interaction: &Interaction,
+    id: &Key,
+) -> Result<Option<Entry<Key, Val>>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    Ok(board.get_entry(id).map(|v| v.clone()))
+}
+
+pub fn get_entry_and_rank(
+    interaction: &Interaction,
+    id: &Key,
+) -> Result<Option<RankedEntry>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    let Some((rank, entry)) = board.get_entry_and_rank(id) else {
+        return Ok(None);
+    };
+    let movement = board.get_rank_movement(id, rank);
+    Ok(Some(RankedEntry(rank, entry, movement)))
+}
+
+pub fn get_size(interaction: &Interaction) -> Result<usize, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    Ok(board.get_size())
+}
+
+pub fn get_rank(interaction: &Interaction, id: &Key) -> Result<Option<usize>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    Ok(board.get_rank(id))
+}
+
+pub fn at_rank(
+    interaction: &Interaction,
+    rank: usize,
+) -> Result<Option<Entry<Key, Val>>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    Ok(board.at_rank(rank))
+}
+
+pub fn clear(interaction: &Interaction) -> Result<(), ApiError> {
+    match clear_board(interaction.state, &interaction.user.board) {
+        true => Ok(()),
+        false => Err(Status::NotFound.into()),
+    }
+}
+
+// False if there is no board by that name.
+pub fn clear_board(state: &AppState, name: &String) -> bool {
+    let binding = match state.get_board(name) {
+        Some(v) => v,
+        None => return false,
+    };
+    let mut board = binding.write().unwrap();
+    board.clear();
+    state.journal(name, &[JournalRecord::Clear]);
+    return true;
+}
+
+pub fn get_top(
+    interaction: &Interaction,
+    count: usize,
+    no_cache: bool,
+) -> Result<Vec<RankedEntry>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    let entries = board.get_top(count, no_cache, interaction.state.settings().cache_len);
+    Ok(with_movement(&board, entries))
+}
+
+pub fn get_bottom(
+    interaction: &Interaction,
+    count: usize,
+    no_cache: bool,
+) -> Result<Vec<RankedEntry>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    let entries = board.get_bottom(count, no_cache, interaction.state.settings().cache_len);
+    Ok(with_movement(&board, entries))
+}
+
+pub fn get_after(
+    interaction: &Interaction,
+    id: &Key,
+    count: usize,
+) -> Result<Option<Vec<RankedEntry>>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    let Some(entries) = board.get_after(id, count) else {
+        return Ok(None);
+    };
+    Ok(Some(with_movement(&board, entries)))
+}
+
+pub fn get_before(
+    interaction: &Interaction,
+    id: &Key,
+    count: usize,
+) -> Result<Option<Vec<RankedEntry>>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    let Some(entries) = board.get_before(id, count) else {
+        return Ok(None);
+    };
+    Ok(Some(with_movement(&board, entries)))
+}
+
+pub fn get_around(
+    interaction: &Interaction,
+    id: &Key,
+    before: usize,
+    after: usize,
+) -> Result<Option<Vec<RankedEntry>>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    let Some(entries) = board.get_around(id, before, after) else {
+        return Ok(None);
+    };
+    Ok(Some(with_movement(&board, entries)))
+}
+
+pub fn get_range(
+    interaction: &Interaction,
+    start: usize,
+    end: usize,
+) -> Result<Vec<RankedEntry>, ApiError> {
+    let binding = interaction_board(interaction)?;
+    let board = binding.read().unwrap();
+    let entries = board.get_range(start, end);
+    Ok(with_movement(&board, entries))
+}
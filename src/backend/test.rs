@@ -0,0 +1,226 @@
+use rocket::fairing::AdHoc;
+use rocket::http::Header;
+use rocket::local::blocking::Client;
+
+use super::*;
+use crate::app_state::test::{TEST_CONFIG, temp_state};
+
+fn user(scopes: Scopes, boards: &[(&str, Scopes)]) -> User {
+    User {
+        board: "main".to_string(),
+        scopes: scopes,
+        boards: boards
+            .iter()
+            .map(|(name, scopes)| (name.to_string(), *scopes))
+            .collect(),
+        player: None,
+    }
+}
+
+#[test]
+fn test_matches_pattern() {
+    assert!(matches_pattern("main", "main"));
+    assert!(!matches_pattern("main", "main2"));
+    assert!(!matches_pattern("main", "mai"));
+
+    assert!(matches_pattern("*", "main"));
+    assert!(matches_pattern("*", ""));
+    assert!(matches_pattern("game_*", "game_1"));
+    assert!(matches_pattern("game_*", "game_"));
+    assert!(!matches_pattern("game_*", "game"));
+    assert!(!matches_pattern("game_*", "xgame_1"));
+
+    assert!(matches_pattern("*_weekly", "game_weekly"));
+    assert!(!matches_pattern("*_weekly", "game_weekly2"));
+
+    assert!(matches_pattern("game_*_weekly", "game_1_weekly"));
+    assert!(matches_pattern("game_*_weekly", "game__weekly"));
+    assert!(!matches_pattern("game_*_weekly", "game_weekly"));
+    assert!(matches_pattern("*a*b*", "xaybz"));
+    assert!(!matches_pattern("*a*b*", "xbyaz"));
+
+    // the prefix and suffix can't share characters
+    assert!(!matches_pattern("ab*ba", "aba"));
+    assert!(matches_pattern("ab*ba", "abba"));
+}
+
+#[test]
+fn test_access() {
+    let read = Scopes::of(&[Scope::Read]);
+    let submit = Scopes::of(&[Scope::Read, Scope::Submit]);
+    let all = Scopes::all();
+    let key = user(
+        all,
+        &[
+            ("game_1", read),
+            ("game_*", submit),
+            ("game_1*", all),
+            ("*", read),
+        ],
+    );
+
+    assert_eq!(key.access("main"), Some(all));
+    // an exact name wins over the patterns that match it
+    assert_eq!(key.access("game_1"), Some(read));
+    // and the longest pattern over shorter ones
+    assert_eq!(key.access("game_10"), Some(all));
+    assert_eq!(key.access("game_2"), Some(submit));
+    assert_eq!(key.access("other"), Some(read));
+
+    let key = user(read, &[("game_*", submit)]);
+    assert_eq!(key.access("game_2"), Some(submit));
+    assert_eq!(key.access("other"), None);
+    // names without a * only match themselves
+    let key = user(read, &[("game_", submit)]);
+    assert_eq!(key.access("game_1"), None);
+}
+
+#[test]
+fn test_on_board() {
+    let read = Scopes::of(&[Scope::Read]);
+    let key = user(Scopes::all(), &[("game_*", read)]);
+
+    let on_board = key.on_board("game_1").unwrap();
+    assert_eq!(on_board.board, "game_1");
+    assert_eq!(on_board.scopes, read);
+    assert_eq!(on_board.boards, key.boards);
+
+    let on_board = key.on_board("main").unwrap();
+    assert_eq!(on_board.scopes, Scopes::all());
+
+    assert!(key.on_board("other").is_none());
+}
+
+#[rocket::get("/board?<n>")]
+fn board(interaction: Interaction, n: Option<usize>) -> String {
+    format!("{} {n:?}", interaction.user.board)
+}
+
+#[rocket::post("/get", data = "<data>")]
+fn get_route(interaction: Interaction, data: String) -> Result<String, ApiError> {
+    execute_get(&interaction, data)
+}
+
+fn client(name: &str) -> Client {
+    let (state, _) = temp_state(
+        name,
+        TEST_CONFIG,
+        r#"{
+            "main": {"keys": {
+                "k": {"write": false, "boards": {"game_*": {"write": true}}},
+                "own": {"scopes": ["read_own"], "player": 1, "boards": {"game_*": {"scopes": ["read_own"]}}},
+                "unbound": {"scopes": ["read_own"]}
+            }, "cap": null},
+            "game_1": {"keys": {}, "cap": null},
+            "other": {"keys": {}, "cap": null}
+        }"#,
+    );
+    let rocket = rocket::build()
+        .manage(Arc::new(state))
+        .mount("/", rocket::routes![board, get_route])
+        .attach(AdHoc::on_request("Board Paths", |req, _| {
+            Box::pin(async move {
+                route_board_path(req);
+            })
+        }));
+    Client::tracked(rocket).unwrap()
+}
+
+fn get(client: &Client, uri: &str) -> (Status, Option<String>) {
+    let response = client
+        .get(uri.to_string())
+        .header(Header::new("x-api-key", "k"))
+        .dispatch();
+    (response.status(), response.into_string())
+}
+
+#[test]
+fn test_route_board_path() {
+    let client = client("route_board_path");
+
+    assert_eq!(get(&client, "/board").1.unwrap(), "main None");
+    assert_eq!(
+        get(&client, "/boards/game_1/board?n=3").1.unwrap(),
+        "game_1 Some(3)"
+    );
+    assert_eq!(get(&client, "/boards/main/board").1.unwrap(), "main None");
+
+    // the key can't name other, and game_2 fits its pattern but doesn't exist
+    assert_eq!(get(&client, "/boards/other/board").0, Status::Forbidden);
+    assert_eq!(get(&client, "/boards/game_2/board").0, Status::NotFound);
+
+    // paths that don't name a board and a route are left as they are
+    assert_eq!(get(&client, "/boards//board").0, Status::NotFound);
+    assert_eq!(get(&client, "/boards/game_1").0, Status::NotFound);
+    assert_eq!(get(&client, "/boards").0, Status::NotFound);
+}
+
+// Posts to /get as the key and returns the status and the message or why it was denied.
+fn get_player(client: &Client, uri: &str, key: &str, id: Key) -> (Status, serde_json::Value) {
+    let response = client
+        .post(uri.to_string())
+        .header(Header::new("x-api-key", key.to_string()))
+        .body(format!(r#"{{"id": {id}}}"#))
+        .dispatch();
+    let status = response.status();
+    (
+        status,
+        serde_json::from_str(&response.into_string().unwrap()).unwrap(),
+    )
+}
+
+#[test]
+fn test_read_own() {
+    let client = client("read_own");
+
+    // read reads anyone, read_own only the player the key is bound to
+    assert_eq!(get_player(&client, "/get", "k", 2).0, Status::Ok);
+    assert_eq!(get_player(&client, "/get", "own", 1).0, Status::Ok);
+    assert_eq!(
+        get_player(&client, "/boards/game_1/get", "own", 1).0,
+        Status::Ok
+    );
+
+    let (status, json) = get_player(&client, "/get", "own", 2);
+    assert_eq!(status, Status::Forbidden);
+    assert_eq!(json["missing_scope"], "read");
+    assert_eq!(
+        json["message"],
+        "This API key can only read player 1 on board main, other players need the read scope."
+    );
+    assert_eq!(
+        get_player(&client, "/boards/game_1/get", "own", 2).0,
+        Status::Forbidden
+    );
+
+    let (status, json) = get_player(&client, "/get", "unbound", 1);
+    assert_eq!(status, Status::Forbidden);
+    assert_eq!(json["missing_scope"], "read");
+}
+
+#[test]
+fn test_board_unloaded() {
+    let (state, _) = temp_state(
+        "board_unloaded",
+        TEST_CONFIG,
+        r#"{"main": {"keys": {}, "cap": null}}"#,
+    );
+    let state = Arc::new(state);
+    let interaction = Interaction {
+        user: user(Scopes::all(), &[]),
+        state: (&state).into(),
+    };
+    assert_eq!(update_entry(&interaction, 1, 1.0).unwrap(), Ok(true));
+
+    // a request that got past its guard finds the board gone
+    assert!(state.delete_board(&"main".to_string()));
+    for result in [
+        execute_get(&interaction, r#"{"id": 1}"#.to_string()),
+        execute_update(&interaction, r#"{"id": 1, "value": 2}"#.to_string()),
+        execute_top(&interaction, r#"{"count": 1}"#.to_string()),
+        execute_clear(&interaction, String::new()),
+    ] {
+        assert_eq!(result.unwrap_err().status, Status::NotFound);
+    }
+    assert!(get_top(&interaction, 1, true).is_err());
+}
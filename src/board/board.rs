@@ -1,14 +1,21 @@
 use bincode::Decode;
 use bincode::Encode;
 use bincode::de::Decoder;
-use rocket::tokio::time::Instant;
+use serde::{Deserialize, Serialize};
 
 use super::Entry;
 use super::Tree;
-use super::diff_map::{DiffMap, SnapshotBorrow};
+use super::ranked_index::{AnyIndex, IndexKind, RankedIndex};
+use super::diff_map::{Changes, DiffMap, SnapshotBorrow};
 use std::collections::HashMap;
 use std::hash::Hash;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::mem;
+use std::ops::{Bound, RangeBounds};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// how many entries are built between progress reports when loading
+const PROGRESS_STEP: usize = 4096;
 
 fn current_time() -> f64 {
     SystemTime::now()
@@ -30,18 +37,139 @@ where
     bottom_requested: bool,
 }
 
+impl<K, V> Cache<K, V>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone,
+    V: PartialOrd + Default + ?Sized + Clone,
+{
+    fn new() -> Self {
+        Self {
+            top_cache: None,
+            bottom_cache: None,
+            top_cache_time: Instant::now(),
+            bottom_cache_time: Instant::now(),
+            top_requested: false,
+            bottom_requested: false,
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.top_cache = None;
+        self.bottom_cache = None;
+    }
+
+    // Patch the caches to account for an entry having been removed from the board.
+    fn on_remove(&mut self, entry: &Entry<K, V>) {
+        if let Some(top) = self.top_cache.as_mut() {
+            // top is sorted best to worst, so everything before `pos` outranks the removed entry.
+            let pos = top.partition_point(|(_, e)| e > entry);
+            if pos < top.len() && top[pos].1 == *entry {
+                top.remove(pos);
+                for (rank, _) in top[pos..].iter_mut() {
+                    *rank -= 1;
+                }
+            }
+        }
+
+        if let Some(bottom) = self.bottom_cache.as_mut() {
+            // bottom is sorted worst to best, so everything before `pos` is ranked below the removed entry.
+            let pos = bottom.partition_point(|(_, e)| e < entry);
+            if pos < bottom.len() && bottom[pos].1 == *entry {
+                bottom.remove(pos);
+            }
+            for (rank, _) in bottom[..pos].iter_mut() {
+                *rank -= 1;
+            }
+        }
+    }
+
+    // Patch the caches to account for an entry having been added to the board.
+    fn on_insert(&mut self, entry: &Entry<K, V>) {
+        if let Some(top) = self.top_cache.as_mut() {
+            let pos = top.partition_point(|(_, e)| e > entry);
+            // if the entry lands after the cached window, the window itself is unchanged.
+            if pos < top.len() {
+                top.insert(pos, (pos + 1, entry.clone()));
+                for (rank, _) in top[pos + 1..].iter_mut() {
+                    *rank += 1;
+                }
+            }
+        }
+
+        if let Some(bottom) = self.bottom_cache.as_mut() {
+            let pos = bottom.partition_point(|(_, e)| e < entry);
+            for (rank, _) in bottom[..pos].iter_mut() {
+                *rank += 1;
+            }
+            if pos < bottom.len() {
+                let rank = bottom[pos].0 + 1;
+                bottom.insert(pos, (rank, entry.clone()));
+            }
+        }
+    }
+}
+
+// When a board records everyone's rank so later responses can report movement since then.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RankCheckpoint {
+    Reset,
+    Save,
+    // minutes between checkpoints
+    Interval(u64),
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct RankMovement {
+    pub previous_rank: Option<usize>,
+    // positive when the entry has climbed since the checkpoint
+    pub delta: Option<i64>,
+}
+
+// Notable changes produced by the last mutation, recorded only while event tracking is on.
+#[derive(Clone)]
+pub enum BoardEvent<K, V>
+where
+    K: PartialOrd + Default,
+    V: PartialOrd + Default,
+{
+    Ranked {
+        entry: Entry<K, V>,
+        rank: usize,
+        previous_rank: Option<usize>,
+    },
+    NewLeader {
+        entry: Entry<K, V>,
+        previous: Option<Entry<K, V>>,
+    },
+    Evicted {
+        entry: Entry<K, V>,
+    },
+}
+
+// The entries are kept in any RankedIndex, the AVL tree unless another is picked.
 pub struct Board<
     K: PartialOrd + Eq + Hash + Sized + Default + Clone = u64,
     V: PartialOrd + Default + ?Sized + Clone = f64,
+    I: RankedIndex<Entry<K, V>> = Tree<Entry<K, V>>,
 > {
-    tree: Tree<Entry<K, V>>,
+    tree: I,
     map: DiffMap<K, Entry<K, V>>,
     size_cap: Option<usize>,
-    cache: Cache<K, V>,
+    // behind its own lock so cached reads only need shared access to the board
+    cache: Mutex<Cache<K, V>>,
+    rank_checkpoint: Option<RankCheckpoint>,
+    previous_ranks: Arc<HashMap<K, usize>>,
+    checkpoint_time: Instant,
+    event_depth: Option<usize>,
+    events: Vec<BoardEvent<K, V>>,
 }
 
-impl<K: PartialOrd + Eq + Hash + Sized + Default + Clone, V: PartialOrd + Default + ?Sized + Clone>
-    Board<K, V>
+impl<
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone,
+    V: PartialOrd + Default + ?Sized + Clone,
+    I: RankedIndex<Entry<K, V>>,
+> Board<K, V, I>
 {
     pub fn get_entry(&self, id: &K) -> Option<Entry<K, V>> {
         return self.map.get(id);
@@ -53,7 +181,10 @@ impl<K: PartialOrd + Eq + Hash + Sized + Default + Clone, V: PartialOrd + Defaul
         Some((rank, entry))
     }
 
-    pub fn get_tree_copy(&self) -> Tree<Entry<K, V>> {
+    pub fn get_tree_copy(&self) -> I
+    where
+        I: Clone,
+    {
         self.tree.clone()
     }
 
@@ -65,7 +196,52 @@ impl<K: PartialOrd + Eq + Hash + Sized + Default + Clone, V: PartialOrd + Defaul
         self.map.is_borrowed()
     }
 
+    // Whether entries changed since the last delta was taken.
+    pub fn is_dirty(&self) -> bool {
+        self.map.has_changes()
+    }
+
+    pub fn take_delta(&mut self) -> BoardDelta<K, V> {
+        let changes = self.map.take_changes();
+        BoardDelta {
+            cleared: changes.cleared,
+            changes: changes
+                .keys
+                .into_iter()
+                .map(|key| {
+                    let entry = self.map.get(&key);
+                    (key, entry)
+                })
+                .collect(),
+        }
+    }
+
+    // Puts a delta that failed to save back, so its entries go out with the next one.
+    pub fn restore_delta(&mut self, delta: BoardDelta<K, V>) {
+        self.map.restore_changes(Changes {
+            cleared: delta.cleared,
+            keys: delta.changes.into_iter().map(|(key, _)| key).collect(),
+        });
+    }
+
+    pub fn apply_delta(&mut self, delta: BoardDelta<K, V>) {
+        if delta.cleared {
+            self.clear();
+        }
+        for (key, entry) in delta.changes {
+            match entry {
+                Some(entry) => {
+                    let _ = self.set_entry(entry);
+                }
+                None => {
+                    let _ = self.remove_entry(&key);
+                }
+            }
+        }
+    }
+
     pub fn add_entry(&mut self, entry: Entry<K, V>) -> Result<bool, String> {
+        self.events.clear();
         let id = entry.key.clone();
 
         if self.map.contains_key(&id) {
@@ -79,41 +255,75 @@ impl<K: PartialOrd + Eq + Hash + Sized + Default + Clone, V: PartialOrd + Defaul
             return Err("Too low rank to fall into the size cap.".to_string());
         }
 
+        let leader = self.tracked_leader();
+
         self.tree.insert(entry.clone());
+        self.cache.get_mut().unwrap().on_insert(&entry);
+        self.record_rank(&entry, None);
         self.map.insert(id, entry);
 
         if self.is_past_size_cap() {
-            let mut cursor = self.tree.cursor_mut();
-            let entry = cursor.delete_next().unwrap();
+            let lowest = self.tree.at_index(self.tree.len() - 1).unwrap().clone();
+            let entry = self.tree.remove(&lowest).unwrap();
+            self.cache.get_mut().unwrap().on_remove(&entry);
             self.map.remove(&entry.key);
+            if self.event_depth.is_some() {
+                self.events.push(BoardEvent::Evicted { entry: entry });
+            }
         }
 
+        self.record_leader(leader);
+
         return Ok(true);
     }
 
-    pub fn get_ids(&self) -> Vec<K> {
-        let mut ret = Vec::with_capacity(self.get_size());
+    // (rank, entry) from the top of the board down
+    pub fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (usize, &Entry<K, V>)> + ExactSizeIterator {
+        self.range(..)
+    }
 
-        let mut cursor = self.tree.cursor();
+    pub fn iter_from_rank(
+        &self,
+        rank: usize,
+    ) -> impl DoubleEndedIterator<Item = (usize, &Entry<K, V>)> + ExactSizeIterator {
+        self.range(rank..)
+    }
 
-        loop {
-            let val = cursor.move_next();
-            match val {
-                Some(v) => {
-                    ret.push(v.key.clone());
-                }
-                None => {
-                    break;
-                }
-            }
-        }
+    // Ranks start at 1, a range that starts at 0 is treated as starting at 1.
+    pub fn range<R: RangeBounds<usize>>(
+        &self,
+        ranks: R,
+    ) -> impl DoubleEndedIterator<Item = (usize, &Entry<K, V>)> + ExactSizeIterator {
+        let start = match ranks.start_bound() {
+            Bound::Included(&r) => Bound::Included(r.saturating_sub(1)),
+            Bound::Excluded(&r) => Bound::Excluded(r.saturating_sub(1)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        let end = match ranks.end_bound() {
+            Bound::Included(&0) => Bound::Excluded(0),
+            Bound::Included(&r) => Bound::Included(r - 1),
+            Bound::Excluded(&r) => Bound::Excluded(r.saturating_sub(1)),
+            Bound::Unbounded => Bound::Unbounded,
+        };
+        self.tree.range((start, end)).map(|(i, v)| (i + 1, v))
+    }
 
-        return ret;
+    // keys from the lowest entry to the highest
+    pub fn get_ids(&self) -> Vec<K> {
+        return self.iter().rev().map(|(_, v)| v.key.clone()).collect();
     }
 
     pub fn remove_entry(&mut self, id: &K) -> Option<Entry<K, V>> {
+        self.events.clear();
+        let leader = self.tracked_leader();
+
         let entry = self.map.remove(id)?;
         self.tree.remove(&entry);
+        self.cache.get_mut().unwrap().on_remove(&entry);
+
+        self.record_leader(leader);
         Some(entry)
     }
 
@@ -125,17 +335,88 @@ impl<K: PartialOrd + Eq + Hash + Sized + Default + Clone, V: PartialOrd + Defaul
         self.size_cap = None;
     }
 
-    pub fn trim_after_cap(&mut self) {
+    // Removes every entry beyond the size cap, returning them as a new board.
+    pub fn trim_after_cap(&mut self) -> Self {
         if self.size_cap.is_none() {
-            return;
+            return Self::from_tree(I::default());
         }
 
         let cap = self.size_cap.unwrap();
-        let mut cursor = self.tree.cursor_mut();
-        while cap < cursor.get_tree().len() {
-            let entry = cursor.delete_next().unwrap();
+        if cap >= self.tree.len() {
+            return Self::from_tree(I::default());
+        }
+
+        let trimmed = self.tree.split_at_index(cap);
+        self.forget_entries(&trimmed);
+        Self::from_tree(trimmed)
+    }
+
+    // Removes every entry from the given rank down, returning them as a new board.
+    pub fn split_at_rank(&mut self, rank: usize) -> Self {
+        let split = self.tree.split_at_index(rank.max(1) - 1);
+        self.forget_entries(&split);
+        Self::from_tree(split)
+    }
+
+    // Removes every entry with fewer than the given points, returning them as a new board.
+    pub fn split_at_points(&mut self, points: V) -> Self {
+        // ties are ranked by earliest timestamp, so this sits below every entry with these points
+        let bound = Entry {
+            key: K::default(),
+            points: points,
+            timestamp: f64::INFINITY,
+        };
+        let split = self.tree.split_at_value(&bound);
+        self.forget_entries(&split);
+        Self::from_tree(split)
+    }
+
+    // Moves every entry of another board into this one, returning the entries the size cap trims
+    // off after. Only works when all of the other board's entries rank above or below this
+    // board's and no key is on both, otherwise it is given back.
+    pub fn join(&mut self, other: Self) -> Result<Self, Box<Self>> {
+        self.events.clear();
+
+        if !self.tree.is_empty() && !other.tree.is_empty() {
+            let low = self.tree.at_index(self.tree.len() - 1).unwrap();
+            let high = self.tree.at_index(0).unwrap();
+            let other_low = other.tree.at_index(other.tree.len() - 1).unwrap();
+            let other_high = other.tree.at_index(0).unwrap();
+            if !(other_high < low || other_low > high) {
+                return Err(Box::new(other));
+            }
+        }
+
+        if other
+            .iter()
+            .any(|(_, entry)| self.map.contains_key(&entry.key))
+        {
+            return Err(Box::new(other));
+        }
+        let entries: Vec<_> = other.iter().map(|(_, entry)| entry.clone()).collect();
+
+        let leader = self.tracked_leader();
+
+        if self.tree.join(other.tree).is_err() {
+            panic!("Boards with separate ranges failed to join!");
+        }
+        for entry in entries {
+            self.map.insert(entry.key.clone(), entry);
+        }
+        self.cache.get_mut().unwrap().invalidate();
+
+        self.record_leader(leader);
+        Ok(self.trim_after_cap())
+    }
+
+    // Drops entries that were split off the tree from the map.
+    fn forget_entries(&mut self, split: &I) {
+        for (_, entry) in split.iter() {
             self.map.remove(&entry.key);
         }
+
+        // cheaper to rebuild than to patch once per removed entry.
+        self.cache.get_mut().unwrap().invalidate();
     }
 
     pub fn get_size_cap(&self) -> Option<usize> {
@@ -151,31 +432,48 @@ impl<K: PartialOrd + Eq + Hash + Sized + Default + Clone, V: PartialOrd + Defaul
     }
 
     pub fn update_entry(&mut self, id: K, points: V) -> Result<bool, String> {
-        let old_entry_opt = self.map.get(&id);
+        self.events.clear();
+        if let Some(old_entry) = self.map.get(&id)
+            && old_entry.points == points
+        {
+            return Ok(true);
+        }
+
+        self.set_entry(Entry {
+            key: id,
+            points: points,
+            timestamp: current_time(),
+        })
+    }
+
+    // Like update_entry but keeps the entry's timestamp, used when replaying the journal.
+    pub fn set_entry(&mut self, new_entry: Entry<K, V>) -> Result<bool, String> {
+        self.events.clear();
+        let old_entry_opt = self.map.get(&new_entry.key);
         if let None = old_entry_opt {
-            let new_entry = Entry {
-                key: id,
-                points: points,
-                timestamp: current_time(),
-            };
             return match self.add_entry(new_entry) {
                 Ok(_) => Ok(true),
                 Err(v) => Err(v),
             };
         }
         let old_entry = old_entry_opt.unwrap();
-        if old_entry.points == points {
+        if old_entry == new_entry {
             return Ok(true);
         }
 
-        let new_entry = Entry {
-            key: id.clone(),
-            points: points,
-            timestamp: current_time(),
-        };
+        let leader = self.tracked_leader();
+        let previous_rank = self
+            .event_depth
+            .map(|_| self.tree.index_of(&old_entry).0 + 1);
 
         self.tree.replace(&old_entry, new_entry.clone());
-        self.map.insert(id, new_entry);
+        let cache = self.cache.get_mut().unwrap();
+        cache.on_remove(&old_entry);
+        cache.on_insert(&new_entry);
+        self.record_rank(&new_entry, previous_rank);
+        self.map.insert(new_entry.key.clone(), new_entry);
+
+        self.record_leader(leader);
         Ok(true)
     }
 
@@ -193,113 +491,146 @@ impl<K: PartialOrd + Eq + Hash + Sized + Default + Clone, V: PartialOrd + Defaul
     }
 
     pub fn is_top_cache_expired(&self, expire_len_secs: f64) -> bool {
-        self.cache.top_cache_time.elapsed().as_secs_f64() > expire_len_secs
+        let cache = self.cache.lock().unwrap();
+        cache.top_cache_time.elapsed().as_secs_f64() > expire_len_secs
     }
 
     pub fn get_top(
-        &mut self,
+        &self,
         count: usize,
         no_cache: bool,
         expire_len_secs: f64,
     ) -> Vec<(usize, Entry<K, V>)> {
-        self.cache.top_requested = self.cache.top_requested || !no_cache;
+        let mut cache = self.cache.lock().unwrap();
+        cache.top_requested = cache.top_requested || !no_cache;
 
-        let cache_unusable = self.cache.top_cache.as_ref().is_none()
-            || count > self.cache.top_cache.as_ref().unwrap().len()
-            || self.is_top_cache_expired(expire_len_secs);
+        let cache_unusable = cache.top_cache.is_none()
+            || cache.top_cache_time.elapsed().as_secs_f64() > expire_len_secs;
         if no_cache || cache_unusable {
             let top = self.get_top_cacheless(count);
-            if self.cache.top_requested && cache_unusable {
-                self.cache.top_cache = Some(top.clone());
-                self.cache.top_cache_time = Instant::now();
+            if cache.top_requested && cache_unusable {
+                cache.top_cache = Some(top.clone());
+                cache.top_cache_time = Instant::now();
             }
             top
         } else {
-            self.cache.top_cache.as_ref().unwrap()[0..count].to_vec()
+            self.extend_top_cache(&mut cache, count);
+            let top = cache.top_cache.as_ref().unwrap();
+            top[0..count.min(top.len())].to_vec()
         }
     }
 
-    pub fn get_top_cacheless(&self, count: usize) -> Vec<(usize, Entry<K, V>)> {
-        let mut ret = Vec::with_capacity(count);
-
-        let mut cursor = self.tree.cursor();
-
-        for _i in 0..count {
-            cursor.move_prev();
-            if let Some(v) = cursor.get_value() {
-                let entry = v.clone();
-                ret.push((cursor.get_index().unwrap() + 1, entry));
-            } else {
-                break;
-            }
+    // Lazily grow the top cache so it covers at least `count` entries, if the board has them.
+    fn extend_top_cache(&self, cache: &mut Cache<K, V>, count: usize) {
+        let top = cache.top_cache.as_mut().unwrap();
+        if count <= top.len() {
+            return;
         }
 
-        return ret;
+        let start = top.len();
+        top.extend(
+            self.tree
+                .range(start..count)
+                .map(|(i, v)| (i + 1, v.clone())),
+        );
+    }
+
+    pub fn get_top_cacheless(&self, count: usize) -> Vec<(usize, Entry<K, V>)> {
+        return self
+            .iter()
+            .take(count)
+            .map(|(r, v)| (r, v.clone()))
+            .collect();
     }
 
     pub fn is_bottom_cache_expired(&self, expire_len_secs: f64) -> bool {
-        self.cache.bottom_cache_time.elapsed().as_secs_f64() > expire_len_secs
+        let cache = self.cache.lock().unwrap();
+        cache.bottom_cache_time.elapsed().as_secs_f64() > expire_len_secs
     }
 
     pub fn get_bottom(
-        &mut self,
+        &self,
         count: usize,
         no_cache: bool,
         expire_len_secs: f64,
     ) -> Vec<(usize, Entry<K, V>)> {
-        self.cache.bottom_requested = self.cache.bottom_requested || !no_cache;
+        let mut cache = self.cache.lock().unwrap();
+        cache.bottom_requested = cache.bottom_requested || !no_cache;
 
-        let cache_unusable = self.cache.bottom_cache.as_ref().is_none()
-            || count > self.cache.bottom_cache.as_ref().unwrap().len()
-            || self.is_bottom_cache_expired(expire_len_secs);
+        let cache_unusable = cache.bottom_cache.is_none()
+            || cache.bottom_cache_time.elapsed().as_secs_f64() > expire_len_secs;
         if no_cache || cache_unusable {
             let bottom = self.get_bottom_cacheless(count);
-            if self.cache.bottom_requested && cache_unusable {
-                self.cache.bottom_cache = Some(bottom.clone());
-                self.cache.bottom_cache_time = Instant::now();
+            if cache.bottom_requested && cache_unusable {
+                cache.bottom_cache = Some(bottom.clone());
+                cache.bottom_cache_time = Instant::now();
             }
             bottom
         } else {
-            self.cache.bottom_cache.as_ref().unwrap()[0..count].to_vec()
+            self.extend_bottom_cache(&mut cache, count);
+            let bottom = cache.bottom_cache.as_ref().unwrap();
+            bottom[0..count.min(bottom.len())].to_vec()
         }
     }
 
-    pub fn get_bottom_cacheless(&self, count: usize) -> Vec<(usize, Entry<K, V>)> {
-        let mut ret = Vec::with_capacity(count);
-
-        let mut cursor = self.tree.cursor();
-
-        for _i in 0..count {
-            cursor.move_next();
-            if let Some(v) = cursor.get_value() {
-                let entry = v.clone();
-                ret.push((cursor.get_index().unwrap() + 1, entry));
-            } else {
-                break;
-            }
+    // Lazily grow the bottom cache so it covers at least `count` entries, if the board has them.
+    fn extend_bottom_cache(&self, cache: &mut Cache<K, V>, count: usize) {
+        let bottom = cache.bottom_cache.as_mut().unwrap();
+        if count <= bottom.len() || bottom.len() >= self.tree.len() {
+            return;
         }
 
-        return ret;
+        let end = self.tree.len() - bottom.len();
+        bottom.extend(
+            self.tree
+                .range(..end)
+                .rev()
+                .take(count - bottom.len())
+                .map(|(i, v)| (i + 1, v.clone())),
+        );
+    }
+
+    pub fn get_bottom_cacheless(&self, count: usize) -> Vec<(usize, Entry<K, V>)> {
+        return self
+            .iter()
+            .rev()
+            .take(count)
+            .map(|(r, v)| (r, v.clone()))
+            .collect();
     }
 
     pub fn clear(&mut self) {
+        if self.rank_checkpoint == Some(RankCheckpoint::Reset) {
+            self.take_rank_checkpoint();
+        }
+
         self.tree.clear();
         self.map.clear();
+        self.cache.get_mut().unwrap().invalidate();
+    }
+
+    // Swaps in the entries of other, keeping this board's settings and kind of index. Deltas see
+    // it as the board being cleared and every entry added.
+    pub fn replace_entries(&mut self, other: Self) {
+        self.clear();
+
+        let _ = self.tree.join(other.tree);
+        for (_, entry) in self.tree.iter() {
+            self.map.insert(entry.key.clone(), entry.clone());
+        }
     }
 
     pub fn new() -> Self {
         Self {
-            tree: Tree::new(),
+            tree: I::default(),
             map: DiffMap::new(),
             size_cap: None,
-            cache: Cache {
-                top_cache: None,
-                bottom_cache: None,
-                top_cache_time: Instant::now(),
-                bottom_cache_time: Instant::now(),
-                top_requested: false,
-                bottom_requested: false,
-            },
+            cache: Mutex::new(Cache::new()),
+            rank_checkpoint: None,
+            previous_ranks: Arc::new(HashMap::new()),
+            checkpoint_time: Instant::now(),
+            event_depth: None,
+            events: Vec::new(),
         }
     }
 
@@ -310,228 +641,427 @@ impl<K: PartialOrd + Eq + Hash + Sized + Default + Clone, V: PartialOrd + Defaul
         after: usize,
     ) -> Option<Vec<(usize, Entry<K, V>)>> {
         let entry = self.map.get(id)?;
-        let mut ret = Vec::with_capacity(before + after + 1);
-
-        let mut cursor = self.tree.seek_val(&entry)?;
-        let mut cursor2 = cursor.clone();
-
-        for _i in 0..before {
-            let val = cursor2.move_next();
-            if let Some(v) = val {
-                let entry = v.clone();
-                ret.push((cursor2.get_index().unwrap() + 1, entry));
-            } else {
-                break;
-            }
-        }
-
-        ret.reverse();
-
-        ret.push((cursor.get_index()? + 1, cursor.get_value().unwrap().clone()));
-
-        for _i in 0..after {
-            let val = cursor.move_prev();
-            if let Some(v) = val {
-                let entry = v.clone();
-                ret.push((cursor.get_index().unwrap() + 1, entry));
-            } else {
-                break;
-            }
+        let (index, found) = self.tree.index_of(&entry);
+        if !found {
+            return None;
         }
 
-        ret.shrink_to_fit();
-
-        return Some(ret);
+        let start = index.saturating_sub(before);
+        let end = index.saturating_add(after);
+        return Some(
+            self.tree
+                .range(start..=end)
+                .map(|(i, v)| (i + 1, v.clone()))
+                .collect(),
+        );
     }
 
     pub fn get_after(&self, id: &K, count: usize) -> Option<Vec<(usize, Entry<K, V>)>> {
         let entry = self.map.get(id)?;
-        let mut ret = Vec::with_capacity(count);
-
-        let mut cursor = self.tree.seek_val(&entry).unwrap();
-        for _i in 0..count {
-            cursor.move_prev();
-            if let Some(v) = cursor.get_value() {
-                let entry = v.clone();
-                ret.push((cursor.get_index().unwrap() + 1, entry));
-            } else {
-                break;
-            }
-        }
-
-        ret.shrink_to_fit();
-
-        Some(ret)
+        let (index, _) = self.tree.index_of(&entry);
+
+        Some(
+            self.tree
+                .iter_from_index(index + 1)
+                .take(count)
+                .map(|(i, v)| (i + 1, v.clone()))
+                .collect(),
+        )
     }
 
     pub fn get_range(&self, start_rank: usize, end_rank: usize) -> Vec<(usize, Entry<K, V>)> {
-        if end_rank < start_rank {
-            return Vec::new();
-        }
-        let num = end_rank - start_rank + 1;
-        let mut ret = Vec::with_capacity(num);
-
-        let mut cursor = match self.tree.seek_index(start_rank - 1) {
-            Some(v) => v,
-            None => return Vec::new(),
-        };
-        for _i in 0..num {
-            if let Some(v) = cursor.get_value() {
-                let entry = v.clone();
-                ret.push((cursor.get_index().unwrap() + 1, entry));
-                cursor.move_prev();
-            } else {
-                break;
-            }
-        }
-
-        ret.shrink_to_fit();
-
-        return ret;
+        return self
+            .range(start_rank..=end_rank)
+            .map(|(r, v)| (r, v.clone()))
+            .collect();
     }
 
+    // the entries ranked just above id, nearest first
     pub fn get_before(&self, id: &K, count: usize) -> Option<Vec<(usize, Entry<K, V>)>> {
         let entry = self.map.get(id)?;
-        let mut ret = Vec::with_capacity(count);
-
-        let mut cursor = self.tree.seek_val(&entry).unwrap();
-        for _i in 0..count {
-            cursor.move_next();
-            if let Some(v) = cursor.get_value() {
-                let entry = v.clone();
-                ret.push((cursor.get_index().unwrap() + 1, entry));
-            } else {
-                break;
-            }
-        }
-
-        ret.shrink_to_fit();
-
-        Some(ret)
+        let (index, _) = self.tree.index_of(&entry);
+
+        Some(
+            self.tree
+                .range(..index)
+                .rev()
+                .take(count)
+                .map(|(i, v)| (i + 1, v.clone()))
+                .collect(),
+        )
     }
 
-    pub fn from_tree(tree: Tree<Entry<K, V>>) -> Self {
+    pub fn from_tree(tree: I) -> Self {
         let mut map = DiffMap::with_capacity(tree.len());
-
-        let mut cursor = tree.cursor();
-        cursor.move_next();
-
-        while !cursor.is_at_end() {
-            map.insert(
-                cursor.get_value().unwrap().key.clone(),
-                cursor.get_value().unwrap().clone(),
-            );
-            cursor.move_next();
+        for (_, entry) in tree.iter() {
+            map.insert(entry.key.clone(), entry.clone());
         }
 
         map.shrink_to_fit();
+        // building the map isn't a change to the board
+        let _ = map.take_changes();
 
         Self {
             tree: tree,
             map: map,
             size_cap: None,
-            cache: Cache {
-                top_cache: None,
-                bottom_cache: None,
-                top_cache_time: Instant::now(),
-                bottom_cache_time: Instant::now(),
-                top_requested: false,
-                bottom_requested: false,
-            },
+            cache: Mutex::new(Cache::new()),
+            rank_checkpoint: None,
+            previous_ranks: Arc::new(HashMap::new()),
+            checkpoint_time: Instant::now(),
+            event_depth: None,
+            events: Vec::new(),
         }
     }
 
     pub fn from_map(map: HashMap<K, Entry<K, V>>) -> Self {
-        let mut tree = Tree::new();
-
-        for (_, elem) in map.iter() {
-            tree.insert(elem.clone());
-        }
+        let mut saved = SavedEntries::from_map(&map);
+        saved.sort();
 
         Self {
-            tree: tree,
+            tree: I::from_sorted(saved.0),
             map: DiffMap::from_map(map),
             size_cap: None,
-            cache: Cache {
-                top_cache: None,
-                bottom_cache: None,
-                top_cache_time: Instant::now(),
-                bottom_cache_time: Instant::now(),
-                top_requested: false,
-                bottom_requested: false,
-            },
+            cache: Mutex::new(Cache::new()),
+            rank_checkpoint: None,
+            previous_ranks: Arc::new(HashMap::new()),
+            checkpoint_time: Instant::now(),
+            event_depth: None,
+            events: Vec::new(),
         }
     }
 
-    pub fn from_map_prog(map: HashMap<K, Entry<K, V>>, prog: impl Fn(usize) -> ()) -> Self {
-        let mut tree = Tree::new();
+    pub fn from_saved(saved: SavedEntries<K, V>) -> Self {
+        Self::from_saved_prog(saved, |_| {})
+    }
 
-        for (_, elem) in map.iter() {
-            tree.insert(elem.clone());
-            prog(1);
+    pub fn from_saved_prog(mut saved: SavedEntries<K, V>, prog: impl Fn(usize) -> ()) -> Self {
+        // saves from before entries were stored in order still need sorting
+        if !saved.is_sorted() {
+            saved.sort();
         }
 
+        let mut map = HashMap::with_capacity(saved.0.len());
+        let mut done = 0;
+        let tree = I::from_sorted(saved.0.into_iter().inspect(|entry| {
+            map.insert(entry.key.clone(), entry.clone());
+            done += 1;
+            if done % PROGRESS_STEP == 0 {
+                prog(PROGRESS_STEP);
+            }
+        }));
+        prog(done % PROGRESS_STEP);
+
         Self {
             tree: tree,
             map: DiffMap::from_map(map),
             size_cap: None,
-            cache: Cache {
-                top_cache: None,
-                bottom_cache: None,
-                top_cache_time: Instant::now(),
-                bottom_cache_time: Instant::now(),
-                top_requested: false,
-                bottom_requested: false,
-            },
+            cache: Mutex::new(Cache::new()),
+            rank_checkpoint: None,
+            previous_ranks: Arc::new(HashMap::new()),
+            checkpoint_time: Instant::now(),
+            event_depth: None,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn set_rank_checkpoint(&mut self, mode: Option<RankCheckpoint>) {
+        if mode.is_none() {
+            self.previous_ranks = Arc::new(HashMap::new());
+        }
+        self.rank_checkpoint = mode;
+    }
+
+    pub fn get_rank_checkpoint(&self) -> Option<RankCheckpoint> {
+        self.rank_checkpoint
+    }
+
+    // Record the current rank of every entry with a single in-order walk of the tree.
+    pub fn take_rank_checkpoint(&mut self) {
+        let mut ranks = HashMap::with_capacity(self.tree.len());
+
+        for (rank, v) in self.iter() {
+            ranks.insert(v.key.clone(), rank);
+        }
+
+        self.previous_ranks = Arc::new(ranks);
+        self.checkpoint_time = Instant::now();
+    }
+
+    pub fn is_rank_checkpoint_due(&self) -> bool {
+        match self.rank_checkpoint {
+            Some(RankCheckpoint::Interval(minutes)) => {
+                self.checkpoint_time.elapsed().as_secs() >= minutes * 60
+            }
+            _ => false,
+        }
+    }
+
+    pub fn get_previous_ranks(&self) -> Arc<HashMap<K, usize>> {
+        self.previous_ranks.clone()
+    }
+
+    pub fn set_previous_ranks(&mut self, ranks: HashMap<K, usize>) {
+        self.previous_ranks = Arc::new(ranks);
+        self.checkpoint_time = Instant::now();
+    }
+
+    pub fn get_rank_movement(&self, id: &K, rank: usize) -> Option<RankMovement> {
+        self.rank_checkpoint?;
+
+        let previous_rank = self.previous_ranks.get(id).copied();
+        Some(RankMovement {
+            previous_rank: previous_rank,
+            delta: previous_rank.map(|prev| prev as i64 - rank as i64),
+        })
+    }
+
+    // Record events for mutations, reporting rank changes down to rank `depth`. None turns tracking off.
+    pub fn set_event_tracking(&mut self, depth: Option<usize>) {
+        self.event_depth = depth;
+        self.events.clear();
+    }
+
+    // The events produced by the most recent add, update or removal.
+    pub fn take_events(&mut self) -> Vec<BoardEvent<K, V>> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn tracked_leader(&self) -> Option<Entry<K, V>> {
+        self.event_depth?;
+        self.tree.at_index(0).cloned()
+    }
+
+    fn record_rank(&mut self, entry: &Entry<K, V>, previous_rank: Option<usize>) {
+        let depth = match self.event_depth {
+            Some(v) => v,
+            None => return,
+        };
+
+        let rank = self.tree.index_of(entry).0 + 1;
+        if rank <= depth {
+            self.events.push(BoardEvent::Ranked {
+                entry: entry.clone(),
+                rank: rank,
+                previous_rank: previous_rank,
+            });
+        }
+    }
+
+    fn record_leader(&mut self, previous: Option<Entry<K, V>>) {
+        if self.event_depth.is_none() {
+            return;
+        }
+
+        if let Some(leader) = self.tree.at_index(0)
+            && previous.as_ref().is_none_or(|v| v.key != leader.key)
+        {
+            self.events.push(BoardEvent::NewLeader {
+                entry: leader.clone(),
+                previous: previous,
+            });
         }
     }
 
     pub fn get_min(&self) -> Option<V> {
-        let mut c = self.tree.cursor();
-        Some(c.move_next()?.points.clone())
+        Some(self.iter().next_back()?.1.points.clone())
+    }
+}
+
+// Boards with each index kind, selectable at runtime.
+impl<
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone + Send + 'static,
+    V: PartialOrd + Default + ?Sized + Clone + Send + 'static,
+> Board<K, V, AnyIndex<Entry<K, V>>>
+{
+    pub fn get_index_kind(&self) -> IndexKind {
+        self.tree.kind()
+    }
+
+    // Moves the entries into another kind of index, the map and caches stay as they are.
+    pub fn set_index_kind(&mut self, kind: IndexKind) {
+        let tree = mem::take(&mut self.tree);
+        self.tree = tree.into_kind(kind);
     }
 }
 
 unsafe impl<
     K: PartialOrd + Eq + Hash + Sized + Default + Clone + Send,
     V: PartialOrd + Default + ?Sized + Clone + Send,
-> Send for Board<K, V>
+    I: RankedIndex<Entry<K, V>> + Send,
+> Send for Board<K, V, I>
 {
 }
 unsafe impl<
     K: PartialOrd + Eq + Hash + Sized + Default + Clone + Sync,
     V: PartialOrd + Default + ?Sized + Clone + Sync,
-> Sync for Board<K, V>
+    I: RankedIndex<Entry<K, V>> + Sync,
+> Sync for Board<K, V, I>
 {
 }
 
-impl<K, V, Context> Decode<Context> for Board<K, V>
+impl<K, V, I, Context> Decode<Context> for Board<K, V, I>
 where
     K: PartialOrd + Eq + Hash + Sized + Default + Clone + Decode<Context>,
     V: PartialOrd + Default + ?Sized + Clone + Decode<Context>,
+    I: RankedIndex<Entry<K, V>>,
 {
     fn decode<D: bincode::de::Decoder>(decoder: &mut D) -> Result<Self, bincode::error::DecodeError>
     where
         K: Decode<<D as Decoder>::Context>,
         V: Decode<<D as Decoder>::Context>,
     {
-        let map = bincode::Decode::decode(decoder)?;
+        let saved = bincode::Decode::decode(decoder)?;
 
-        Ok(Board::from_map(map))
+        Ok(Board::from_saved(saved))
     }
 }
 
-impl<K, V> Encode for Board<K, V>
+impl<K, V, I> Encode for Board<K, V, I>
 where
     K: PartialOrd + Eq + Hash + Sized + Default + Clone + Encode,
     V: PartialOrd + Default + ?Sized + Clone + Encode,
+    I: RankedIndex<Entry<K, V>>,
 {
     fn encode<E: bincode::enc::Encoder>(
         &self,
         encoder: &mut E,
     ) -> Result<(), bincode::error::EncodeError> {
-        bincode::Encode::encode(&self.map, encoder)?;
+        bincode::Encode::encode(&(self.tree.len() as u64), encoder)?;
+        for (_, entry) in self.iter().rev() {
+            encode_saved_entry(entry, encoder)?;
+        }
+
+        Ok(())
+    }
+}
+
+// The entries changed since the last delta, as they are at the time it was taken. None marks an
+// entry that was removed. Applying it to the board as it was before gives the board as it is.
+pub struct BoardDelta<K, V>
+where
+    K: PartialOrd + Default,
+    V: PartialOrd + Default,
+{
+    pub cleared: bool,
+    pub changes: Vec<(K, Option<Entry<K, V>>)>,
+}
+
+impl<K: PartialOrd + Default, V: PartialOrd + Default> BoardDelta<K, V> {
+    pub fn is_empty(&self) -> bool {
+        !self.cleared && self.changes.is_empty()
+    }
+}
 
+impl<K, V> Encode for BoardDelta<K, V>
+where
+    K: PartialOrd + Default + Encode,
+    V: PartialOrd + Default + Encode,
+{
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        bincode::Encode::encode(&self.cleared, encoder)?;
+        bincode::Encode::encode(&self.changes, encoder)?;
         Ok(())
     }
 }
+
+impl<K, V, Context> Decode<Context> for BoardDelta<K, V>
+where
+    K: PartialOrd + Default + Decode<Context>,
+    V: PartialOrd + Default + Decode<Context>,
+{
+    fn decode<D: Decoder<Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        Ok(Self {
+            cleared: bincode::Decode::decode(decoder)?,
+            changes: bincode::Decode::decode(decoder)?,
+        })
+    }
+}
+
+// Entries as stored in a .board file, as (key, entry) pairs from lowest to highest. This is the
+// same encoding as the HashMap older saves used, so those still decode and are sorted on load.
+pub struct SavedEntries<K, V>(pub Vec<Entry<K, V>>)
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone,
+    V: PartialOrd + Default + ?Sized + Clone;
+
+impl<K, V> SavedEntries<K, V>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone,
+    V: PartialOrd + Default + ?Sized + Clone,
+{
+    // Copies entries out of a map snapshot, sort afterwards so the snapshot can be released first.
+    pub fn from_map(map: &HashMap<K, Entry<K, V>>) -> Self {
+        Self(map.values().cloned().collect())
+    }
+
+    pub fn sort(&mut self) {
+        self.0.sort_unstable();
+    }
+
+    pub fn is_sorted(&self) -> bool {
+        self.0.windows(2).all(|pair| pair[0] < pair[1])
+    }
+}
+
+fn encode_saved_entry<K, V, E>(
+    entry: &Entry<K, V>,
+    encoder: &mut E,
+) -> Result<(), bincode::error::EncodeError>
+where
+    K: PartialOrd + Default + Encode,
+    V: PartialOrd + Default + Encode,
+    E: bincode::enc::Encoder,
+{
+    bincode::Encode::encode(&entry.key, encoder)?;
+    bincode::Encode::encode(entry, encoder)
+}
+
+impl<K, V> Encode for SavedEntries<K, V>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone + Encode,
+    V: PartialOrd + Default + ?Sized + Clone + Encode,
+{
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        bincode::Encode::encode(&(self.0.len() as u64), encoder)?;
+        for entry in self.0.iter() {
+            encode_saved_entry(entry, encoder)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<K, V, Context> Decode<Context> for SavedEntries<K, V>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone + Decode<Context>,
+    V: PartialOrd + Default + ?Sized + Clone + Decode<Context>,
+{
+    fn decode<D: bincode::de::Decoder>(decoder: &mut D) -> Result<Self, bincode::error::DecodeError>
+    where
+        K: Decode<<D as Decoder>::Context>,
+        V: Decode<<D as Decoder>::Context>,
+    {
+        let len: u64 = bincode::Decode::decode(decoder)?;
+        let len = usize::try_from(len)
+            .map_err(|_| bincode::error::DecodeError::OutsideUsizeRange(len))?;
+        decoder.claim_container_read::<(K, Entry<K, V>)>(len)?;
+
+        let mut entries = Vec::with_capacity(len);
+        for _ in 0..len {
+            decoder.unclaim_bytes_read(core::mem::size_of::<(K, Entry<K, V>)>());
+            let _key: K = bincode::Decode::decode(decoder)?;
+            entries.push(bincode::Decode::decode(decoder)?);
+        }
+
+        Ok(Self(entries))
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     hash::Hash,
+    mem,
     sync::{Arc, Mutex, RwLock, RwLockReadGuard},
 };
 
@@ -23,6 +24,16 @@ where
     num_borrows: usize,
     cleared: bool,
     diff: HashMap<K, Option<V>>,
+    // keys changed since the last take_changes, kept whether or not the map is borrowed
+    changed: HashSet<K>,
+    changed_cleared: bool,
+}
+
+// What changed in a DiffMap since the last call to take_changes. When cleared is set the map
+// was cleared first and keys only holds what changed after that.
+pub struct Changes<K> {
+    pub cleared: bool,
+    pub keys: HashSet<K>,
 }
 
 pub struct SnapshotBorrow<K, V>
@@ -44,6 +55,8 @@ where
                 num_borrows: 0,
                 cleared: false,
                 diff: HashMap::new(),
+                changed: HashSet::new(),
+                changed_cleared: false,
             })),
             map: Arc::new(RwLock::new(HashMap::new())),
         }
@@ -55,6 +68,8 @@ where
                 num_borrows: 0,
                 cleared: false,
                 diff: HashMap::new(),
+                changed: HashSet::new(),
+                changed_cleared: false,
             })),
             map: Arc::new(RwLock::new(map)),
         }
@@ -66,6 +81,8 @@ where
                 num_borrows: 0,
                 cleared: false,
                 diff: HashMap::new(),
+                changed: HashSet::new(),
+                changed_cleared: false,
             })),
             map: Arc::new(RwLock::new(HashMap::with_capacity(capacity))),
         }
@@ -102,6 +119,7 @@ where
 
     pub fn insert(&mut self, key: K, val: V) -> Option<V> {
         let mut inner = self.inner.lock().unwrap();
+        inner.changed.insert(key.clone());
 
         let ret = if inner.num_borrows == 0 {
             self.map.write().unwrap().insert(key, val)
@@ -125,6 +143,7 @@ where
 
     pub fn remove(&mut self, key: &K) -> Option<V> {
         let mut inner = self.inner.lock().unwrap();
+        inner.changed.insert(key.clone());
 
         let ret = if inner.num_borrows == 0 {
             self.map.write().unwrap().remove(key)
@@ -170,6 +189,8 @@ where
 
     pub fn clear(&mut self) {
         let mut inner = self.inner.lock().unwrap();
+        inner.changed.clear();
+        inner.changed_cleared = true;
 
         if inner.num_borrows == 0 {
             self.map.write().unwrap().clear();
@@ -196,6 +217,29 @@ where
         return ret;
     }
 
+    pub fn has_changes(&self) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.changed_cleared || !inner.changed.is_empty()
+    }
+
+    pub fn take_changes(&mut self) -> Changes<K> {
+        let mut inner = self.inner.lock().unwrap();
+        Changes {
+            cleared: mem::take(&mut inner.changed_cleared),
+            keys: mem::take(&mut inner.changed),
+        }
+    }
+
+    // Marks keys as changed again, for changes taken that couldn't be saved.
+    pub fn restore_changes(&mut self, changes: Changes<K>) {
+        let mut inner = self.inner.lock().unwrap();
+        if changes.cleared && !inner.changed_cleared {
+            // keys changed since were changed after the clear as well
+            inner.changed_cleared = true;
+        }
+        inner.changed.extend(changes.keys);
+    }
+
     pub fn is_borrowed(&self) -> bool {
         self.inner.lock().unwrap().num_borrows > 0
     }
@@ -1,8 +1,15 @@
 mod board;
 mod diff_map;
 mod entry;
+mod ranked_index;
+mod skip_list;
 mod tree;
 
-pub use board::Board;
+pub use board::{Board, BoardDelta, BoardEvent, RankCheckpoint, RankMovement, SavedEntries};
 pub use entry::Entry;
+pub use ranked_index::{AnyIndex, IndexKind, RankedCursor, RankedIndex};
+pub use skip_list::SkipList;
 pub use tree::Tree;
+
+#[cfg(test)]
+mod test;
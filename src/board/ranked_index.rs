@@ -0,0 +1,321 @@
+use std::{mem, ops::RangeBounds};
+
+use serde::{Deserialize, Serialize};
+
+use super::skip_list::{self, SkipList};
+use super::tree::{self, Tree};
+
+pub trait RankedCursor<V> {
+    // Moves to the next higher value, from the end this is the lowest value.
+    fn move_next(&mut self) -> Option<&V>;
+    // Moves to the next lower value, from the end this is the highest value.
+    fn move_prev(&mut self) -> Option<&V>;
+    fn get_value(&self) -> Option<&V>;
+    fn get_index(&mut self) -> Option<usize>;
+    fn is_at_end(&self) -> bool;
+}
+
+// Unique values kept in order that can also be looked up by index. Index 0 is the highest value.
+pub trait RankedIndex<V: Ord>: Default + Sized {
+    type Cursor<'a>: RankedCursor<V>
+    where
+        Self: 'a,
+        V: 'a;
+    type Iter<'a>: DoubleEndedIterator<Item = (usize, &'a V)> + ExactSizeIterator
+    where
+        Self: 'a,
+        V: 'a;
+
+    // Values must be unique and in ascending order.
+    fn from_sorted<I: IntoIterator<Item = V>>(values: I) -> Self
+    where
+        I::IntoIter: ExactSizeIterator;
+
+    fn insert(&mut self, val: V) -> bool;
+    fn remove(&mut self, val: &V) -> Option<V>;
+    // None if old_val is missing or new_val is already present.
+    fn replace(&mut self, old_val: &V, new_val: V) -> Option<V>;
+    // The index of val, or the index it would be inserted at if it is missing.
+    fn index_of(&self, val: &V) -> (usize, bool);
+    fn at_index(&self, index: usize) -> Option<&V>;
+    fn len(&self) -> usize;
+    fn clear(&mut self);
+    // Starts at the end, before the highest and after the lowest value.
+    fn cursor(&self) -> Self::Cursor<'_>;
+    fn seek_index(&self, index: usize) -> Option<Self::Cursor<'_>>;
+    fn range<R: RangeBounds<usize>>(&self, indices: R) -> Self::Iter<'_>;
+    fn memory_usage(&self) -> usize;
+    // every value, lowest first
+    fn into_sorted(self) -> Vec<V>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn iter(&self) -> Self::Iter<'_> {
+        self.range(..)
+    }
+
+    fn iter_from_index(&self, index: usize) -> Self::Iter<'_> {
+        self.range(index..)
+    }
+
+    // The split and join below rebuild from every value. Implementations that can relink in
+    // place should override them.
+
+    // Moves every value from index onwards (the lowest values) out, keeping the first index.
+    fn split_at_index(&mut self, index: usize) -> Self {
+        let mut low = mem::take(self).into_sorted();
+        let high = low.split_off(low.len().saturating_sub(index));
+        *self = Self::from_sorted(high);
+        Self::from_sorted(low)
+    }
+
+    // Moves every value below val out.
+    fn split_at_value(&mut self, val: &V) -> Self {
+        let (index, found) = self.index_of(val);
+        self.split_at_index(index + found as usize)
+    }
+
+    // Joins values that all lie above or below these ones, otherwise other is given back.
+    fn join(&mut self, other: Self) -> Result<(), Self> {
+        if other.is_empty() {
+            return Ok(());
+        }
+        if self.is_empty() {
+            *self = other;
+            return Ok(());
+        }
+
+        let other_is_low = other.at_index(0) < self.at_index(self.len() - 1);
+        if !other_is_low && other.at_index(other.len() - 1) <= self.at_index(0) {
+            return Err(other);
+        }
+
+        let (mut low, high) = match other_is_low {
+            true => (other.into_sorted(), mem::take(self).into_sorted()),
+            false => (mem::take(self).into_sorted(), other.into_sorted()),
+        };
+        low.extend(high);
+        *self = Self::from_sorted(low);
+        Ok(())
+    }
+}
+
+// Which structure a board keeps its entries in, chosen per board in boards.json.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexKind {
+    #[default]
+    Avl,
+    SkipList,
+}
+
+// A ranked index picked at runtime, so boards with different kinds can sit side by side.
+#[derive(Clone)]
+pub enum AnyIndex<V: Ord + Default> {
+    Avl(Tree<V>),
+    SkipList(SkipList<V>),
+}
+
+pub enum AnyCursor<'a, V: Ord + Default + Clone> {
+    Avl(tree::Cursor<'a, V>),
+    SkipList(skip_list::Cursor<'a, V>),
+}
+
+pub enum AnyIter<'a, V: Ord + Default> {
+    Avl(tree::Iter<'a, V>),
+    SkipList(skip_list::Iter<'a, V>),
+}
+
+macro_rules! dispatch {
+    ($value:expr, $enum:ident, $inner:ident => $result:expr) => {
+        match $value {
+            $enum::Avl($inner) => $result,
+            $enum::SkipList($inner) => $result,
+        }
+    };
+}
+
+impl<V: Ord + Default + Clone + Send + 'static> AnyIndex<V> {
+    pub fn new(kind: IndexKind) -> Self {
+        match kind {
+            IndexKind::Avl => AnyIndex::Avl(Tree::new()),
+            IndexKind::SkipList => AnyIndex::SkipList(SkipList::new()),
+        }
+    }
+
+    pub fn kind(&self) -> IndexKind {
+        match self {
+            AnyIndex::Avl(_) => IndexKind::Avl,
+            AnyIndex::SkipList(_) => IndexKind::SkipList,
+        }
+    }
+
+    // Moves every value into an index of another kind.
+    pub fn into_kind(self, kind: IndexKind) -> Self {
+        if self.kind() == kind {
+            return self;
+        }
+
+        let values = self.into_sorted();
+        match kind {
+            IndexKind::Avl => AnyIndex::Avl(Tree::from_sorted(values)),
+            IndexKind::SkipList => AnyIndex::SkipList(SkipList::from_sorted(values)),
+        }
+    }
+}
+
+impl<V: Ord + Default + Clone + Send + 'static> Default for AnyIndex<V> {
+    fn default() -> Self {
+        Self::new(IndexKind::default())
+    }
+}
+
+impl<V: Ord + Default + Clone + Send + 'static> RankedIndex<V> for AnyIndex<V> {
+    type Cursor<'a>
+        = AnyCursor<'a, V>
+    where
+        Self: 'a;
+    type Iter<'a>
+        = AnyIter<'a, V>
+    where
+        Self: 'a;
+
+    // built as the default kind, use into_kind to change it
+    fn from_sorted<I: IntoIterator<Item = V>>(values: I) -> Self
+    where
+        I::IntoIter: ExactSizeIterator,
+    {
+        AnyIndex::Avl(Tree::from_sorted(values))
+    }
+
+    fn insert(&mut self, val: V) -> bool {
+        dispatch!(self, AnyIndex, index => index.insert(val))
+    }
+
+    fn remove(&mut self, val: &V) -> Option<V> {
+        dispatch!(self, AnyIndex, index => index.remove(val))
+    }
+
+    fn replace(&mut self, old_val: &V, new_val: V) -> Option<V> {
+        dispatch!(self, AnyIndex, index => index.replace(old_val, new_val))
+    }
+
+    fn index_of(&self, val: &V) -> (usize, bool) {
+        dispatch!(self, AnyIndex, index => index.index_of(val))
+    }
+
+    fn at_index(&self, at: usize) -> Option<&V> {
+        dispatch!(self, AnyIndex, index => index.at_index(at))
+    }
+
+    fn len(&self) -> usize {
+        dispatch!(self, AnyIndex, index => index.len())
+    }
+
+    fn clear(&mut self) {
+        dispatch!(self, AnyIndex, index => index.clear())
+    }
+
+    fn cursor(&self) -> AnyCursor<'_, V> {
+        match self {
+            AnyIndex::Avl(index) => AnyCursor::Avl(index.cursor()),
+            AnyIndex::SkipList(index) => AnyCursor::SkipList(RankedIndex::cursor(index)),
+        }
+    }
+
+    fn seek_index(&self, at: usize) -> Option<AnyCursor<'_, V>> {
+        match self {
+            AnyIndex::Avl(index) => index.seek_index(at).map(AnyCursor::Avl),
+            AnyIndex::SkipList(index) => {
+                RankedIndex::seek_index(index, at).map(AnyCursor::SkipList)
+            }
+        }
+    }
+
+    fn range<R: RangeBounds<usize>>(&self, indices: R) -> AnyIter<'_, V> {
+        match self {
+            AnyIndex::Avl(index) => AnyIter::Avl(index.range(indices)),
+            AnyIndex::SkipList(index) => AnyIter::SkipList(index.range(indices)),
+        }
+    }
+
+    fn memory_usage(&self) -> usize {
+        dispatch!(self, AnyIndex, index => index.memory_usage())
+    }
+
+    fn into_sorted(self) -> Vec<V> {
+        dispatch!(self, AnyIndex, index => index.into_sorted())
+    }
+
+    fn split_at_index(&mut self, at: usize) -> Self {
+        match self {
+            AnyIndex::Avl(index) => AnyIndex::Avl(index.split_at_index(at)),
+            AnyIndex::SkipList(index) => AnyIndex::SkipList(index.split_at_index(at)),
+        }
+    }
+
+    fn split_at_value(&mut self, val: &V) -> Self {
+        match self {
+            AnyIndex::Avl(index) => AnyIndex::Avl(index.split_at_value(val)),
+            AnyIndex::SkipList(index) => AnyIndex::SkipList(index.split_at_value(val)),
+        }
+    }
+
+    // other is converted to this kind first if they differ
+    fn join(&mut self, other: Self) -> Result<(), Self> {
+        let other = other.into_kind(self.kind());
+        match (self, other) {
+            (AnyIndex::Avl(index), AnyIndex::Avl(other)) => {
+                index.join(other).map_err(AnyIndex::Avl)
+            }
+            (AnyIndex::SkipList(index), AnyIndex::SkipList(other)) => {
+                index.join(other).map_err(AnyIndex::SkipList)
+            }
+            _ => unreachable!("Index was converted to the same kind."),
+        }
+    }
+}
+
+impl<'a, V: Ord + Default + Clone> RankedCursor<V> for AnyCursor<'a, V> {
+    fn move_next(&mut self) -> Option<&V> {
+        dispatch!(self, AnyCursor, cursor => cursor.move_next())
+    }
+
+    fn move_prev(&mut self) -> Option<&V> {
+        dispatch!(self, AnyCursor, cursor => cursor.move_prev())
+    }
+
+    fn get_value(&self) -> Option<&V> {
+        dispatch!(self, AnyCursor, cursor => cursor.get_value())
+    }
+
+    fn get_index(&mut self) -> Option<usize> {
+        dispatch!(self, AnyCursor, cursor => cursor.get_index())
+    }
+
+    fn is_at_end(&self) -> bool {
+        dispatch!(self, AnyCursor, cursor => cursor.is_at_end())
+    }
+}
+
+impl<'a, V: Ord + Default> Iterator for AnyIter<'a, V> {
+    type Item = (usize, &'a V);
+
+    fn next(&mut self) -> Option<(usize, &'a V)> {
+        dispatch!(self, AnyIter, iter => iter.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        dispatch!(self, AnyIter, iter => iter.size_hint())
+    }
+}
+
+impl<'a, V: Ord + Default> DoubleEndedIterator for AnyIter<'a, V> {
+    fn next_back(&mut self) -> Option<(usize, &'a V)> {
+        dispatch!(self, AnyIter, iter => iter.next_back())
+    }
+}
+
+impl<'a, V: Ord + Default> ExactSizeIterator for AnyIter<'a, V> {}
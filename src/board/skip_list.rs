@@ -0,0 +1,573 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    iter::FusedIterator,
+    mem,
+    ops::{Bound, RangeBounds},
+};
+
+use super::ranked_index::{RankedCursor, RankedIndex};
+
+// An indexable skip list. Every link stores how many values it skips, so values can be found by
+// index as well as by value. Values are linked from lowest to highest, while indices count from
+// the highest value down, the same as in the tree.
+type Link = u32;
+const NIL: Link = u32::MAX;
+// the head is always the first slot, it holds no value and has every level
+const HEAD: Link = 0;
+const MAX_LEVEL: usize = 16;
+// one in LEVEL_ODDS nodes at a level also reaches the next level
+const LEVEL_ODDS: u64 = 4;
+
+#[derive(Clone, Copy)]
+struct Step {
+    next: Link,
+    // how many values this link moves forward, counting a virtual node after the highest value
+    width: u32,
+}
+
+#[derive(Clone)]
+struct SkipNode<V> {
+    val: V,
+    prev: Link,
+    steps: Vec<Step>,
+}
+
+#[derive(Clone)]
+pub struct SkipList<V: Ord + Default> {
+    nodes: Vec<SkipNode<V>>,
+    free: Vec<Link>,
+    tail: Link,
+    level: usize,
+    len: usize,
+    rng: u64,
+}
+
+impl<V: Ord + Default> SkipList<V> {
+    pub fn new() -> Self {
+        let seed = RandomState::new().build_hasher().finish();
+        let step = Step {
+            next: NIL,
+            width: 1,
+        };
+        Self {
+            nodes: vec![SkipNode {
+                val: V::default(),
+                prev: NIL,
+                steps: vec![step; MAX_LEVEL],
+            }],
+            free: Vec::new(),
+            tail: HEAD,
+            level: 0,
+            len: 0,
+            // xorshift gets stuck on zero
+            rng: seed | 1,
+        }
+    }
+
+    fn random_level(&mut self) -> usize {
+        let mut level = 1;
+        while level < MAX_LEVEL {
+            self.rng ^= self.rng << 13;
+            self.rng ^= self.rng >> 7;
+            self.rng ^= self.rng << 17;
+            if !self.rng.is_multiple_of(LEVEL_ODDS) {
+                break;
+            }
+            level += 1;
+        }
+        level
+    }
+
+    fn alloc(&mut self, val: V, level: usize) -> Link {
+        let step = Step {
+            next: NIL,
+            width: 0,
+        };
+        let node = SkipNode {
+            val: val,
+            prev: NIL,
+            steps: vec![step; level],
+        };
+
+        if let Some(link) = self.free.pop() {
+            self.nodes[link as usize] = node;
+            return link;
+        }
+
+        if self.nodes.len() >= NIL as usize {
+            panic!("Skip list cannot hold more than {} entries!", NIL - 1);
+        }
+        self.nodes.push(node);
+        (self.nodes.len() - 1) as Link
+    }
+
+    fn release(&mut self, link: Link) -> V {
+        let node = &mut self.nodes[link as usize];
+        node.steps = Vec::new();
+        self.free.push(link);
+        mem::take(&mut node.val)
+    }
+
+    fn step(&self, link: Link, level: usize) -> Step {
+        self.nodes[link as usize].steps[level]
+    }
+
+    fn step_mut(&mut self, link: Link, level: usize) -> &mut Step {
+        &mut self.nodes[link as usize].steps[level]
+    }
+
+    fn val(&self, link: Link) -> &V {
+        &self.nodes[link as usize].val
+    }
+
+    // For every level, the last node holding a value below val and its position, where the
+    // head is position 0 and the lowest value is position 1.
+    fn search(&self, val: &V) -> ([Link; MAX_LEVEL], [usize; MAX_LEVEL]) {
+        let mut update = [HEAD; MAX_LEVEL];
+        let mut position = [0; MAX_LEVEL];
+        let mut node = HEAD;
+        let mut pos = 0;
+
+        for level in (0..self.level).rev() {
+            loop {
+                let step = self.step(node, level);
+                if step.next == NIL || *self.val(step.next) >= *val {
+                    break;
+                }
+                pos += step.width as usize;
+                node = step.next;
+            }
+            update[level] = node;
+            position[level] = pos;
+        }
+
+        (update, position)
+    }
+
+    fn find(&self, val: &V) -> Option<Link> {
+        let (update, _) = self.search(val);
+        match self.step(update[0], 0).next {
+            NIL => None,
+            link if *self.val(link) == *val => Some(link),
+            _ => None,
+        }
+    }
+
+    // position counts from 1 at the lowest value
+    fn node_at_position(&self, position: usize) -> Option<Link> {
+        if position == 0 || position > self.len {
+            return None;
+        }
+
+        let mut node = HEAD;
+        let mut pos = 0;
+        for level in (0..self.level).rev() {
+            loop {
+                let step = self.step(node, level);
+                if step.next == NIL || pos + step.width as usize > position {
+                    break;
+                }
+                pos += step.width as usize;
+                node = step.next;
+            }
+            if pos == position {
+                return Some(node);
+            }
+        }
+
+        None
+    }
+
+    fn node_at_index(&self, index: usize) -> Option<Link> {
+        if index >= self.len {
+            return None;
+        }
+        self.node_at_position(self.len - index)
+    }
+
+    fn unlink(&mut self, node: Link, update: &[Link; MAX_LEVEL]) {
+        for (level, &before) in update.iter().enumerate().take(self.level) {
+            if self.step(before, level).next == node {
+                let skipped = self.step(node, level);
+                let step = self.step_mut(before, level);
+                step.width += skipped.width - 1;
+                step.next = skipped.next;
+            } else {
+                self.step_mut(before, level).width -= 1;
+            }
+        }
+
+        let prev = self.nodes[node as usize].prev;
+        match self.step(node, 0).next {
+            NIL => self.tail = prev,
+            next => self.nodes[next as usize].prev = prev,
+        }
+
+        while self.level > 0 && self.step(HEAD, self.level - 1).next == NIL {
+            self.level -= 1;
+        }
+        self.len -= 1;
+    }
+
+    pub fn validate(&self) {
+        let mut positions = vec![usize::MAX; self.nodes.len()];
+        positions[HEAD as usize] = 0;
+
+        let mut last = HEAD;
+        let mut node = self.step(HEAD, 0).next;
+        let mut position = 0;
+        while node != NIL {
+            position += 1;
+            positions[node as usize] = position;
+            if self.nodes[node as usize].prev != last {
+                panic!("Prev does not agree with next!");
+            }
+            if last != HEAD && *self.val(last) >= *self.val(node) {
+                panic!("Values are out of order!");
+            }
+            last = node;
+            node = self.step(node, 0).next;
+        }
+
+        if position != self.len {
+            panic!("Length does not match the number of values!");
+        }
+        if self.tail != last {
+            panic!("Tail is not the highest value!");
+        }
+        if self.free.len() + self.len + 1 != self.nodes.len() {
+            panic!("Nodes were lost from the arena!");
+        }
+
+        for level in 0..self.level {
+            let mut node = HEAD;
+            loop {
+                let step = self.step(node, level);
+                let next_position = match step.next {
+                    NIL => self.len + 1,
+                    next => positions[next as usize],
+                };
+                if next_position != positions[node as usize] + step.width as usize {
+                    panic!("Width does not match the values skipped!");
+                }
+                if step.next == NIL {
+                    break;
+                }
+                node = step.next;
+            }
+        }
+    }
+}
+
+impl<V: Ord + Default> Default for SkipList<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: Ord + Default> RankedIndex<V> for SkipList<V> {
+    type Cursor<'a>
+        = Cursor<'a, V>
+    where
+        Self: 'a;
+    type Iter<'a>
+        = Iter<'a, V>
+    where
+        Self: 'a;
+
+    fn from_sorted<I: IntoIterator<Item = V>>(values: I) -> Self
+    where
+        I::IntoIter: ExactSizeIterator,
+    {
+        let values = values.into_iter();
+        let mut list = Self::new();
+        list.nodes.reserve(values.len());
+
+        // the last node reaching each level and its position, new values are appended after them
+        let mut last = [HEAD; MAX_LEVEL];
+        let mut last_position = [0; MAX_LEVEL];
+
+        for (i, val) in values.enumerate() {
+            let position = i + 1;
+            let level = list.random_level();
+            let node = list.alloc(val, level);
+
+            for l in 0..level {
+                *list.step_mut(last[l], l) = Step {
+                    next: node,
+                    width: (position - last_position[l]) as u32,
+                };
+                last[l] = node;
+                last_position[l] = position;
+            }
+            list.nodes[node as usize].prev = list.tail;
+            list.tail = node;
+            list.level = list.level.max(level);
+            list.len = position;
+        }
+
+        for l in 0..list.level {
+            list.step_mut(last[l], l).width = (list.len + 1 - last_position[l]) as u32;
+        }
+
+        list
+    }
+
+    fn insert(&mut self, val: V) -> bool {
+        let (mut update, mut position) = self.search(&val);
+        let next = self.step(update[0], 0).next;
+        if next != NIL && *self.val(next) == val {
+            return false;
+        }
+
+        let level = self.random_level();
+        if level > self.level {
+            for l in self.level..level {
+                update[l] = HEAD;
+                position[l] = 0;
+                self.step_mut(HEAD, l).width = (self.len + 1) as u32;
+            }
+            self.level = level;
+        }
+
+        let node = self.alloc(val, level);
+        for l in 0..level {
+            let before = update[l];
+            let step = self.step(before, l);
+            // positions of the nodes before the new one at level 0 and at this level
+            let distance = position[0] - position[l];
+            *self.step_mut(node, l) = Step {
+                next: step.next,
+                width: step.width - distance as u32,
+            };
+            *self.step_mut(before, l) = Step {
+                next: node,
+                width: distance as u32 + 1,
+            };
+        }
+        for (l, &before) in update.iter().enumerate().take(self.level).skip(level) {
+            self.step_mut(before, l).width += 1;
+        }
+
+        self.nodes[node as usize].prev = update[0];
+        match self.step(node, 0).next {
+            NIL => self.tail = node,
+            next => self.nodes[next as usize].prev = node,
+        }
+        self.len += 1;
+
+        return true;
+    }
+
+    fn remove(&mut self, val: &V) -> Option<V> {
+        let (update, _) = self.search(val);
+        let node = self.step(update[0], 0).next;
+        if node == NIL || *self.val(node) != *val {
+            return None;
+        }
+
+        self.unlink(node, &update);
+        return Some(self.release(node));
+    }
+
+    fn replace(&mut self, old_val: &V, new_val: V) -> Option<V> {
+        self.find(old_val)?;
+        if self.find(&new_val).is_some() {
+            return None;
+        }
+
+        let old = self.remove(old_val);
+        self.insert(new_val);
+        return old;
+    }
+
+    fn index_of(&self, val: &V) -> (usize, bool) {
+        // position[0] is how many values are below val
+        let (update, position) = self.search(val);
+        match self.step(update[0], 0).next {
+            NIL => (self.len - position[0], false),
+            link if *self.val(link) == *val => (self.len - 1 - position[0], true),
+            _ => (self.len - position[0], false),
+        }
+    }
+
+    fn at_index(&self, index: usize) -> Option<&V> {
+        self.node_at_index(index).map(|link| self.val(link))
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    fn cursor(&self) -> Cursor<'_, V> {
+        Cursor {
+            list: self,
+            node: HEAD,
+            index: None,
+        }
+    }
+
+    fn seek_index(&self, index: usize) -> Option<Cursor<'_, V>> {
+        Some(Cursor {
+            list: self,
+            node: self.node_at_index(index)?,
+            index: Some(index),
+        })
+    }
+
+    fn range<R: RangeBounds<usize>>(&self, indices: R) -> Iter<'_, V> {
+        let start = match indices.start_bound() {
+            Bound::Included(&s) => s,
+            Bound::Excluded(&s) => s.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match indices.end_bound() {
+            Bound::Included(&e) => e.saturating_add(1),
+            Bound::Excluded(&e) => e,
+            Bound::Unbounded => usize::MAX,
+        }
+        .min(self.len);
+
+        if start >= end {
+            return Iter {
+                list: self,
+                front: NIL,
+                back: NIL,
+                front_index: start,
+                remaining: 0,
+            };
+        }
+
+        Iter {
+            list: self,
+            front: self.node_at_index(start).unwrap(),
+            back: self.node_at_index(end - 1).unwrap(),
+            front_index: start,
+            remaining: end - start,
+        }
+    }
+
+    fn memory_usage(&self) -> usize {
+        let steps: usize = self.nodes.iter().map(|node| node.steps.capacity()).sum();
+        self.nodes.capacity() * mem::size_of::<SkipNode<V>>()
+            + steps * mem::size_of::<Step>()
+            + self.free.capacity() * mem::size_of::<Link>()
+    }
+
+    fn into_sorted(mut self) -> Vec<V> {
+        let mut values = Vec::with_capacity(self.len);
+        let mut node = self.step(HEAD, 0).next;
+        while node != NIL {
+            values.push(mem::take(&mut self.nodes[node as usize].val));
+            node = self.step(node, 0).next;
+        }
+        values
+    }
+}
+
+#[derive(Clone)]
+pub struct Cursor<'a, V: Ord + Default> {
+    list: &'a SkipList<V>,
+    node: Link,
+    index: Option<usize>,
+}
+
+impl<'a, V: Ord + Default> RankedCursor<V> for Cursor<'a, V> {
+    // From the head, moves to the lowest value.
+    fn move_next(&mut self) -> Option<&V> {
+        self.node = match self.list.step(self.node, 0).next {
+            NIL => HEAD,
+            link => link,
+        };
+        self.index = match (self.node, self.index) {
+            (HEAD, _) => None,
+            (_, Some(v)) => Some(v - 1),
+            (_, None) => Some(self.list.len - 1),
+        };
+        self.get_value()
+    }
+
+    // From the head, moves to the highest value.
+    fn move_prev(&mut self) -> Option<&V> {
+        self.node = match self.node {
+            HEAD => self.list.tail,
+            link => self.list.nodes[link as usize].prev,
+        };
+        self.index = match (self.node, self.index) {
+            (HEAD, _) => None,
+            (_, Some(v)) => Some(v + 1),
+            (_, None) => Some(0),
+        };
+        self.get_value()
+    }
+
+    fn get_value(&self) -> Option<&V> {
+        match self.node {
+            HEAD => None,
+            link => Some(self.list.val(link)),
+        }
+    }
+
+    fn get_index(&mut self) -> Option<usize> {
+        self.index
+    }
+
+    fn is_at_end(&self) -> bool {
+        self.node == HEAD
+    }
+}
+
+// Walks the list from the highest value down, yielding (index, value).
+#[derive(Clone)]
+pub struct Iter<'a, V: Ord + Default> {
+    list: &'a SkipList<V>,
+    front: Link,
+    back: Link,
+    front_index: usize,
+    remaining: usize,
+}
+
+impl<'a, V: Ord + Default> Iterator for Iter<'a, V> {
+    type Item = (usize, &'a V);
+
+    fn next(&mut self) -> Option<(usize, &'a V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let node = self.front;
+        let index = self.front_index;
+        self.remaining -= 1;
+        self.front_index += 1;
+        self.front = self.list.nodes[node as usize].prev;
+
+        return Some((index, self.list.val(node)));
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, V: Ord + Default> DoubleEndedIterator for Iter<'a, V> {
+    fn next_back(&mut self) -> Option<(usize, &'a V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let node = self.back;
+        self.remaining -= 1;
+        self.back = self.list.step(node, 0).next;
+
+        return Some((self.front_index + self.remaining, self.list.val(node)));
+    }
+}
+
+impl<'a, V: Ord + Default> ExactSizeIterator for Iter<'a, V> {}
+
+impl<'a, V: Ord + Default> FusedIterator for Iter<'a, V> {}
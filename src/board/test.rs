@@ -0,0 +1,378 @@
+use rand::Rng;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+use super::*;
+
+#[test]
+fn test_cache_tracks_mutations() {
+    let mut board: Board<u64, f64> = Board::new();
+    let mut rng = rand::rng();
+
+    for i in 0..200 {
+        let _ = board.update_entry(i, rng.random_range(0..50) as f64);
+    }
+
+    assert!(board.get_top(10, false, 600.0) == board.get_top_cacheless(10));
+    assert!(board.get_bottom(10, false, 600.0) == board.get_bottom_cacheless(10));
+
+    for _ in 0..2000 {
+        let id = rng.random_range(0..250);
+        if rng.random_bool(0.2) {
+            board.remove_entry(&id);
+        } else {
+            let _ = board.update_entry(id, rng.random_range(0..50) as f64);
+        }
+
+        let count = rng.random_range(0..20);
+        assert!(board.get_top(count, false, 600.0) == board.get_top_cacheless(count));
+        assert!(board.get_bottom(count, false, 600.0) == board.get_bottom_cacheless(count));
+    }
+}
+
+#[test]
+fn test_cache_extends() {
+    let mut board: Board<u64, f64> = Board::new();
+
+    for i in 0..5 {
+        let _ = board.update_entry(i, i as f64);
+    }
+
+    assert_eq!(board.get_top(3, false, 600.0).len(), 3);
+    assert!(board.get_top(10, false, 600.0) == board.get_top_cacheless(10));
+    assert_eq!(board.get_bottom(2, false, 600.0).len(), 2);
+    assert!(board.get_bottom(4, false, 600.0) == board.get_bottom_cacheless(4));
+
+    for i in 5..10 {
+        let _ = board.update_entry(i, i as f64);
+    }
+
+    assert!(board.get_top(10, false, 600.0) == board.get_top_cacheless(10));
+    assert!(board.get_bottom(10, false, 600.0) == board.get_bottom_cacheless(10));
+}
+
+#[test]
+fn test_rank_movement() {
+    let mut board: Board<u64, f64> = Board::new();
+
+    for i in 0..10 {
+        let _ = board.update_entry(i, i as f64);
+    }
+
+    assert!(board.get_rank_movement(&0, 10).is_none());
+
+    board.set_rank_checkpoint(Some(RankCheckpoint::Save));
+    board.take_rank_checkpoint();
+
+    let _ = board.update_entry(0, 100.0);
+    let _ = board.update_entry(20, 5.5);
+
+    let movement = board
+        .get_rank_movement(&0, board.get_rank(&0).unwrap())
+        .unwrap();
+    assert_eq!(movement.previous_rank, Some(10));
+    assert_eq!(movement.delta, Some(9));
+
+    let movement = board
+        .get_rank_movement(&9, board.get_rank(&9).unwrap())
+        .unwrap();
+    assert_eq!(movement.previous_rank, Some(1));
+    assert_eq!(movement.delta, Some(-1));
+
+    let movement = board
+        .get_rank_movement(&20, board.get_rank(&20).unwrap())
+        .unwrap();
+    assert_eq!(movement.previous_rank, None);
+    assert_eq!(movement.delta, None);
+}
+
+#[test]
+fn test_shared_cached_reads() {
+    let mut board: Board<u64, f64> = Board::new();
+    for i in 0..1000 {
+        let _ = board.update_entry(i, i as f64);
+    }
+    let expected = board.get_top_cacheless(50);
+    let board = Arc::new(RwLock::new(board));
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let board = board.clone();
+            thread::spawn(move || {
+                let board = board.read().unwrap();
+                for count in 1..=50 {
+                    assert_eq!(board.get_top(count, false, 600.0).len(), count);
+                }
+                board.get_top(50, false, 600.0)
+            })
+        })
+        .collect();
+
+    for handle in handles {
+        assert!(handle.join().unwrap() == expected);
+    }
+}
+
+#[test]
+fn test_saved_entries() {
+    let mut board: Board<u64, f64> = Board::new();
+    let mut rng = rand::rng();
+    for i in 0..500 {
+        let _ = board.update_entry(i, rng.random_range(0..100) as f64);
+    }
+
+    let config = bincode::config::standard();
+    let bytes = bincode::encode_to_vec(&board, config).unwrap();
+    let (saved, _): (SavedEntries<u64, f64>, usize) =
+        bincode::decode_from_slice(&bytes, config).unwrap();
+    assert!(saved.is_sorted());
+
+    let (loaded, _): (Board<u64, f64>, usize) = bincode::decode_from_slice(&bytes, config).unwrap();
+    assert!(loaded.get_top_cacheless(500) == board.get_top_cacheless(500));
+
+    // older saves are an unordered HashMap
+    let mut map = HashMap::new();
+    for i in 0..500 {
+        map.insert(i, board.get_entry(&i).unwrap());
+    }
+    let bytes = bincode::encode_to_vec(&map, config).unwrap();
+    let (saved, _): (SavedEntries<u64, f64>, usize) =
+        bincode::decode_from_slice(&bytes, config).unwrap();
+    let loaded: Board<u64, f64> = Board::from_saved(saved);
+    assert!(loaded.get_top_cacheless(500) == board.get_top_cacheless(500));
+    assert!(loaded.get_entry(&42) == board.get_entry(&42));
+}
+
+#[test]
+fn test_split_join() {
+    let mut board: Board<u64, f64> = Board::new();
+    for i in 0..100 {
+        let _ = board.update_entry(i, i as f64);
+    }
+    let _ = board.get_top(10, false, 600.0);
+    let _ = board.get_bottom(10, false, 600.0);
+
+    let low = board.split_at_rank(51);
+    assert_eq!(board.get_size(), 50);
+    assert_eq!(low.get_size(), 50);
+    assert!(board.get_entry(&49).is_none());
+    assert!(low.get_entry(&49).is_some());
+    assert!(board.get_bottom(10, false, 600.0) == board.get_bottom_cacheless(10));
+
+    let mut lowest = board.split_at_points(75.0);
+    assert_eq!(board.get_rank(&75), Some(25));
+    assert!(board.get_entry(&74).is_none());
+    assert_eq!(lowest.get_size(), 25);
+
+    // overlapping ranges are given back
+    let mut overlap: Board<u64, f64> = Board::new();
+    let _ = overlap.update_entry(200, 80.0);
+    let overlap = board.join(overlap).err().unwrap();
+    assert_eq!(overlap.get_size(), 1);
+
+    assert!(lowest.join(low).is_ok());
+    assert!(board.join(lowest).is_ok());
+    assert_eq!(board.get_size(), 100);
+    assert_eq!(board.get_rank(&0), Some(100));
+    assert!(board.get_top(100, false, 600.0) == board.get_top_cacheless(100));
+
+    board.set_size_cap(30);
+    let trimmed = board.trim_after_cap();
+    assert_eq!(board.get_size(), 30);
+    assert!(board.get_entry(&69).is_none());
+    assert!(board.get_entry(&70).is_some());
+    assert_eq!(trimmed.get_size(), 70);
+    assert!(trimmed.get_entry(&69).is_some());
+    assert_eq!(board.trim_after_cap().get_size(), 0);
+
+    // joining past the cap trims the lowest entries off again
+    let mut top: Board<u64, f64> = Board::new();
+    let _ = top.update_entry(500, 500.0);
+    let trimmed = board.join(top).ok().unwrap();
+    assert_eq!(board.get_size(), 30);
+    assert_eq!(board.get_rank(&500), Some(1));
+    assert_eq!(trimmed.get_size(), 1);
+    assert!(trimmed.get_entry(&70).is_some());
+}
+
+#[test]
+fn test_iter_queries() {
+    let mut board: Board<u64, f64> = Board::new();
+    for i in 0..20 {
+        let _ = board.update_entry(i, i as f64);
+    }
+
+    assert!(
+        board
+            .iter()
+            .map(|(rank, v)| (rank, v.key))
+            .eq((1..=20).zip((0..20).rev()))
+    );
+    assert_eq!(board.iter().len(), 20);
+    assert_eq!(board.iter().next_back().unwrap().0, 20);
+    assert_eq!(board.get_min(), Some(0.0));
+    assert!(board.get_ids() == (0..20).collect::<Vec<u64>>());
+
+    assert_eq!(board.range(0..=3).len(), 3);
+    assert!(board.iter_from_rank(15).map(|(rank, _)| rank).eq(15..=20));
+    let range: Vec<_> = board
+        .get_range(5, 7)
+        .iter()
+        .map(|(r, v)| (*r, v.key))
+        .collect();
+    assert!(range == vec![(5, 15), (6, 14), (7, 13)]);
+    assert!(board.get_range(7, 5).is_empty());
+    assert!(board.get_range(19, 30).len() == 2);
+
+    let after: Vec<_> = board
+        .get_after(&3, 5)
+        .unwrap()
+        .iter()
+        .map(|(_, v)| v.key)
+        .collect();
+    assert!(after == vec![2, 1, 0]);
+    let before: Vec<_> = board
+        .get_before(&17, 5)
+        .unwrap()
+        .iter()
+        .map(|(_, v)| v.key)
+        .collect();
+    assert!(before == vec![18, 19]);
+    let around: Vec<_> = board
+        .get_around(&10, 2, 1)
+        .unwrap()
+        .iter()
+        .map(|(r, _)| *r)
+        .collect();
+    assert!(around == vec![8, 9, 10, 11]);
+    assert!(board.get_around(&99, 2, 2).is_none());
+
+    assert!(board.get_top_cacheless(3) == board.get_top(3, false, 600.0));
+    assert!(board.get_bottom_cacheless(3) == board.get_bottom(3, false, 600.0));
+    assert!(board.get_bottom(6, false, 600.0) == board.get_bottom_cacheless(6));
+}
+
+#[test]
+fn test_index_kinds() {
+    let mut avl: Board<u64, f64> = Board::new();
+    let mut skip: Board<u64, f64, SkipList<Entry<u64, f64>>> = Board::new();
+    let mut any: Board<u64, f64, AnyIndex<Entry<u64, f64>>> = Board::new();
+    any.set_index_kind(IndexKind::SkipList);
+
+    for i in 0..300 {
+        let points = (i * 7 % 101) as f64;
+        let _ = avl.update_entry(i, points);
+        let _ = skip.update_entry(i, points);
+        let _ = any.update_entry(i, points);
+    }
+    for i in (0..300).step_by(4) {
+        let _ = avl.update_entry(i, 500.0 - i as f64);
+        let _ = skip.update_entry(i, 500.0 - i as f64);
+        let _ = any.update_entry(i, 500.0 - i as f64);
+    }
+    avl.remove_entry(&7);
+    skip.remove_entry(&7);
+    any.remove_entry(&7);
+
+    // timestamps differ between the boards, so compare ranks and keys
+    let keys = |entries: Vec<(usize, Entry<u64, f64>)>| -> Vec<(usize, u64)> {
+        entries
+            .into_iter()
+            .map(|(rank, entry)| (rank, entry.key))
+            .collect()
+    };
+    assert!(keys(skip.get_top_cacheless(300)) == keys(avl.get_top_cacheless(300)));
+    assert!(keys(any.get_bottom_cacheless(50)) == keys(avl.get_bottom_cacheless(50)));
+    assert!(
+        keys(skip.get_around(&40, 10, 10).unwrap()) == keys(avl.get_around(&40, 10, 10).unwrap())
+    );
+    assert!(skip.get_rank(&99) == avl.get_rank(&99));
+
+    // switching kinds keeps every entry in place
+    any.set_index_kind(IndexKind::Avl);
+    assert!(any.get_index_kind() == IndexKind::Avl);
+    assert!(keys(any.get_top_cacheless(300)) == keys(avl.get_top_cacheless(300)));
+
+    // swapped in entries take on the board's kind
+    any.set_index_kind(IndexKind::SkipList);
+    let mut other: Board<u64, f64, AnyIndex<Entry<u64, f64>>> = Board::new();
+    for i in 0..20 {
+        let _ = other.update_entry(i, i as f64);
+    }
+    any.replace_entries(other);
+    assert!(any.get_index_kind() == IndexKind::SkipList);
+    assert_eq!(any.get_size(), 20);
+    assert_eq!(any.get_rank(&19), Some(1));
+}
+
+#[test]
+fn test_delta() {
+    let mut rng = rand::rng();
+    let mut board: Board<u64, f64> = Board::new();
+    for i in 0..200 {
+        let _ = board.update_entry(i, rng.random_range(0..1000) as f64);
+    }
+
+    let copy_of = |board: &Board<u64, f64>| {
+        let mut saved = SavedEntries::from_map(&board.get_map_snapshot().get_lock());
+        saved.sort();
+        Board::<u64, f64>::from_saved(saved)
+    };
+    // loading a save isn't a change
+    assert!(!copy_of(&board).is_dirty());
+
+    let _ = board.take_delta();
+    assert!(!board.is_dirty());
+    let mut saved = copy_of(&board);
+
+    for round in 0..4 {
+        // changes are tracked the same while a save holds a snapshot
+        let snapshot = (round % 2 == 1).then(|| board.get_map_snapshot());
+        for _ in 0..100 {
+            let id = rng.random_range(0..300);
+            match rng.random_range(0..3) {
+                0 => {
+                    let _ = board.remove_entry(&id);
+                }
+                _ => {
+                    let _ = board.update_entry(id, rng.random_range(0..1000) as f64);
+                }
+            }
+        }
+        if round == 2 {
+            board.clear();
+            let _ = board.update_entry(5, 5.0);
+        }
+        let _ = drop(snapshot);
+
+        assert!(board.is_dirty());
+        let delta = board.take_delta();
+        assert!(!board.is_dirty());
+        saved.apply_delta(delta);
+        assert!(saved.get_top_cacheless(300) == board.get_top_cacheless(300));
+    }
+
+    // a delta that failed to save goes out with the next one
+    let _ = board.update_entry(1000, 1.0);
+    let failed = board.take_delta();
+    let _ = board.update_entry(1001, 2.0);
+    board.restore_delta(failed);
+    let delta = board.take_delta();
+    assert_eq!(delta.changes.len(), 2);
+    saved.apply_delta(delta);
+    assert!(saved.get_top_cacheless(300) == board.get_top_cacheless(300));
+
+    // replacing every entry is a clear followed by adding each one
+    let mut other: Board<u64, f64> = Board::new();
+    for i in 0..50 {
+        let _ = other.update_entry(i + 500, i as f64);
+    }
+    let expected = other.get_top_cacheless(300);
+    board.replace_entries(other);
+    assert!(board.get_top_cacheless(300) == expected);
+    let delta = board.take_delta();
+    assert!(delta.cleared && delta.changes.len() == 50);
+    saved.apply_delta(delta);
+    assert!(saved.get_top_cacheless(300) == expected);
+}
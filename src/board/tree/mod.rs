@@ -1,8 +1,7 @@
 mod node;
 mod tree_struct;
 
-use node::Node;
-pub use tree_struct::Tree;
+pub use tree_struct::{Cursor, Iter, Tree};
 
 #[cfg(test)]
 mod test;
@@ -1,263 +1,619 @@
-use std::{cmp, ptr::NonNull};
+use std::{
+    any::Any,
+    cell::UnsafeCell,
+    cmp, mem,
+    ops::{Index, IndexMut},
+    ptr,
+    sync::{Arc, Mutex},
+};
 
-type Link<V> = Option<NonNull<Node<V>>>;
+// Nodes live in an arena and link to each other by index instead of by pointer.
+pub(super) type Link = u32;
+pub(super) const NIL: Link = u32::MAX;
+// every tree has its own sentinel outside the store, its right child is the root
+pub(super) const SENTINEL: Link = 0;
 
-#[derive(PartialEq)]
-pub struct Node<V: Ord + ?Sized> {
-    pub(super) count: usize,
-    pub(super) height: usize,
+// The store keeps slots in chunks of 1 << CHUNK_BITS that never move once allocated. Arenas take
+// and give back BATCH slots at a time.
+const CHUNK_BITS: u32 = 16;
+const CHUNKS: usize = 1 << (Link::BITS - CHUNK_BITS);
+const BATCH: usize = 64;
 
-    pub(super) left: Link<V>,
-    pub(super) right: Link<V>,
-    pub(super) parent: Link<V>,
+#[derive(PartialEq, Clone)]
+pub struct Node<V: Ord> {
+    pub(super) count: u32,
+    pub(super) height: u8,
+
+    pub(super) left: Link,
+    pub(super) right: Link,
+    pub(super) parent: Link,
     pub(super) is_left_child: bool,
 
     pub val: V,
 }
 
-impl<V: Ord + ?Sized> Node<V> {
-    unsafe fn rotate(node: *mut Self) {
-        unsafe {
-            // get the parent node and parent's parent node, meanwhile making sure this is a valid node to rotate around.
-            let parent = match (*node).parent {
-                None => panic!("Attempt to rotate about sentinel!"),
-                Some(ptr) => ptr.as_ptr(),
-            };
+impl<V: Ord + Default> Node<V> {
+    fn vacant() -> Self {
+        Self {
+            count: 0,
+            height: 0,
+            left: NIL,
+            right: NIL,
+            parent: NIL,
+            is_left_child: false,
+            val: V::default(),
+        }
+    }
+}
 
-            let parent_parent = match (*parent).parent {
-                None => panic!("Attempt to rotate about root node!"),
-                Some(ptr) => ptr.as_ptr(),
-            };
+// Every tree of one value type keeps its nodes in the same store, so splitting a tree or joining
+// two only relinks nodes. Each slot belongs to one arena at a time, the one whose tree links it
+// or keeps it for reuse, and only that arena reads or writes it. Spare slots belong to the store.
+struct Store<V: Ord + Default> {
+    // null until the store first hands out one of the chunk's slots, which it does under the
+    // spare lock, so an arena only reads the chunks it holds slots in after they were set
+    chunks: Box<[UnsafeCell<*mut Node<V>>; CHUNKS]>,
+    spare: Mutex<Spare>,
+}
 
-            let parent_parent_ref = (*parent).parent;
-            let self_ref = match (*node).is_left_child {
-                true => (*parent).left,
-                false => (*parent).right,
-            };
-            let was_left_child = (*node).is_left_child;
-
-            if (*parent).is_left_child {
-                (*parent_parent).left = self_ref.clone();
-                (*node).is_left_child = true;
-            } else {
-                (*parent_parent).right = self_ref.clone();
-                (*node).is_left_child = false;
+struct Spare {
+    // slots from here on were never handed out, slot 0 stands for the sentinel
+    next: u64,
+    free: Vec<Link>,
+}
+
+// Slots are only touched by the arena that owns them, like values behind a Mutex.
+unsafe impl<V: Ord + Default + Send> Send for Store<V> {}
+unsafe impl<V: Ord + Default + Send> Sync for Store<V> {}
+
+impl<V: Ord + Default + Send + 'static> Store<V> {
+    fn shared() -> Arc<Self> {
+        static STORES: Mutex<Vec<Arc<dyn Any + Send + Sync>>> = Mutex::new(Vec::new());
+
+        let mut stores = STORES.lock().unwrap();
+        for store in stores.iter() {
+            if let Ok(store) = Arc::clone(store).downcast::<Self>() {
+                return store;
             }
+        }
 
-            if was_left_child {
-                (*parent).left = (*node).right;
-                if let Some(child_ptr) = (*node).right {
-                    let child = child_ptr.as_ptr();
-                    (*child).parent = (*node).parent;
-                    (*child).is_left_child = true;
-                }
+        let store = Arc::new(Self {
+            chunks: (0..CHUNKS)
+                .map(|_| UnsafeCell::new(ptr::null_mut()))
+                .collect::<Box<[_]>>()
+                .try_into()
+                .unwrap(),
+            spare: Mutex::new(Spare {
+                next: 1,
+                free: Vec::new(),
+            }),
+        });
+        stores.push(store.clone());
+        return store;
+    }
+}
 
-                (*node).right = (*node).parent;
-                (*parent).parent = self_ref;
-                (*parent).is_left_child = false;
-            } else {
-                (*parent).right = (*node).left;
-                if let Some(child_ptr) = (*node).left {
-                    let child = child_ptr.as_ptr();
-                    (*child).parent = (*node).parent;
-                    (*child).is_left_child = false;
-                }
+impl<V: Ord + Default> Store<V> {
+    #[inline]
+    fn chunk_of(link: Link) -> (usize, usize) {
+        let chunk = link >> CHUNK_BITS;
+        let offset = link & ((1 << CHUNK_BITS) - 1);
+        (chunk as usize, offset as usize)
+    }
 
-                (*node).left = (*node).parent;
-                (*parent).parent = self_ref;
-                (*parent).is_left_child = true;
+    #[inline]
+    fn slot(&self, link: Link) -> *mut Node<V> {
+        let (chunk, offset) = Self::chunk_of(link);
+        let chunk = unsafe { *self.chunks[chunk].get() };
+        debug_assert!(
+            !chunk.is_null(),
+            "Link to a slot the store never handed out."
+        );
+        // offsets stay within their chunk, see chunk_of
+        unsafe { chunk.add(offset) }
+    }
+
+    // Moves count slots to the arena's free list, the lowest ones last so they are used first.
+    fn take(&self, free: &mut Vec<Link>, count: usize) {
+        let mut spare = self.spare.lock().unwrap();
+
+        let reused = count.min(spare.free.len());
+        let from = spare.free.len() - reused;
+        free.extend(spare.free.drain(from..));
+
+        let fresh = (count - reused) as u64;
+        if fresh == 0 {
+            return;
+        }
+        if spare.next + fresh > NIL as u64 {
+            panic!("Trees cannot hold more than {} entries in total!", NIL - 1);
+        }
+
+        let start = spare.next as Link;
+        let end = (spare.next + fresh - 1) as Link;
+        for chunk in Self::chunk_of(start).0..=Self::chunk_of(end).0 {
+            let slots = self.chunks[chunk].get();
+            if unsafe { *slots }.is_null() {
+                let nodes: Box<[Node<V>]> = (0..1 << CHUNK_BITS).map(|_| Node::vacant()).collect();
+                let nodes = Box::into_raw(nodes) as *mut Node<V>;
+                unsafe { *slots = nodes };
             }
+        }
+        free.extend((start..=end).rev());
+        spare.next += fresh;
+    }
 
-            (*node).parent = parent_parent_ref;
+    fn give(&self, free: impl Iterator<Item = Link>) {
+        self.spare.lock().unwrap().free.extend(free);
+    }
+}
 
-            Self::fix(parent);
-            Self::fix(node);
+impl<V: Ord + Default> Drop for Store<V> {
+    fn drop(&mut self) {
+        for nodes in self.chunks.iter_mut() {
+            let nodes = *nodes.get_mut();
+            if !nodes.is_null() {
+                let nodes = ptr::slice_from_raw_parts_mut(nodes, 1 << CHUNK_BITS);
+                let _ = drop(unsafe { Box::from_raw(nodes) });
+            }
         }
     }
+}
 
-    pub(super) unsafe fn fix(node: *mut Self) {
-        unsafe {
-            Self::fix_count(node);
-            Self::fix_height(node);
+pub(super) struct Arena<V: Ord + Default> {
+    store: Arc<Store<V>>,
+    sentinel: Node<V>,
+    // released slots this arena reuses before asking the store
+    free: Vec<Link>,
+}
+
+impl<V: Ord + Default> Index<Link> for Arena<V> {
+    type Output = Node<V>;
+
+    #[inline]
+    fn index(&self, link: Link) -> &Node<V> {
+        if link == SENTINEL {
+            return &self.sentinel;
         }
+        // the slot belongs to this arena, so nothing else touches it
+        unsafe { &*self.store.slot(link) }
     }
+}
 
-    pub(super) unsafe fn fix_height(node: *mut Self) {
-        unsafe {
-            (*node).height = 1 + Self::get_left_height(node).max(Self::get_right_height(node))
+impl<V: Ord + Default> IndexMut<Link> for Arena<V> {
+    #[inline]
+    fn index_mut(&mut self, link: Link) -> &mut Node<V> {
+        if link == SENTINEL {
+            return &mut self.sentinel;
+        }
+        unsafe { &mut *self.store.slot(link) }
+    }
+}
+
+impl<V: Ord + Default> Drop for Arena<V> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<V: Ord + Default + Send + 'static> Arena<V> {
+    pub(super) fn with_capacity(capacity: usize) -> Self {
+        let mut arena = Self {
+            store: Store::shared(),
+            sentinel: Node::vacant(),
+            free: Vec::new(),
         };
+        arena.store.take(&mut arena.free, capacity);
+        arena
     }
+}
 
-    pub(super) unsafe fn get_left_height(node: *mut Self) -> usize {
-        unsafe {
-            match (*node).left {
-                None => 0,
-                Some(ptr) => (*ptr.as_ptr()).height.clone(),
-            }
+impl<V: Ord + Default> Arena<V> {
+    // An empty arena in the same store, its tree can be joined with this one by relinking.
+    pub(super) fn share(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            sentinel: Node::vacant(),
+            free: Vec::new(),
         }
     }
 
-    pub(super) unsafe fn get_right_height(node: *mut Self) -> usize {
-        unsafe {
-            match (*node).right {
-                None => 0,
-                Some(ptr) => (*ptr.as_ptr()).height.clone(),
-            }
+    // Takes over the nodes of another arena in the same store, returning the root of its tree.
+    // The other arena is left empty.
+    pub(super) fn adopt(&mut self, other: &mut Self) -> Link {
+        assert!(
+            Arc::ptr_eq(&self.store, &other.store),
+            "Only trees in the same store can be joined."
+        );
+        let root = other.sentinel.right;
+        other.sentinel.right = NIL;
+        self.free.append(&mut other.free);
+        self.give_back();
+        root
+    }
+
+    pub(super) fn alloc(&mut self, val: V, parent: Link, is_left_child: bool) -> Link {
+        if self.free.is_empty() {
+            self.store.take(&mut self.free, BATCH);
+        }
+        let link = self.free.pop().unwrap();
+
+        self[link] = Node {
+            count: 1,
+            height: 1,
+            left: NIL,
+            right: NIL,
+            parent: parent,
+            is_left_child: is_left_child,
+            val: val,
+        };
+        return link;
+    }
+
+    // Frees a node that has already been unlinked from the tree, returning its value.
+    pub(super) fn release(&mut self, link: Link) -> V {
+        let node = &mut self[link];
+        node.left = NIL;
+        node.right = NIL;
+        node.parent = NIL;
+        let val = mem::take(&mut node.val);
+
+        self.free.push(link);
+        self.give_back();
+        val
+    }
+
+    // keeps a few chunks' worth of free slots and hands the rest back to the store
+    fn give_back(&mut self) {
+        let keep = 4 * BATCH;
+        if self.free.len() > 2 * keep {
+            self.store.give(self.free.drain(keep..));
         }
     }
 
-    pub(super) unsafe fn get_left_count(node: *mut Self) -> usize {
-        unsafe {
-            match (*node).left {
-                None => 0,
-                Some(ptr) => (*ptr.as_ptr()).count,
+    // the slots go back to the store, which keeps them for other trees
+    pub(super) fn clear(&mut self) {
+        let mut stack = vec![self.sentinel.right];
+        self.sentinel.right = NIL;
+
+        while let Some(link) = stack.pop() {
+            if link == NIL {
+                continue;
             }
+            stack.push(self[link].left);
+            stack.push(self[link].right);
+            let _ = self.release(link);
         }
+
+        self.store.give(self.free.drain(..));
+        self.free.shrink_to_fit();
     }
 
-    pub(super) unsafe fn get_right_count(node: *mut Self) -> usize {
-        unsafe {
-            match (*node).right {
-                None => 0,
-                Some(ptr) => (*ptr.as_ptr()).count,
+    // the slots this arena's tree links and keeps for reuse, the store itself is shared
+    pub(super) fn memory_usage(&self) -> usize {
+        let slots = 1 + self.get_right_count(SENTINEL) + self.free.len();
+        slots * mem::size_of::<Node<V>>() + self.free.capacity() * mem::size_of::<Link>()
+    }
+
+    fn rotate(&mut self, node: Link) {
+        // get the parent node and parent's parent node, meanwhile making sure this is a valid node to rotate around.
+        let parent = self[node].parent;
+        if parent == NIL {
+            panic!("Attempt to rotate about sentinel!");
+        }
+
+        let parent_parent = self[parent].parent;
+        if parent_parent == NIL {
+            panic!("Attempt to rotate about root node!");
+        }
+
+        let was_left_child = self[node].is_left_child;
+
+        if self[parent].is_left_child {
+            self[parent_parent].left = node;
+            self[node].is_left_child = true;
+        } else {
+            self[parent_parent].right = node;
+            self[node].is_left_child = false;
+        }
+
+        if was_left_child {
+            let child = self[node].right;
+            self[parent].left = child;
+            if child != NIL {
+                self[child].parent = parent;
+                self[child].is_left_child = true;
+            }
+
+            self[node].right = parent;
+            self[parent].parent = node;
+            self[parent].is_left_child = false;
+        } else {
+            let child = self[node].left;
+            self[parent].right = child;
+            if child != NIL {
+                self[child].parent = parent;
+                self[child].is_left_child = false;
             }
+
+            self[node].left = parent;
+            self[parent].parent = node;
+            self[parent].is_left_child = true;
+        }
+
+        self[node].parent = parent_parent;
+
+        self.fix(parent);
+        self.fix(node);
+    }
+
+    pub(super) fn fix(&mut self, node: Link) {
+        self.fix_count(node);
+        self.fix_height(node);
+    }
+
+    pub(super) fn fix_height(&mut self, node: Link) {
+        self[node].height = 1 + self.get_left_height(node).max(self.get_right_height(node));
+    }
+
+    pub(super) fn get_left_height(&self, node: Link) -> u8 {
+        match self[node].left {
+            NIL => 0,
+            link => self[link].height,
         }
     }
 
-    pub(super) unsafe fn fix_count(node: *mut Self) {
-        unsafe {
-            (*node).count = 1 + Self::get_left_count(node) + Self::get_right_count(node);
+    pub(super) fn get_right_height(&self, node: Link) -> u8 {
+        match self[node].right {
+            NIL => 0,
+            link => self[link].height,
         }
     }
 
-    pub(super) unsafe fn is_imbalanced(node: *mut Self) -> bool {
-        unsafe { Self::get_left_height(node).abs_diff(Self::get_right_height(node)) > 1 }
-    }
-
-    pub(super) unsafe fn fix_imbalance(node: *mut Self) {
-        unsafe {
-            let rot_target: *mut Node<V>;
-            let zag: bool;
-
-            match Self::get_left_height(node).cmp(&Self::get_right_height(node)) {
-                cmp::Ordering::Equal => panic!("Heights are equal for imbalance"),
-                cmp::Ordering::Greater => {
-                    let left = (*node).left.unwrap().as_ptr();
-                    match Self::get_left_height(left).cmp(&Self::get_right_height(left)) {
-                        cmp::Ordering::Equal | cmp::Ordering::Greater => {
-                            zag = false;
-                            rot_target = left;
-                        }
-                        cmp::Ordering::Less => {
-                            zag = true;
-                            rot_target = (*left).right.unwrap().as_ptr();
-                        }
+    pub(super) fn get_left_count(&self, node: Link) -> usize {
+        match self[node].left {
+            NIL => 0,
+            link => self[link].count as usize,
+        }
+    }
+
+    pub(super) fn get_right_count(&self, node: Link) -> usize {
+        match self[node].right {
+            NIL => 0,
+            link => self[link].count as usize,
+        }
+    }
+
+    pub(super) fn fix_count(&mut self, node: Link) {
+        self[node].count = (1 + self.get_left_count(node) + self.get_right_count(node)) as u32;
+    }
+
+    pub(super) fn is_imbalanced(&self, node: Link) -> bool {
+        self.get_left_height(node).abs_diff(self.get_right_height(node)) > 1
+    }
+
+    pub(super) fn fix_imbalance(&mut self, node: Link) {
+        let rot_target: Link;
+        let zag: bool;
+
+        match self.get_left_height(node).cmp(&self.get_right_height(node)) {
+            cmp::Ordering::Equal => panic!("Heights are equal for imbalance"),
+            cmp::Ordering::Greater => {
+                let left = self[node].left;
+                match self.get_left_height(left).cmp(&self.get_right_height(left)) {
+                    cmp::Ordering::Equal | cmp::Ordering::Greater => {
+                        zag = false;
+                        rot_target = left;
+                    }
+                    cmp::Ordering::Less => {
+                        zag = true;
+                        rot_target = self[left].right;
                     }
                 }
-                cmp::Ordering::Less => {
-                    let right = (*node).right.unwrap().as_ptr();
-                    match Self::get_right_height(right).cmp(&Self::get_left_height(right)) {
-                        cmp::Ordering::Equal | cmp::Ordering::Greater => {
-                            zag = false;
-                            rot_target = right;
-                        }
-                        cmp::Ordering::Less => {
-                            zag = true;
-                            rot_target = (*right).left.unwrap().as_ptr();
-                        }
+            }
+            cmp::Ordering::Less => {
+                let right = self[node].right;
+                match self.get_right_height(right).cmp(&self.get_left_height(right)) {
+                    cmp::Ordering::Equal | cmp::Ordering::Greater => {
+                        zag = false;
+                        rot_target = right;
+                    }
+                    cmp::Ordering::Less => {
+                        zag = true;
+                        rot_target = self[right].left;
                     }
                 }
-            };
+            }
+        };
 
-            Self::rotate(rot_target);
-            if zag {
-                Self::rotate(rot_target);
+        self.rotate(rot_target);
+        if zag {
+            self.rotate(rot_target);
+        }
+    }
+
+    pub(super) fn get_height(&self, node: Link) -> u8 {
+        match node {
+            NIL => 0,
+            link => self[link].height,
+        }
+    }
+
+    pub(super) fn set_child(&mut self, parent: Link, child: Link, left: bool) {
+        if left {
+            self[parent].left = child;
+        } else {
+            self[parent].right = child;
+        }
+        if child != NIL {
+            self[child].parent = parent;
+            self[child].is_left_child = left;
+        }
+    }
+
+    // Rotates node's left child into its place and returns it. The caller relinks the parent.
+    fn rotate_up_left(&mut self, node: Link) -> Link {
+        let pivot = self[node].left;
+        let inner = self[pivot].right;
+        self.set_child(node, inner, true);
+
+        self[pivot].parent = self[node].parent;
+        self[pivot].is_left_child = self[node].is_left_child;
+        self.set_child(pivot, node, false);
+
+        self.fix(node);
+        self.fix(pivot);
+        pivot
+    }
+
+    // Rotates node's right child into its place and returns it. The caller relinks the parent.
+    fn rotate_up_right(&mut self, node: Link) -> Link {
+        let pivot = self[node].right;
+        let inner = self[pivot].left;
+        self.set_child(node, inner, false);
+
+        self[pivot].parent = self[node].parent;
+        self[pivot].is_left_child = self[node].is_left_child;
+        self.set_child(pivot, node, true);
+
+        self.fix(node);
+        self.fix(pivot);
+        pivot
+    }
+
+    // Rebalances a subtree whose children differ in height by at most two, returning its new
+    // root. Unlike fix_imbalance this works on subtrees that are detached from the tree.
+    pub(super) fn balance(&mut self, node: Link) -> Link {
+        self.fix(node);
+
+        let left = self[node].left;
+        let right = self[node].right;
+        let left_height = self.get_height(left);
+        let right_height = self.get_height(right);
+
+        if left_height > right_height + 1 {
+            if self.get_left_height(left) < self.get_right_height(left) {
+                let pivot = self.rotate_up_right(left);
+                self.set_child(node, pivot, true);
             }
+            return self.rotate_up_left(node);
+        } else if right_height > left_height + 1 {
+            if self.get_right_height(right) < self.get_left_height(right) {
+                let pivot = self.rotate_up_left(right);
+                self.set_child(node, pivot, false);
+            }
+            return self.rotate_up_right(node);
         }
+
+        node
     }
 
-    pub(super) unsafe fn next_node(node: *mut Self) -> *mut Self {
-        unsafe {
-            if let Some(right) = (*node).right {
-                let mut next = right.as_ptr();
-                while let Some(left) = (*next).left {
-                    next = left.as_ptr();
+    pub(super) fn next_node(&self, node: Link) -> Link {
+        if self[node].right != NIL {
+            let mut next = self[node].right;
+            while self[next].left != NIL {
+                next = self[next].left;
+            }
+            return next;
+        } else {
+            let mut next = match self[node].parent {
+                NIL => {
+                    return node;
                 }
-                return next;
-            } else {
-                let mut next = match (*node).parent {
-                    Some(v) => v.as_ptr(),
-                    None => {
-                        return node;
+                link => link,
+            };
+            let mut is_left = self[node].is_left_child;
+            while !is_left {
+                is_left = self[next].is_left_child;
+                next = match self[next].parent {
+                    NIL => {
+                        return next;
                     }
+                    link => link,
                 };
-                let mut is_left = (*node).is_left_child;
-                while !is_left {
-                    is_left = (*next).is_left_child;
-                    next = match (*next).parent {
-                        None => {
-                            return next;
-                        }
-                        Some(v) => v.as_ptr(),
-                    };
-                }
-                return next;
             }
+            return next;
         }
     }
 
-    pub(super) unsafe fn prev_node(node: *mut Self) -> *mut Self {
-        unsafe {
-            if (*node).parent.is_none() {
-                let mut prev = match (*node).right {
-                    None => {
-                        return node;
-                    }
-                    Some(v) => v.as_ptr(),
-                };
-                while let Some(right) = (*prev).right {
-                    prev = right.as_ptr();
+    pub(super) fn prev_node(&self, node: Link) -> Link {
+        if self[node].parent == NIL {
+            let mut prev = match self[node].right {
+                NIL => {
+                    return node;
                 }
-                return prev;
+                link => link,
+            };
+            while self[prev].right != NIL {
+                prev = self[prev].right;
             }
+            return prev;
+        }
 
-            if let Some(left) = (*node).left {
-                let mut prev: *mut Node<V> = left.as_ptr();
-                while let Some(right) = (*prev).right {
-                    prev = right.as_ptr();
-                }
-                return prev;
-            } else {
-                let mut prev = (*node).parent.unwrap().as_ptr();
-                let mut is_left = (*node).is_left_child;
-                while is_left {
-                    is_left = (*prev).is_left_child;
-                    prev = match (*prev).parent {
-                        None => {
-                            return prev;
-                        }
-                        Some(v) => v.as_ptr(),
-                    };
-                }
-                return prev;
+        if self[node].left != NIL {
+            let mut prev = self[node].left;
+            while self[prev].right != NIL {
+                prev = self[prev].right;
             }
+            return prev;
+        } else {
+            let mut prev = self[node].parent;
+            let mut is_left = self[node].is_left_child;
+            while is_left {
+                is_left = self[prev].is_left_child;
+                prev = match self[prev].parent {
+                    NIL => {
+                        return prev;
+                    }
+                    link => link,
+                };
+            }
+            return prev;
         }
     }
 }
 
-impl<V: Ord + ?Sized> PartialOrd for Node<V> {
+impl<V: Ord + Default + Clone> Clone for Arena<V> {
+    // copies the tree into new slots of the same store, links and all
+    fn clone(&self) -> Self {
+        let mut arena = self.share();
+        arena
+            .store
+            .take(&mut arena.free, self.get_right_count(SENTINEL));
+        let root = self.copy_links(&mut arena, self.sentinel.right);
+        arena.set_child(SENTINEL, root, false);
+        arena
+    }
+}
+
+impl<V: Ord + Default + Clone> Arena<V> {
+    fn copy_links(&self, into: &mut Self, link: Link) -> Link {
+        if link == NIL {
+            return NIL;
+        }
+
+        let node = &self[link];
+        let copy = into.alloc(node.val.clone(), NIL, false);
+        let left = self.copy_links(into, node.left);
+        into.set_child(copy, left, true);
+        let right = self.copy_links(into, node.right);
+        into.set_child(copy, right, false);
+        into[copy].count = node.count;
+        into[copy].height = node.height;
+        copy
+    }
+}
+
+impl<V: Ord> PartialOrd for Node<V> {
     fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
         return self.val.partial_cmp(&other.val);
     }
 }
 
-impl<V: Ord + ?Sized> Ord for Node<V> {
+impl<V: Ord> Ord for Node<V> {
     fn cmp(&self, other: &Self) -> cmp::Ordering {
         return self.val.cmp(&other.val);
     }
 }
 
-impl<V: Ord + ?Sized> Eq for Node<V> {}
-
-unsafe impl<V: Send + Ord + ?Sized> Send for Node<V> {}
-unsafe impl<V: Sync + Ord + ?Sized> Sync for Node<V> {}
+impl<V: Ord> Eq for Node<V> {}
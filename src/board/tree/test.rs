@@ -1,55 +1,134 @@
+use rand::Rng;
+use std::collections::BTreeSet;
 use std::env;
+use std::ptr::NonNull;
+
+use crate::board::{AnyIndex, Entry, IndexKind, RankedCursor, RankedIndex, SkipList};
 
 use super::*;
 
-#[test]
-fn test_simple() {
-    unsafe {
-        env::set_var("RUST_BACKTRACE", "1");
+// The tests below run against every RankedIndex, check covers what each one can verify about
+// its own structure.
+trait Checked: RankedIndex<u32> {
+    fn check(&self);
+}
+
+impl Checked for Tree<u32> {
+    fn check(&self) {
+        self.validate();
+        // AVL trees are never taller than about 1.44 log2(n + 2)
+        assert!(self.height() as f64 <= 1.45 * ((self.len() + 2) as f64).log2());
+    }
+}
+
+impl Checked for SkipList<u32> {
+    fn check(&self) {
+        self.validate();
     }
+}
 
-    let mut tree: Tree<u32> = Tree::new();
+impl Checked for AnyIndex<u32> {
+    fn check(&self) {
+        match self {
+            AnyIndex::Avl(tree) => tree.check(),
+            AnyIndex::SkipList(list) => list.check(),
+        }
+    }
+}
 
-    assert!(tree.is_empty());
-    assert!(!tree.contains(&10));
-    assert_eq!(tree.len(), 0);
+macro_rules! ranked_index_tests {
+    ($($name:ident: $index:ty => $new:expr),* $(,)?) => {
+        $(
+            mod $name {
+                use super::*;
+
+                fn new() -> $index {
+                    $new
+                }
+
+                #[test]
+                fn test_simple() {
+                    check_simple(new());
+                }
+
+                #[test]
+                fn test_100() {
+                    check_100(new());
+                }
+
+                #[test]
+                fn test_from_sorted() {
+                    check_from_sorted::<$index>();
+                }
+
+                #[test]
+                fn test_random_ops() {
+                    check_random_ops(new());
+                }
+
+                #[test]
+                fn test_split_join() {
+                    check_split_join(new);
+                }
+
+                #[test]
+                fn test_iter() {
+                    check_iter::<$index>();
+                }
+            }
+        )*
+    };
+}
 
-    assert!(tree.insert(10));
-    assert!(!tree.insert(10));
-    assert!(tree.contains(&10));
-    assert!(!tree.is_empty());
-    assert_eq!(tree.len(), 1);
+ranked_index_tests! {
+    avl: Tree<u32> => Tree::new(),
+    skip_list: SkipList<u32> => SkipList::new(),
+    any_skip_list: AnyIndex<u32> => AnyIndex::new(IndexKind::SkipList),
+}
 
-    assert!(tree.remove(&10).is_some());
-    assert!(!tree.remove(&10).is_some());
-    assert!(!tree.contains(&10));
-    assert!(tree.is_empty());
+fn contains<I: RankedIndex<u32>>(index: &I, val: u32) -> bool {
+    index.index_of(&val).1
 }
 
-#[test]
-fn test_100() {
+fn check_simple<I: Checked>(mut index: I) {
     unsafe {
         env::set_var("RUST_BACKTRACE", "1");
     }
 
-    let mut tree: Tree<u32> = Tree::new();
+    assert!(index.is_empty());
+    assert!(!contains(&index, 10));
+    assert_eq!(index.len(), 0);
+
+    assert!(index.insert(10));
+    assert!(!index.insert(10));
+    assert!(contains(&index, 10));
+    assert!(!index.is_empty());
+    assert_eq!(index.len(), 1);
+
+    assert!(index.remove(&10).is_some());
+    assert!(!index.remove(&10).is_some());
+    assert!(!contains(&index, 10));
+    assert!(index.is_empty());
+    index.check();
+}
 
+fn check_100<I: Checked>(mut index: I) {
     for i in 0..100 {
-        assert!(tree.insert(i));
+        assert!(index.insert(i));
     }
 
-    tree.validate();
-    assert_eq!(tree.len(), 100);
-    assert!(tree.contains(&72));
-    assert!(!tree.contains(&100));
+    index.check();
+    assert_eq!(index.len(), 100);
+    assert!(contains(&index, 72));
+    assert!(!contains(&index, 100));
 
     for i in 0..100 {
-        // print!("{}: {}\n", i, tree.index_of(i).0)
-        assert!(tree.index_of(&i).0 == (99 - i as usize));
-        assert!(tree.at_index((99 - i) as usize).is_some_and(|v| *v == i))
+        assert!(index.index_of(&i).0 == (99 - i as usize));
+        assert!(index.at_index((99 - i) as usize).is_some_and(|v| *v == i))
     }
+    assert!(index.index_of(&100) == (0, false));
 
-    let mut cursor = tree.cursor();
+    let mut cursor = index.cursor();
 
     for i in 0..100 {
         cursor.move_next();
@@ -68,15 +147,229 @@ fn test_100() {
     }
     cursor.move_prev();
     assert!(cursor.is_at_end());
+    let _ = drop(cursor);
 
-    for i in 0..25 {
-        assert!(tree.remove(&i).is_some());
+    let mut cursor = index.seek_index(40).unwrap();
+    assert!(cursor.get_value() == Some(&59));
+    assert!(cursor.move_next() == Some(&60));
+    assert!(cursor.get_index() == Some(39));
+    assert!(index.seek_index(100).is_none());
+    let _ = drop(cursor);
+
+    for i in 0..50 {
+        assert!(index.remove(&i).is_some());
+    }
+    index.check();
+    assert_eq!(index.len(), 50);
+
+    assert_eq!(index.replace(&50, 49), Some(50));
+    assert!(index.replace(&50, 51).is_none());
+    assert!(index.replace(&49, 51).is_none());
+    index.check();
+    assert!(contains(&index, 49));
+    assert!(!contains(&index, 50));
+
+    assert_eq!(index.replace(&49, 4), Some(49));
+    index.check();
+    assert!(index.at_index(index.len() - 1) == Some(&4));
+
+    index.clear();
+    assert!(index.is_empty());
+    assert!(index.cursor().move_next().is_none());
+}
+
+fn check_from_sorted<I: Checked>() {
+    for len in [0usize, 1, 2, 3, 7, 8, 100, 1000] {
+        let mut index = I::from_sorted(0..len as u32);
+
+        index.check();
+        assert_eq!(index.len(), len);
+
+        for i in 0..len as u32 {
+            assert!(index.index_of(&i) == (len - 1 - i as usize, true));
+            assert!(
+                index
+                    .at_index(len - 1 - i as usize)
+                    .is_some_and(|v| *v == i)
+            );
+        }
+
+        // the structure must hold up under later changes
+        for i in 0..len as u32 {
+            if i % 3 == 0 {
+                assert!(index.remove(&i).is_some());
+            }
+        }
+        for i in len as u32..len as u32 + 50 {
+            assert!(index.insert(i));
+        }
+        index.check();
+
+        let mut cursor = index.cursor();
+        let mut expected = (0..len as u32 + 50).filter(|i| *i >= len as u32 || i % 3 != 0);
+        while let Some(v) = cursor.move_next() {
+            assert!(expected.next() == Some(*v));
+        }
+        assert!(expected.next().is_none());
+    }
+}
+
+fn check_random_ops<I: Checked>(mut index: I) {
+    let mut model: BTreeSet<u32> = BTreeSet::new();
+    let mut rng = rand::rng();
+
+    for i in 0..5000 {
+        let val = rng.random_range(0..2000);
+        match rng.random_range(0..3) {
+            0 => assert_eq!(index.insert(val), model.insert(val)),
+            1 => assert_eq!(index.remove(&val), model.take(&val)),
+            _ => {
+                let Some(&old) = model.iter().nth(rng.random_range(0..model.len().max(1))) else {
+                    continue;
+                };
+                let replaced = index.replace(&old, val);
+                if model.contains(&val) {
+                    assert!(replaced.is_none());
+                } else {
+                    assert_eq!(replaced, Some(old));
+                    model.remove(&old);
+                    model.insert(val);
+                }
+            }
+        }
+
+        if i % 250 == 0 {
+            index.check();
+        }
+    }
+
+    index.check();
+    assert_eq!(index.len(), model.len());
+
+    let mut cursor = index.cursor();
+    for (rank, val) in model.iter().rev().enumerate() {
+        assert!(cursor.move_prev() == Some(val));
+        assert_eq!(cursor.get_index(), Some(rank));
+        assert_eq!(index.index_of(val), (rank, true));
+    }
+    assert!(cursor.move_prev().is_none());
+    let _ = drop(cursor);
+    assert!(index.into_sorted() == model.into_iter().collect::<Vec<_>>());
+}
+
+fn assert_contents<I: Checked>(index: &I, expected: impl DoubleEndedIterator<Item = u32>) {
+    index.check();
+
+    let mut cursor = index.cursor();
+    for val in expected.rev() {
+        assert!(cursor.move_prev() == Some(&val));
+    }
+    assert!(cursor.move_prev().is_none());
+}
+
+fn check_split_join<I: Checked>(new: fn() -> I) {
+    let mut rng = rand::rng();
+
+    for _ in 0..50 {
+        let len = rng.random_range(0..500);
+        let index = rng.random_range(0..len + 2);
+
+        let mut high = new();
+        for i in 0..len {
+            high.insert(i);
+        }
+
+        let low = high.split_at_index(index as usize);
+        let boundary = len.saturating_sub(index);
+        assert_contents(&high, boundary..len);
+        assert_contents(&low, 0..boundary);
+
+        let mut middle = high.split_at_value(&(boundary + (len - boundary) / 2));
+        assert_contents(&high, boundary + (len - boundary) / 2..len);
+        assert_contents(&middle, boundary..boundary + (len - boundary) / 2);
+
+        assert!(middle.join(high).is_ok());
+        assert_contents(&middle, boundary..len);
+        assert!(middle.join(low).is_ok());
+        assert_contents(&middle, 0..len);
+    }
+
+    let mut small = new();
+    for i in 1000..1003 {
+        small.insert(i);
+    }
+    let large = I::from_sorted(0..1000);
+    assert!(small.join(large).is_ok());
+    assert_contents(&small, 0..1003);
+
+    let overlapping = I::from_sorted([5, 2000]);
+    let overlapping = small.join(overlapping).unwrap_err();
+    assert_eq!(overlapping.len(), 2);
+    assert_contents(&small, 0..1003);
+}
+
+fn check_iter<I: Checked>() {
+    let mut rng = rand::rng();
+
+    let empty = I::default();
+    assert!(empty.iter().next().is_none());
+    assert_eq!(empty.range(3..10).len(), 0);
+
+    for _ in 0..50 {
+        let len: usize = rng.random_range(0..300);
+        let index = I::from_sorted(0..len as u32);
+        // index 0 holds the highest value
+        let value_at = |i: usize| (len - 1 - i) as u32;
+
+        let all: Vec<_> = index.iter().map(|(i, v)| (i, *v)).collect();
+        let expected: Vec<_> = (0..len).map(|i| (i, value_at(i))).collect();
+        assert!(all == expected);
+        assert!(index.iter().rev().map(|(i, _)| i).eq((0..len).rev()));
+
+        let start = rng.random_range(0..len + 2);
+        let end = rng.random_range(0..len + 2);
+        let mut range = index.range(start..end);
+        assert_eq!(range.len(), end.min(len).saturating_sub(start));
+        assert!(
+            index.range(start..=end).eq(index
+                .iter()
+                .skip(start)
+                .take((end + 1).saturating_sub(start)))
+        );
+        assert!(index.iter_from_index(start).eq(index.range(start..)));
+
+        // meet in the middle from both ends
+        let mut front = start;
+        let mut back = end.min(len);
+        loop {
+            let remaining = range.len();
+            let next = match rng.random_bool(0.5) {
+                true => range.next().map(|v| (v, front)),
+                false => range.next_back().map(|v| (v, back - 1)),
+            };
+            let Some(((index, val), expected_index)) = next else {
+                assert_eq!(remaining, 0);
+                break;
+            };
+            assert_eq!(index, expected_index);
+            assert_eq!(*val, value_at(index));
+            assert_eq!(range.len(), remaining - 1);
+            if index == front {
+                front += 1;
+            } else {
+                back -= 1;
+            }
+        }
+        assert!(range.next().is_none() && range.next_back().is_none());
     }
+}
+
+// The parts below are specific to the tree.
+
+#[test]
+fn test_cursor_mut() {
+    let mut tree: Tree<u32> = Tree::from_sorted(25..100);
 
-    // for i in 0..25 {
-    //     tree.validate();
-    //     assert!(tree.remove(&(49 - i)).is_some());
-    // };
     let mut cursor_mut = tree.seek_val_mut(&50).unwrap();
     for i in 0..25 {
         assert!(cursor_mut.delete_prev() == Some(49 - i));
@@ -95,7 +388,7 @@ fn test_100() {
 
     tree.remove(&52);
 
-    cursor_mut = tree.seek_val_mut(&49).unwrap();
+    let mut cursor_mut = tree.seek_val_mut(&49).unwrap();
     cursor_mut.replace(52);
 
     tree.validate();
@@ -104,15 +397,119 @@ fn test_100() {
 
     tree.remove(&4);
 
-    cursor_mut = tree.seek_val_mut(&52).unwrap();
+    let mut cursor_mut = tree.seek_val_mut(&52).unwrap();
     cursor_mut.replace(4);
 
     tree.validate();
     assert!(!tree.contains(&52));
     assert!(tree.contains(&4));
+}
+
+#[test]
+fn test_from_sorted_height() {
+    for len in [0usize, 1, 2, 3, 7, 8, 100, 1000] {
+        let tree: Tree<u32> = Tree::from_sorted(0..len as u32);
+        // perfectly balanced, the height is as small as possible
+        assert_eq!(tree.height(), (usize::BITS - len.leading_zeros()) as usize);
+    }
+}
+
+#[test]
+fn test_split_join_in_place() {
+    let mut high: Tree<u32> = Tree::from_sorted(0..1000);
+    let address =
+        |tree: &Tree<u32>, val: u32| tree.at_index(tree.index_of(&val).0).unwrap() as *const u32;
+    let before: Vec<_> = (0..1000).map(|val| address(&high, val)).collect();
+
+    // both halves keep the nodes where they were
+    let mut low = high.split_at_index(400);
+    assert!((0..600).all(|val| address(&low, val) == before[val as usize]));
+    assert!((600..1000).all(|val| address(&high, val) == before[val as usize]));
+
+    // and so does joining, even trees that were built apart
+    let mut other: Tree<u32> = Tree::from_sorted(1000..2000);
+    let others: Vec<_> = (1000..2000).map(|val| address(&other, val)).collect();
+    assert!(other.join(high).is_ok());
+    assert!(low.join(other).is_ok());
+    assert_contents(&low, 0..2000);
+    assert!((0..1000).all(|val| address(&low, val) == before[val as usize]));
+    assert!((1000..2000).all(|val| address(&low, val) == others[val as usize - 1000]));
+
+    // a clone gets nodes of its own
+    let copy = low.clone();
+    assert_contents(&copy, 0..2000);
+    assert!((0..2000).all(|val| address(&copy, val) != address(&low, val)));
+}
+
+#[test]
+fn test_shared_store_threads() {
+    let threads: Vec<_> = (0..4)
+        .map(|_| {
+            std::thread::spawn(|| {
+                for _ in 0..2 {
+                    check_random_ops(Tree::new());
+                    check_split_join(Tree::new);
+                }
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
+
+#[test]
+fn test_into_kind() {
+    let mut index: AnyIndex<u32> = AnyIndex::from_sorted(0..500);
+    assert!(index.kind() == IndexKind::Avl);
+
+    index = index.into_kind(IndexKind::SkipList);
+    assert!(index.kind() == IndexKind::SkipList);
+    assert_contents(&index, 0..500);
+
+    // joining takes on this index's kind
+    let low = AnyIndex::from_sorted(500..600).into_kind(IndexKind::Avl);
+    let mut high = AnyIndex::from_sorted(600..700).into_kind(IndexKind::SkipList);
+    assert!(high.join(low).is_ok());
+    assert!(high.kind() == IndexKind::SkipList);
+    assert_contents(&high, 500..700);
+}
+
+// The node layout before the arena, allocated one Box per node.
+#[allow(dead_code)]
+struct BoxedNode<V> {
+    count: usize,
+    height: usize,
+    left: Option<NonNull<BoxedNode<V>>>,
+    right: Option<NonNull<BoxedNode<V>>>,
+    parent: Option<NonNull<BoxedNode<V>>>,
+    is_left_child: bool,
+    val: V,
+}
+
+#[test]
+fn test_memory_per_entry() {
+    let len = 100_000;
+    let entries = (0..len).map(|i| Entry::<i64, f64> {
+        timestamp: 0.0,
+        points: i as f64,
+        key: i as i64,
+    });
+
+    let built: Tree<Entry<i64, f64>> = Tree::from_sorted(entries.clone());
+    let mut inserted: Tree<Entry<i64, f64>> = Tree::new();
+    for entry in entries {
+        inserted.insert(entry);
+    }
+
+    // each Box also carries the allocator's chunk header and is rounded up to 16 bytes
+    let boxed = (size_of::<BoxedNode<Entry<i64, f64>>>() + 8).next_multiple_of(16);
+    let built_per_entry = built.memory_usage() as f64 / len as f64;
+    let inserted_per_entry = inserted.memory_usage() as f64 / len as f64;
 
-    tree.clear();
-    assert!(tree.is_empty());
+    assert!(built_per_entry < boxed as f64);
+    // an arena keeps a few chunks of released slots for reuse
+    assert!(inserted_per_entry <= 2.0 * built_per_entry + 1.0);
 }
 
 // #[test]
@@ -1,114 +1,84 @@
-use std::ptr::NonNull;
+use crate::board::tree::node::{Arena, Link, NIL, SENTINEL};
 
-use super::Node;
 use super::Tree;
-use super::stacks::*;
 
-impl<V: Ord + Sized + Default> Tree<V> {
+impl<V: Ord + Sized + Default + Send + 'static> Tree<V> {
     pub fn new() -> Self {
-        unsafe {
-            Self {
-                sentinel: NonNull::new_unchecked(Box::into_raw(Box::new(Node {
-                    parent: None,
-                    left: None,
-                    right: None,
-                    count: 0,
-                    height: 0,
-                    is_left_child: false,
-                    val: V::default(),
-                }))),
-            }
+        Self {
+            arena: Arena::with_capacity(0),
         }
     }
-}
 
-impl<V: Ord + Sized + Default + Clone> Tree<V> {
-    pub fn from_tree(other: &Self) -> Self {
-        let tree = Self::new();
+    // Builds a perfectly balanced tree in O(n). Values must be unique and in ascending order.
+    pub fn from_sorted<I: IntoIterator<Item = V>>(values: I) -> Self
+    where
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut iter = values.into_iter();
+        let len = iter.len();
+        let mut tree = Self {
+            arena: Arena::with_capacity(len),
+        };
 
-        if other.is_empty() {
-            return tree;
+        let root = tree.build_sorted(&mut iter, len);
+        if root != NIL {
+            tree.arena[root].parent = SENTINEL;
+            tree.arena[root].is_left_child = false;
+            tree.arena[SENTINEL].right = root;
         }
 
-        unsafe {
-            let mut stack: Vec<CloneStackEntry<V>> = Vec::with_capacity(other.height());
-
-            stack.push(CloneStackEntry {
-                new_ptr: std::ptr::null_mut(),
-                ptr: (*other.sentinel.as_ptr()).right.unwrap().as_ptr(),
-                state: StackState::Left,
-            });
-            let mut last = stack.last_mut().unwrap();
-            let mut dir = false; // true is left, false is right
-            let mut parent = tree.sentinel.as_ptr();
-
-            loop {
-                match last.state {
-                    StackState::Left => {
-                        let new_node = Box::into_raw(Box::new(Node {
-                            count: (*last.ptr).count,
-                            height: (*last.ptr).height,
-                            left: None,
-                            right: None,
-                            parent: Some(NonNull::new_unchecked(parent)),
-                            is_left_child: dir,
-                            val: (*last.ptr).val.clone(),
-                        }));
+        tree
+    }
+}
 
-                        if dir {
-                            (*parent).left = Some(NonNull::new_unchecked(new_node));
-                        } else {
-                            (*parent).right = Some(NonNull::new_unchecked(new_node));
-                        }
+impl<V: Ord + Sized + Default> Tree<V> {
+    // Builds a subtree of len values in order, the caller sets the root's parent.
+    pub(super) fn build_sorted<I: Iterator<Item = V>>(&mut self, iter: &mut I, len: usize) -> Link {
+        if len == 0 {
+            return NIL;
+        }
 
-                        last.new_ptr = new_node;
-                        last.state = StackState::Right;
+        let left_len = (len - 1) / 2;
+        let left = self.build_sorted(iter, left_len);
 
-                        if let Some(child) = (*last.ptr).left {
-                            let entry = CloneStackEntry {
-                                new_ptr: std::ptr::null_mut(),
-                                ptr: child.clone().as_ptr(),
-                                state: StackState::Left,
-                            };
+        let node = self.arena.alloc(
+            iter.next()
+                .expect("Iterator yielded fewer values than its length."),
+            NIL,
+            false,
+        );
 
-                            dir = true;
-                            parent = new_node;
+        self.arena[node].left = left;
+        if left != NIL {
+            self.arena[left].parent = node;
+            self.arena[left].is_left_child = true;
+        }
 
-                            stack.push(entry);
-                            last = stack.last_mut().unwrap();
-                        }
-                    }
-                    StackState::Right => {
-                        last.state = StackState::Handle;
+        let right = self.build_sorted(iter, len - 1 - left_len);
+        self.arena[node].right = right;
+        if right != NIL {
+            self.arena[right].parent = node;
+            self.arena[right].is_left_child = false;
+        }
 
-                        if let Some(child) = (*last.ptr).right {
-                            let entry = CloneStackEntry {
-                                new_ptr: std::ptr::null_mut(),
-                                ptr: child.clone().as_ptr(),
-                                state: StackState::Left,
-                            };
+        self.arena.fix(node);
 
-                            dir = false;
-                            parent = last.new_ptr;
+        node
+    }
+}
 
-                            stack.push(entry);
-                            last = stack.last_mut().unwrap();
-                        }
-                    }
-                    StackState::Handle => {
-                        stack.pop();
-                        last = match stack.last_mut() {
-                            Some(v) => v,
-                            None => {
-                                break;
-                            }
-                        };
-                    }
-                };
-            }
-        };
+impl<V: Ord + Sized + Default + Send + 'static> Default for Tree<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        tree
+impl<V: Ord + Sized + Default + Clone> Tree<V> {
+    // copies every node into the same store, keeping the shape of the tree
+    pub fn from_tree(other: &Self) -> Self {
+        Self {
+            arena: other.arena.clone(),
+        }
     }
 }
 
@@ -0,0 +1,118 @@
+use std::{
+    iter::FusedIterator,
+    ops::{Bound, RangeBounds},
+};
+
+use crate::board::tree::node::{Link, NIL};
+
+use super::Tree;
+
+// Walks the tree from the highest value down, yielding (index, value). Both ends are found
+// in O(log n), after that each step is amortised O(1).
+#[derive(Clone)]
+pub struct Iter<'a, V: Ord + Sized + Default> {
+    tree: &'a Tree<V>,
+    front: Link,
+    back: Link,
+    front_index: usize,
+    remaining: usize,
+}
+
+impl<V: Ord + Sized + Default> Tree<V> {
+    pub fn iter(&self) -> Iter<'_, V> {
+        self.range(..)
+    }
+
+    pub fn iter_from_index(&self, index: usize) -> Iter<'_, V> {
+        self.range(index..)
+    }
+
+    pub fn range<R: RangeBounds<usize>>(&self, indices: R) -> Iter<'_, V> {
+        let start = match indices.start_bound() {
+            Bound::Included(&s) => s,
+            Bound::Excluded(&s) => s.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let end = match indices.end_bound() {
+            Bound::Included(&e) => e.saturating_add(1),
+            Bound::Excluded(&e) => e,
+            Bound::Unbounded => usize::MAX,
+        }
+        .min(self.len());
+
+        if start >= end {
+            return Iter {
+                tree: self,
+                front: NIL,
+                back: NIL,
+                front_index: start,
+                remaining: 0,
+            };
+        }
+
+        Iter {
+            tree: self,
+            front: self.node_at_index(start).unwrap(),
+            back: self.node_at_index(end - 1).unwrap(),
+            front_index: start,
+            remaining: end - start,
+        }
+    }
+}
+
+impl<'a, V: Ord + Sized + Default> IntoIterator for &'a Tree<V> {
+    type Item = (usize, &'a V);
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Iter<'a, V> {
+        self.iter()
+    }
+}
+
+impl<'a, V: Ord + Sized + Default> Iterator for Iter<'a, V> {
+    type Item = (usize, &'a V);
+
+    fn next(&mut self) -> Option<(usize, &'a V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let node = self.front;
+        let index = self.front_index;
+        self.remaining -= 1;
+        self.front_index += 1;
+        // don't step past the back, it may be the last node in the tree
+        if self.remaining > 0 {
+            self.front = self.tree.arena.prev_node(node);
+        }
+
+        return Some((index, &self.tree.arena[node].val));
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, V: Ord + Sized + Default> DoubleEndedIterator for Iter<'a, V> {
+    fn next_back(&mut self) -> Option<(usize, &'a V)> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let node = self.back;
+        self.remaining -= 1;
+        if self.remaining > 0 {
+            self.back = self.tree.arena.next_node(node);
+        }
+
+        return Some((
+            self.front_index + self.remaining,
+            &self.tree.arena[node].val,
+        ));
+    }
+}
+
+impl<'a, V: Ord + Sized + Default> ExactSizeIterator for Iter<'a, V> {}
+
+impl<'a, V: Ord + Sized + Default> FusedIterator for Iter<'a, V> {}
@@ -1,27 +1,19 @@
-use std::ptr::NonNull;
-
 mod constructors;
 mod io;
+mod iter;
 mod iteration;
 mod operations;
+mod ranked;
 mod read;
+mod split;
 mod stacks;
 mod test_funcs;
 
-use super::Node;
+use crate::board::tree::node::Arena;
 
-pub struct Tree<V: Ord + Sized + Default> {
-    sentinel: NonNull<Node<V>>,
-}
+pub use iter::Iter;
+pub use iteration::Cursor;
 
-impl<V: Ord + Sized + Default> Drop for Tree<V> {
-    fn drop(&mut self) {
-        self.clear();
-        unsafe {
-            let _ = Box::from_raw(self.sentinel.as_ptr());
-        };
-    }
+pub struct Tree<V: Ord + Sized + Default> {
+    arena: Arena<V>,
 }
-
-unsafe impl<V: Send + Ord + ?Sized + Default> Send for Tree<V> {}
-unsafe impl<V: Sync + Ord + ?Sized + Default> Sync for Tree<V> {}
@@ -0,0 +1,112 @@
+use std::ops::RangeBounds;
+
+use crate::board::ranked_index::{RankedCursor, RankedIndex};
+
+use super::{Cursor, Iter, Tree};
+
+// The tree's own methods take precedence, this only exposes them through the trait. Split and
+// join are overridden since the tree relinks its nodes in O(log n) instead of rebuilding from
+// every value.
+impl<V: Ord + Sized + Default + Clone + Send + 'static> RankedIndex<V> for Tree<V> {
+    type Cursor<'a>
+        = Cursor<'a, V>
+    where
+        Self: 'a;
+    type Iter<'a>
+        = Iter<'a, V>
+    where
+        Self: 'a;
+
+    fn from_sorted<I: IntoIterator<Item = V>>(values: I) -> Self
+    where
+        I::IntoIter: ExactSizeIterator,
+    {
+        Tree::from_sorted(values)
+    }
+
+    fn insert(&mut self, val: V) -> bool {
+        Tree::insert(self, val)
+    }
+
+    fn remove(&mut self, val: &V) -> Option<V> {
+        Tree::remove(self, val)
+    }
+
+    fn replace(&mut self, old_val: &V, new_val: V) -> Option<V> {
+        Tree::replace(self, old_val, new_val)
+    }
+
+    fn index_of(&self, val: &V) -> (usize, bool) {
+        Tree::index_of(self, val)
+    }
+
+    fn at_index(&self, index: usize) -> Option<&V> {
+        Tree::at_index(self, index)
+    }
+
+    fn len(&self) -> usize {
+        Tree::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        Tree::is_empty(self)
+    }
+
+    fn clear(&mut self) {
+        Tree::clear(self)
+    }
+
+    fn cursor(&self) -> Cursor<'_, V> {
+        Tree::cursor(self)
+    }
+
+    fn seek_index(&self, index: usize) -> Option<Cursor<'_, V>> {
+        Tree::seek_index(self, index)
+    }
+
+    fn range<R: RangeBounds<usize>>(&self, indices: R) -> Iter<'_, V> {
+        Tree::range(self, indices)
+    }
+
+    fn memory_usage(&self) -> usize {
+        Tree::memory_usage(self)
+    }
+
+    fn into_sorted(self) -> Vec<V> {
+        Tree::into_sorted(self)
+    }
+
+    fn split_at_index(&mut self, index: usize) -> Self {
+        Tree::split_at_index(self, index)
+    }
+
+    fn split_at_value(&mut self, val: &V) -> Self {
+        Tree::split_at_value(self, val)
+    }
+
+    fn join(&mut self, other: Self) -> Result<(), Self> {
+        Tree::join(self, other)
+    }
+}
+
+impl<'a, V: Ord + Sized + Default + Clone> RankedCursor<V> for Cursor<'a, V> {
+    fn move_next(&mut self) -> Option<&V> {
+        Cursor::move_next(self)
+    }
+
+    fn move_prev(&mut self) -> Option<&V> {
+        Cursor::move_prev(self)
+    }
+
+    fn get_value(&self) -> Option<&V> {
+        Cursor::get_value(self)
+    }
+
+    fn get_index(&mut self) -> Option<usize> {
+        Cursor::get_index(self)
+    }
+
+    fn is_at_end(&self) -> bool {
+        Cursor::is_at_end(self)
+    }
+}
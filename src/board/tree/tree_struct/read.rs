@@ -1,122 +1,121 @@
 use std::cmp;
 
-use crate::board::tree::node::Node;
+use crate::board::tree::node::{Link, NIL, SENTINEL};
 
 use super::Tree;
 
 impl<V: Ord + Sized + Default> Tree<V> {
     pub fn contains(&self, val: &V) -> bool {
-        unsafe {
-            let mut parent = match (*self.sentinel.as_ptr()).right {
-                None => {
-                    return false;
-                }
-                Some(ptr) => ptr.as_ptr(),
-            };
+        let mut parent = match self.arena[SENTINEL].right {
+            NIL => {
+                return false;
+            }
+            link => link,
+        };
 
-            loop {
-                match val.cmp(&(*parent).val) {
-                    cmp::Ordering::Equal => {
-                        return true;
-                    }
-                    cmp::Ordering::Greater => {
-                        if (*parent).right.is_none() {
-                            return false;
-                        }
-                        parent = (*parent).right.unwrap().as_ptr();
+        loop {
+            match val.cmp(&self.arena[parent].val) {
+                cmp::Ordering::Equal => {
+                    return true;
+                }
+                cmp::Ordering::Greater => {
+                    if self.arena[parent].right == NIL {
+                        return false;
                     }
-                    cmp::Ordering::Less => {
-                        if (*parent).left.is_none() {
-                            return false;
-                        }
-                        parent = (*parent).left.unwrap().as_ptr();
+                    parent = self.arena[parent].right;
+                }
+                cmp::Ordering::Less => {
+                    if self.arena[parent].left == NIL {
+                        return false;
                     }
-                };
-            }
+                    parent = self.arena[parent].left;
+                }
+            };
         }
     }
 
     pub fn index_of(&self, val: &V) -> (usize, bool) {
         let mut ind: usize = 0;
-        unsafe {
-            let mut parent = match (*self.sentinel.as_ptr()).right {
-                None => {
-                    return (0, false);
-                }
-                Some(ptr) => ptr.as_ptr(),
-            };
+        let mut parent = match self.arena[SENTINEL].right {
+            NIL => {
+                return (0, false);
+            }
+            link => link,
+        };
 
-            loop {
-                match val.cmp(&(*parent).val) {
-                    cmp::Ordering::Equal => {
-                        ind += Node::get_right_count(parent);
-                        return (ind, true);
-                    }
-                    cmp::Ordering::Greater => {
-                        if (*parent).right.is_none() {
-                            return (ind, false);
-                        }
-                        parent = (*parent).right.unwrap().as_ptr();
+        loop {
+            match val.cmp(&self.arena[parent].val) {
+                cmp::Ordering::Equal => {
+                    ind += self.arena.get_right_count(parent);
+                    return (ind, true);
+                }
+                cmp::Ordering::Greater => {
+                    if self.arena[parent].right == NIL {
+                        return (ind, false);
                     }
-                    cmp::Ordering::Less => {
-                        ind += 1 + Node::get_right_count(parent);
-                        if (*parent).left.is_none() {
-                            return (ind, false);
-                        }
-                        parent = (*parent).left.unwrap().as_ptr();
+                    parent = self.arena[parent].right;
+                }
+                cmp::Ordering::Less => {
+                    ind += 1 + self.arena.get_right_count(parent);
+                    if self.arena[parent].left == NIL {
+                        return (ind, false);
                     }
-                };
-            }
+                    parent = self.arena[parent].left;
+                }
+            };
         }
     }
 
-    pub(super) fn node_at_index(&self, ind: usize) -> Option<*mut Node<V>> {
+    pub(super) fn node_at_index(&self, ind: usize) -> Option<Link> {
         let mut amount = ind;
-        unsafe {
-            let mut parent = (*self.sentinel.as_ptr()).right?.as_ptr();
+        let mut parent = match self.arena[SENTINEL].right {
+            NIL => {
+                return None;
+            }
+            link => link,
+        };
 
-            loop {
-                let right = Node::get_right_count(parent);
-                match amount.cmp(&right) {
-                    cmp::Ordering::Equal => {
-                        return Some(parent);
-                    }
-                    cmp::Ordering::Less => {
-                        if (*parent).right.is_none() {
-                            return None;
-                        }
-                        parent = (*parent).right.unwrap().as_ptr();
+        loop {
+            let right = self.arena.get_right_count(parent);
+            match amount.cmp(&right) {
+                cmp::Ordering::Equal => {
+                    return Some(parent);
+                }
+                cmp::Ordering::Less => {
+                    if self.arena[parent].right == NIL {
+                        return None;
                     }
-                    cmp::Ordering::Greater => {
-                        if (*parent).left.is_none() {
-                            return None;
-                        }
-                        amount -= right + 1;
-                        parent = (*parent).left.unwrap().as_ptr();
+                    parent = self.arena[parent].right;
+                }
+                cmp::Ordering::Greater => {
+                    if self.arena[parent].left == NIL {
+                        return None;
                     }
-                };
-            }
+                    amount -= right + 1;
+                    parent = self.arena[parent].left;
+                }
+            };
         }
     }
 
     pub fn at_index<'l>(&'l self, ind: usize) -> Option<&'l V> {
-        unsafe { self.node_at_index(ind).map(|v| &(*v).val) }
+        self.node_at_index(ind).map(|v| &self.arena[v].val)
     }
 
     pub fn is_empty(&self) -> bool {
-        unsafe { (*self.sentinel.as_ptr()).right.is_none() }
+        self.arena[SENTINEL].right == NIL
     }
 
     pub fn len(&self) -> usize {
-        unsafe { Node::get_right_count(self.sentinel.as_ptr()) }
+        self.arena.get_right_count(SENTINEL)
     }
 
     pub fn height(&self) -> usize {
-        unsafe {
-            match (*self.sentinel.as_ptr()).right {
-                Some(ptr) => (*ptr.as_ptr()).height,
-                None => 0,
-            }
-        }
+        self.arena.get_right_height(SENTINEL) as usize
+    }
+
+    // bytes reserved for nodes, including the sentinel and any released slots
+    pub fn memory_usage(&self) -> usize {
+        self.arena.memory_usage()
     }
 }
@@ -0,0 +1,206 @@
+use std::mem;
+
+use crate::board::tree::node::{Link, NIL, SENTINEL};
+
+use super::Tree;
+
+impl<V: Ord + Sized + Default> Tree<V> {
+    // Moves every value from index onwards (the lowest values) into a new tree, keeping the first
+    // index values. Both trees keep their nodes in the same store, so only O(log n) nodes are
+    // relinked and nothing is copied.
+    pub fn split_at_index(&mut self, index: usize) -> Tree<V> {
+        let root = self.detach_root();
+        let (low, high) = self.split_links_index(root, index);
+        self.part(low, high)
+    }
+
+    // Moves every value below val into a new tree, in O(log n) like split_at_index.
+    pub fn split_at_value(&mut self, val: &V) -> Tree<V> {
+        let root = self.detach_root();
+        let (low, high) = self.split_links_value(root, val);
+        self.part(low, high)
+    }
+
+    // Joins a tree whose values all lie above or below the values of this one in O(log n), taking
+    // over its nodes where they are. If the ranges overlap the other tree is given back untouched.
+    pub fn join(&mut self, mut other: Tree<V>) -> Result<(), Tree<V>> {
+        if other.is_empty() {
+            return Ok(());
+        }
+        if self.is_empty() {
+            mem::swap(self, &mut other);
+            return Ok(());
+        }
+
+        let other_is_low = other.arena[other.last_link()].val < self.arena[self.first_link()].val;
+        if !other_is_low && other.arena[other.first_link()].val <= self.arena[self.last_link()].val {
+            return Err(other);
+        }
+
+        let moved = self.arena.adopt(&mut other.arena);
+        let root = self.detach_root();
+        let joined = if other_is_low {
+            self.join_links(moved, root)
+        } else {
+            self.join_links(root, moved)
+        };
+        self.attach_root(joined);
+
+        Ok(())
+    }
+
+    // every value, lowest first
+    pub fn into_sorted(mut self) -> Vec<V> {
+        let root = self.detach_root();
+        self.drain_links(root)
+    }
+
+    // Keeps high and returns low as a tree sharing this one's store.
+    fn part(&mut self, low: Link, high: Link) -> Tree<V> {
+        self.attach_root(high);
+        let mut part = Tree {
+            arena: self.arena.share(),
+        };
+        part.attach_root(low);
+        part
+    }
+
+    fn link_count(&self, link: Link) -> usize {
+        match link {
+            NIL => 0,
+            link => self.arena[link].count as usize,
+        }
+    }
+
+    fn detach_root(&mut self) -> Link {
+        let root = self.arena[SENTINEL].right;
+        self.arena[SENTINEL].right = NIL;
+        root
+    }
+
+    fn attach_root(&mut self, root: Link) {
+        self.arena.set_child(SENTINEL, root, false);
+    }
+
+    fn first_link(&self) -> Link {
+        let mut node = self.arena[SENTINEL].right;
+        while self.arena[node].left != NIL {
+            node = self.arena[node].left;
+        }
+        node
+    }
+
+    fn last_link(&self) -> Link {
+        let mut node = self.arena[SENTINEL].right;
+        while self.arena[node].right != NIL {
+            node = self.arena[node].right;
+        }
+        node
+    }
+
+    // Splits a detached subtree into the values below index and the first index values.
+    fn split_links_index(&mut self, root: Link, index: usize) -> (Link, Link) {
+        if root == NIL {
+            return (NIL, NIL);
+        }
+
+        let left = self.arena[root].left;
+        let right = self.arena[root].right;
+        let right_count = self.arena.get_right_count(root);
+
+        if index <= right_count {
+            let (low, high) = self.split_links_index(right, index);
+            (self.join_links_with(left, root, low), high)
+        } else {
+            let (low, high) = self.split_links_index(left, index - right_count - 1);
+            (low, self.join_links_with(high, root, right))
+        }
+    }
+
+    // Splits a detached subtree into the values below val and the values from val upwards.
+    fn split_links_value(&mut self, root: Link, val: &V) -> (Link, Link) {
+        if root == NIL {
+            return (NIL, NIL);
+        }
+
+        let left = self.arena[root].left;
+        let right = self.arena[root].right;
+
+        if self.arena[root].val < *val {
+            let (low, high) = self.split_links_value(right, val);
+            (self.join_links_with(left, root, low), high)
+        } else {
+            let (low, high) = self.split_links_value(left, val);
+            (low, self.join_links_with(high, root, right))
+        }
+    }
+
+    // Joins two detached subtrees where every value in low is below every value in high.
+    fn join_links(&mut self, low: Link, high: Link) -> Link {
+        if low == NIL {
+            return high;
+        }
+        if high == NIL {
+            return low;
+        }
+
+        let (low, mid) = self.pop_last_link(low);
+        self.join_links_with(low, mid, high)
+    }
+
+    // Joins two detached subtrees around a detached middle node, returning the new root.
+    fn join_links_with(&mut self, low: Link, mid: Link, high: Link) -> Link {
+        let low_height = self.arena.get_height(low);
+        let high_height = self.arena.get_height(high);
+
+        if low_height > high_height + 1 {
+            let inner = self.arena[low].right;
+            let joined = self.join_links_with(inner, mid, high);
+            self.arena.set_child(low, joined, false);
+            return self.arena.balance(low);
+        } else if high_height > low_height + 1 {
+            let inner = self.arena[high].left;
+            let joined = self.join_links_with(low, mid, inner);
+            self.arena.set_child(high, joined, true);
+            return self.arena.balance(high);
+        }
+
+        self.arena.set_child(mid, low, true);
+        self.arena.set_child(mid, high, false);
+        self.arena.fix(mid);
+        mid
+    }
+
+    // Removes the highest node from a detached subtree, returning the new root and the node.
+    fn pop_last_link(&mut self, root: Link) -> (Link, Link) {
+        let right = self.arena[root].right;
+        if right == NIL {
+            return (self.arena[root].left, root);
+        }
+
+        let (right, last) = self.pop_last_link(right);
+        self.arena.set_child(root, right, false);
+        (self.arena.balance(root), last)
+    }
+
+    // Takes the values of a detached subtree in ascending order, releasing its nodes.
+    fn drain_links(&mut self, root: Link) -> Vec<V> {
+        let mut values = Vec::with_capacity(self.link_count(root));
+        let mut stack = Vec::new();
+        let mut node = root;
+
+        loop {
+            while node != NIL {
+                stack.push(node);
+                node = self.arena[node].left;
+            }
+            let Some(next) = stack.pop() else {
+                break;
+            };
+            node = self.arena[next].right;
+            values.push(self.arena.release(next));
+        }
+
+        values
+    }
+}
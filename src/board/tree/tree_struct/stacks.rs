@@ -1,4 +1,4 @@
-use super::Node;
+use crate::board::tree::node::Link;
 
 #[derive(PartialEq, Eq)]
 pub(super) enum StackState {
@@ -6,12 +6,7 @@ pub(super) enum StackState {
     Right,
     Handle,
 }
-pub(super) struct StackEntry<V: Ord + Sized + Default> {
-    pub(super) ptr: *mut Node<V>,
-    pub(super) state: StackState,
-}
-pub(super) struct CloneStackEntry<V: Ord + Sized + Default> {
-    pub(super) new_ptr: *mut Node<V>,
-    pub(super) ptr: *mut Node<V>,
+pub(super) struct StackEntry {
+    pub(super) link: Link,
     pub(super) state: StackState,
 }
@@ -1,93 +1,95 @@
 use std::cmp;
 use std::fmt::Display;
 
+use crate::board::tree::node::{NIL, SENTINEL};
+
 use super::Tree;
 use super::stacks::*;
 
 impl<V: Ord + Sized + Default + Clone> Tree<V> {
     pub fn validate(&self) {
-        unsafe {
-            let mut stack: Vec<StackEntry<V>> = Vec::with_capacity(self.height());
+        let mut stack: Vec<StackEntry> = Vec::with_capacity(self.height());
 
-            if self.is_empty() {
-                return;
-            }
+        if self.is_empty() {
+            return;
+        }
 
-            stack.push(StackEntry {
-                ptr: (*self.sentinel.as_ptr()).right.unwrap().as_ptr(),
-                state: StackState::Left,
-            });
-            let mut last = stack.last_mut().unwrap();
-            let mut dir = false; // left is true, right is false
+        stack.push(StackEntry {
+            link: self.arena[SENTINEL].right,
+            state: StackState::Left,
+        });
+        let mut last = stack.last_mut().unwrap();
+        let mut dir = false; // left is true, right is false
 
-            loop {
-                match last.state {
-                    StackState::Left => {
-                        if dir != (*last.ptr).is_left_child {
-                            panic!("Is left child does not match if it is a left child!");
-                        }
-                        if let Some(left) = (*last.ptr).left {
-                            if (*left.as_ptr()).parent.unwrap().as_ptr() != last.ptr {
-                                panic!("Parent does not agree with left!");
-                            }
-                        }
-                        if let Some(right) = (*last.ptr).right {
-                            if (*right.as_ptr()).parent.unwrap().as_ptr() != last.ptr {
-                                panic!("Parent does not agree with right!");
-                            }
-                        }
-                        // if Node::is_imbalanced(last.ptr) {
-                        //     panic!("Tree is imbalanced!");
-                        // }
-
-                        last.state = StackState::Right;
-                        if let Some(child) = (*last.ptr).left {
-                            match (*child.as_ptr()).cmp(&*last.ptr) {
-                                cmp::Ordering::Greater => {
-                                    panic!("Incorrect ordering! Node is left whilst being greater.")
-                                }
-                                cmp::Ordering::Equal => panic!("Multiple equal nodes in tree!"),
-                                cmp::Ordering::Less => {}
-                            };
-                            let entry = StackEntry {
-                                ptr: child.clone().as_ptr(),
-                                state: StackState::Left,
-                            };
-                            stack.push(entry);
-                            last = stack.last_mut().unwrap();
-                            dir = true;
-                        }
+        loop {
+            let node = &self.arena[last.link];
+            match last.state {
+                StackState::Left => {
+                    if dir != node.is_left_child {
+                        panic!("Is left child does not match if it is a left child!");
                     }
-                    StackState::Right => {
-                        last.state = StackState::Handle;
-                        if let Some(child) = (*last.ptr).right {
-                            match (*child.as_ptr()).cmp(&*last.ptr) {
-                                cmp::Ordering::Less => {
-                                    panic!("Incorrect ordering! Node is right whilst being lesser.")
-                                }
-                                cmp::Ordering::Equal => panic!("Multiple equal nodes in tree!"),
-                                cmp::Ordering::Greater => {}
-                            };
-                            let entry = StackEntry {
-                                ptr: child.clone().as_ptr(),
-                                state: StackState::Left,
-                            };
-                            stack.push(entry);
-                            last = stack.last_mut().unwrap();
-                            dir = false;
-                        }
+                    if node.left != NIL && self.arena[node.left].parent != last.link {
+                        panic!("Parent does not agree with left!");
                     }
-                    StackState::Handle => {
-                        stack.pop();
-                        last = match stack.last_mut() {
-                            Some(v) => v,
-                            None => {
-                                break;
+                    if node.right != NIL && self.arena[node.right].parent != last.link {
+                        panic!("Parent does not agree with right!");
+                    }
+                    // if self.arena.is_imbalanced(last.link) {
+                    //     panic!("Tree is imbalanced!");
+                    // }
+
+                    last.state = StackState::Right;
+                    if node.left != NIL {
+                        match self.arena[node.left].cmp(node) {
+                            cmp::Ordering::Greater => {
+                                panic!("Incorrect ordering! Node is left whilst being greater.")
                             }
+                            cmp::Ordering::Equal => panic!("Multiple equal nodes in tree!"),
+                            cmp::Ordering::Less => {}
+                        };
+                        let entry = StackEntry {
+                            link: node.left,
+                            state: StackState::Left,
                         };
+                        stack.push(entry);
+                        last = stack.last_mut().unwrap();
+                        dir = true;
                     }
-                };
-            }
+                }
+                StackState::Right => {
+                    last.state = StackState::Handle;
+                    if node.right != NIL {
+                        match self.arena[node.right].cmp(node) {
+                            cmp::Ordering::Less => {
+                                panic!("Incorrect ordering! Node is right whilst being lesser.")
+                            }
+                            cmp::Ordering::Equal => panic!("Multiple equal nodes in tree!"),
+                            cmp::Ordering::Greater => {}
+                        };
+                        let entry = StackEntry {
+                            link: node.right,
+                            state: StackState::Left,
+                        };
+                        stack.push(entry);
+                        last = stack.last_mut().unwrap();
+                        dir = false;
+                    }
+                }
+                StackState::Handle => {
+                    if node.count as usize
+                        != 1 + self.arena.get_left_count(last.link) + self.arena.get_right_count(last.link)
+                    {
+                        panic!("Count does not match children!");
+                    }
+                    stack.pop();
+                    last = match stack.last_mut() {
+                        Some(v) => v,
+                        None => {
+                            break;
+                        }
+                    };
+                }
+            };
         }
     }
 }
@@ -1,18 +1,23 @@
 use std::{
+    collections::HashMap,
     fs::File,
     io::{self, BufRead, BufReader, BufWriter, Write},
     path::PathBuf,
     sync::{Arc, Mutex},
-    time::Instant,
+    time::{Instant, SystemTime},
 };
 
+use bincode::Encode;
 use rand::distr::{Distribution, Uniform};
 
 use crate::{
     Key, Val,
-    app_state::AppState,
-    backend::{self, Interaction, User},
-    board::Board,
+    app_state::{self, AppState, ServerBoard},
+    backend::{self, ApiError, Interaction, User},
+    board::{Board, IndexKind, RankCheckpoint, SavedEntries},
+    persist::{self, Compression, JournalRecord},
+    scope::Scopes,
+    transfer::{ConflictPolicy, TransferFormat},
 };
 
 fn create_interaction<'a>(
@@ -27,6 +32,70 @@ fn create_interaction<'a>(
 
 static SET_BOARD_PROMPT: &str = "No current board set, please set it with 'board <board_name>'.";
 
+// The admin API can remove the current board while a command runs on it.
+fn on_board<T>(result: Result<T, ApiError>) -> Option<T> {
+    if result.is_err() {
+        let _ = writeln!(
+            &mut io::stdout().lock(),
+            "The current board was removed while the command ran."
+        );
+    }
+    result.ok()
+}
+
+// Writes a save for the stress test, returning how long it took.
+fn write_test_file<T: Encode>(
+    value: &T,
+    count: usize,
+    compression: Compression,
+    path: &PathBuf,
+) -> Result<f64, String> {
+    let start = Instant::now();
+    let handle = File::create(path)
+        .map_err(|err| format!("Failed to open temp file to save board.\n{err}"))?;
+
+    persist::write_board_file::<Key, Val, _, _>(
+        value,
+        count,
+        compression,
+        &mut BufWriter::new(handle),
+    )
+    .map_err(|err| format!("Failed to serialize leaderboard:\n{err}"))?;
+
+    Ok(start.elapsed().as_secs_f64())
+}
+
+fn read_test_file(path: &PathBuf) -> Result<ServerBoard, String> {
+    let bytes = std::fs::read(path)
+        .map_err(|err| format!("Failed to read file for leaderboard:\n{err}"))?;
+    let (saved, _) = persist::read_board_file(&bytes)
+        .map_err(|err| format!("Failed to parse file for leaderboard:\n{err}"))?;
+
+    Ok(Board::from_saved(saved))
+}
+
+fn file_len(path: &PathBuf) -> u64 {
+    std::fs::metadata(path).map(|meta| meta.len()).unwrap_or(0)
+}
+
+// Roughly how long ago time was, in its largest unit.
+fn format_age(time: SystemTime) -> String {
+    let secs = time.elapsed().unwrap_or_default().as_secs();
+    match secs {
+        0..60 => format!("{secs} seconds"),
+        60..3600 => format!("{} minutes", secs / 60),
+        3600..86400 => format!("{} hours", secs / 3600),
+        _ => format!("{} days", secs / 86400),
+    }
+}
+
+fn index_kind_name(kind: IndexKind) -> &'static str {
+    match kind {
+        IndexKind::Avl => "an AVL tree",
+        IndexKind::SkipList => "a skip list",
+    }
+}
+
 pub fn confirm_action() -> bool {
     let stdout = io::stdout();
 
@@ -67,6 +136,21 @@ pub fn execute_command(
     let stdout = io::stdout();
     let cmd = params.get(0).unwrap();
 
+    // a reload may have removed the current board
+    let mut user = current_user.lock().unwrap();
+    if let Some(removed) = user
+        .as_ref()
+        .filter(|user| cmd_arc.get_board(&user.board).is_none())
+    {
+        let _ = writeln!(
+            &mut stdout.lock(),
+            "Board \"{}\" no longer exists.",
+            removed.board
+        );
+        let _ = std::mem::replace(&mut *user, None);
+    }
+    let _ = drop(user);
+
     match cmd.to_lowercase().as_str() {
         "save" => {
             if params.len() > 1 {
@@ -76,6 +160,207 @@ pub fn execute_command(
 
             backend::save(&cmd_arc, cmd_saves_path);
         }
+        "reload" => {
+            if params.len() > 1 {
+                let _ = writeln!(&mut stdout.lock(), "Usage: reload");
+                return;
+            }
+
+            match backend::reload(cmd_arc, cmd_saves_path) {
+                Ok(changes) if changes.is_empty() => {
+                    let _ = writeln!(&mut stdout.lock(), "Nothing changed.");
+                }
+                Ok(changes) => {
+                    let _ = writeln!(&mut stdout.lock(), "{}", changes.join("\n"));
+                }
+                Err(err) => {
+                    let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                    return;
+                }
+            }
+        }
+        "backups" => {
+            let usage_msg = "Usage: backups <board_name>";
+
+            if params.len() != 2 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            let board_name = params[1].to_string();
+            if cmd_arc.get_board(&board_name).is_none() {
+                let _ = writeln!(&mut stdout.lock(), "No board named \"{board_name}\".");
+                return;
+            }
+
+            let backups = match persist::list_backups(cmd_saves_path, &board_name) {
+                Ok(v) => v,
+                Err(err) => {
+                    let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                    return;
+                }
+            };
+
+            if backups.is_empty() {
+                let _ = writeln!(
+                    &mut stdout.lock(),
+                    "No backups of \"{board_name}\". They are taken on full saves when \"backups\" is set in config.json."
+                );
+                return;
+            }
+
+            let mut out = stdout.lock();
+            for backup in backups.iter() {
+                let _ = writeln!(
+                    &mut out,
+                    "{}\t{} ago\t{} bytes",
+                    backup.id,
+                    format_age(backup.created),
+                    backup.size
+                );
+            }
+        }
+        "restore" => {
+            let usage_msg = "Usage: restore <board_name> <backup>";
+
+            if params.len() != 3 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            let board_name = params[1].to_string();
+            if cmd_arc.get_board(&board_name).is_none() {
+                let _ = writeln!(&mut stdout.lock(), "No board named \"{board_name}\".");
+                return;
+            }
+
+            let backup = match persist::find_backup(cmd_saves_path, &board_name, params[2]) {
+                Ok(Some(v)) => v,
+                Ok(None) => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "No backup {} of \"{board_name}\", see 'backups {board_name}'.",
+                        params[2]
+                    );
+                    return;
+                }
+                Err(err) => {
+                    let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                    return;
+                }
+            };
+
+            let _ = writeln!(
+                &mut stdout.lock(),
+                "Replace every entry of board {board_name} with the backup from {} ago? Changes since then will be lost.",
+                format_age(backup.created)
+            );
+
+            if !confirm_action() {
+                return;
+            }
+
+            match cmd_arc.restore_board(&board_name, &backup) {
+                Ok(size) => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "Restored {size} entries to \"{board_name}\"."
+                    );
+                    backend::save(&cmd_arc, cmd_saves_path);
+                }
+                Err(err) => {
+                    let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                }
+            }
+        }
+        "quarantine" => {
+            if params.len() > 1 {
+                let _ = writeln!(&mut stdout.lock(), "Usage: quarantine");
+                return;
+            }
+
+            let quarantined = match persist::list_quarantined(cmd_saves_path) {
+                Ok(v) => v,
+                Err(err) => {
+                    let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                    return;
+                }
+            };
+
+            if quarantined.is_empty() {
+                let _ = writeln!(&mut stdout.lock(), "No saves are in quarantine.");
+                return;
+            }
+
+            let mut out = stdout.lock();
+            for item in quarantined.iter() {
+                let read_only = match cmd_arc.is_read_only(&item.name) {
+                    true => ", read-only",
+                    false => "",
+                };
+                let _ = writeln!(
+                    &mut out,
+                    "{}\t{}\t{} ago{read_only}",
+                    item.name,
+                    item.id,
+                    format_age(item.created)
+                );
+                for line in item.reason.lines() {
+                    let _ = writeln!(&mut out, "\t{line}");
+                }
+            }
+        }
+        "retry" => {
+            let usage_msg = "Usage: retry <board_name> [id]";
+
+            if params.len() != 2 && params.len() != 3 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            let board_name = params[1].to_string();
+            if cmd_arc.get_board(&board_name).is_none() {
+                let _ = writeln!(&mut stdout.lock(), "No board named \"{board_name}\".");
+                return;
+            }
+
+            // the newest unless it is given
+            let found = match params.get(2) {
+                Some(id) => persist::find_quarantined(cmd_saves_path, &board_name, id),
+                None => persist::list_quarantined(cmd_saves_path)
+                    .map(|list| list.into_iter().find(|item| item.name == board_name)),
+            };
+            let quarantined = match found {
+                Ok(Some(v)) => v,
+                Ok(None) => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "Nothing of \"{board_name}\" to retry, see 'quarantine'."
+                    );
+                    return;
+                }
+                Err(err) => {
+                    let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                    return;
+                }
+            };
+
+            match cmd_arc.retry_quarantined(&quarantined) {
+                Ok(size) => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "Loaded the quarantined save of \"{board_name}\", it now has {size} entries."
+                    );
+                    backend::save(&cmd_arc, cmd_saves_path);
+                }
+                Err(err) => {
+                    let _ = writeln!(
+                        &mut io::stderr().lock(),
+                        "Still can't load the save of {board_name}, it stays in quarantine.\n{err}"
+                    );
+                }
+            }
+        }
         "update" => {
             let usage_msg = "Usage: update <user_id> <points>";
 
@@ -117,11 +402,14 @@ pub fn execute_command(
                 return;
             }
 
-            match backend::update_entry(
+            let Some(result) = on_board(backend::update_entry(
                 &create_interaction(&current_user, &cmd_arc),
                 user_id,
                 points,
-            ) {
+            )) else {
+                return;
+            };
+            match result {
                 Ok(b) => match b {
                     true => {
                         let _ = writeln!(
@@ -168,9 +456,13 @@ pub fn execute_command(
                 return;
             }
 
-            if backend::remove_entry(&create_interaction(&current_user, &cmd_arc), user_id)
-                .is_some()
-            {
+            let Some(removed) = on_board(backend::remove_entry(
+                &create_interaction(&current_user, &cmd_arc),
+                user_id,
+            )) else {
+                return;
+            };
+            if removed.is_some() {
                 let _ = writeln!(&mut stdout.lock(), "Removed {user_id}.");
             } else {
                 let _ = writeln!(&mut stdout.lock(), "{user_id} is not on the leaderboard.");
@@ -203,7 +495,13 @@ pub fn execute_command(
                 return;
             }
 
-            match backend::get_points(&create_interaction(&current_user, &cmd_arc), &user_id) {
+            let Some(points) = on_board(backend::get_points(
+                &create_interaction(&current_user, &cmd_arc),
+                &user_id,
+            )) else {
+                return;
+            };
+            match points {
                 Some(pts) => {
                     let _ = writeln!(&mut stdout.lock(), "User {user_id} has {pts} points.");
                 }
@@ -239,9 +537,14 @@ pub fn execute_command(
                 return;
             }
 
-            for entry in
-                backend::get_top(&create_interaction(&current_user, &cmd_arc), count, true).iter()
-            {
+            let Some(top) = on_board(backend::get_top(
+                &create_interaction(&current_user, &cmd_arc),
+                count,
+                true,
+            )) else {
+                return;
+            };
+            for entry in top.iter() {
                 let _ = writeln!(
                     &mut stdout.lock(),
                     "{}:\t{}\t({} points)",
@@ -292,11 +595,14 @@ pub fn execute_command(
                 return;
             }
 
-            let result = match backend::get_after(
+            let Some(result) = on_board(backend::get_after(
                 &create_interaction(&current_user, &cmd_arc),
                 &user_id,
                 count,
-            ) {
+            )) else {
+                return;
+            };
+            let result = match result {
                 Some(v) => v,
                 None => {
                     let _ = writeln!(&mut stdout.lock(), "User id {user_id} is not in the board.");
@@ -355,11 +661,14 @@ pub fn execute_command(
                 return;
             }
 
-            let result = match backend::get_before(
+            let Some(result) = on_board(backend::get_before(
                 &create_interaction(&current_user, &cmd_arc),
                 &user_id,
                 count,
-            ) {
+            )) else {
+                return;
+            };
+            let result = match result {
                 Some(v) => v,
                 None => {
                     let _ = writeln!(&mut stdout.lock(), "User id {user_id} is not in the board.");
@@ -432,12 +741,15 @@ pub fn execute_command(
                 return;
             }
 
-            let result = match backend::get_around(
+            let Some(result) = on_board(backend::get_around(
                 &create_interaction(&current_user, &cmd_arc),
                 &user_id,
                 before,
                 after,
-            ) {
+            )) else {
+                return;
+            };
+            let result = match result {
                 Some(v) => v,
                 None => {
                     let _ = writeln!(&mut stdout.lock(), "User id {user_id} is not in the board.");
@@ -496,8 +808,13 @@ pub fn execute_command(
                 return;
             }
 
-            let result =
-                backend::get_range(&create_interaction(&current_user, &cmd_arc), start, end);
+            let Some(result) = on_board(backend::get_range(
+                &create_interaction(&current_user, &cmd_arc),
+                start,
+                end,
+            )) else {
+                return;
+            };
 
             if result.len() == 0 {
                 let _ = writeln!(
@@ -544,11 +861,14 @@ pub fn execute_command(
                 return;
             }
 
-            for entry in
-                backend::get_bottom(&create_interaction(&current_user, &cmd_arc), count, true)
-                    .iter()
-                    .rev()
-            {
+            let Some(bottom) = on_board(backend::get_bottom(
+                &create_interaction(&current_user, &cmd_arc),
+                count,
+                true,
+            )) else {
+                return;
+            };
+            for entry in bottom.iter().rev() {
                 let _ = writeln!(
                     &mut stdout.lock(),
                     "{}:\t{}\t({} points)",
@@ -585,7 +905,13 @@ pub fn execute_command(
                 return;
             }
 
-            match backend::get_rank(&create_interaction(&current_user, &cmd_arc), &user_id) {
+            let Some(rank) = on_board(backend::get_rank(
+                &create_interaction(&current_user, &cmd_arc),
+                &user_id,
+            )) else {
+                return;
+            };
+            match rank {
                 Some(rank) => {
                     let _ = writeln!(
                         &mut stdout.lock(),
@@ -624,7 +950,13 @@ pub fn execute_command(
                 return;
             }
 
-            match backend::at_rank(&create_interaction(&current_user, &cmd_arc), rank.clone()) {
+            let Some(entry) = on_board(backend::at_rank(
+                &create_interaction(&current_user, &cmd_arc),
+                rank.clone(),
+            )) else {
+                return;
+            };
+            match entry {
                 Some(entry) => {
                     let _ = writeln!(
                         &mut stdout.lock(),
@@ -652,11 +984,13 @@ pub fn execute_command(
                 return;
             }
 
-            let _ = writeln!(
-                &mut stdout.lock(),
-                "Current board has {} entries.",
-                backend::get_size(&create_interaction(&current_user, &cmd_arc))
-            );
+            let Some(size) = on_board(backend::get_size(&create_interaction(
+                &current_user,
+                &cmd_arc,
+            ))) else {
+                return;
+            };
+            let _ = writeln!(&mut stdout.lock(), "Current board has {size} entries.");
         }
         "clear" => {
             let usage_msg = "Usage: clear";
@@ -677,8 +1011,9 @@ pub fn execute_command(
             );
             if confirm_action() {
                 let _ = writeln!(&mut stdout.lock(), "Clearing data...");
-                backend::clear(&create_interaction(current_user, cmd_arc));
-                let _ = writeln!(&mut stdout.lock(), "Cleared.");
+                if on_board(backend::clear(&create_interaction(current_user, cmd_arc))).is_some() {
+                    let _ = writeln!(&mut stdout.lock(), "Cleared.");
+                }
             }
         }
         "populate" => {
@@ -709,8 +1044,8 @@ pub fn execute_command(
             }
 
             let interaction = create_interaction(current_user, cmd_arc);
-            let mut binding = interaction.state.boards.lock().unwrap();
-            let board = binding.get_mut(&interaction.user.board).unwrap();
+            let binding = interaction.state.get_board(&interaction.user.board).unwrap();
+            let mut board = binding.write().unwrap();
             let empty = board.get_size() == 0;
 
             if !empty {
@@ -728,6 +1063,15 @@ pub fn execute_command(
                 for i in 0..count {
                     let _ = board.update_entry(i + 1, i as Val);
                 }
+
+                let mut records = vec![JournalRecord::Clear];
+                records.extend(
+                    board
+                        .iter()
+                        .rev()
+                        .map(|(_, entry)| JournalRecord::Update(entry.clone())),
+                );
+                cmd_arc.journal(&interaction.user.board, &records);
             }
         }
         "board" => {
@@ -753,7 +1097,7 @@ pub fn execute_command(
                 }
             };
 
-            if !(cmd_arc.boards.lock().unwrap().contains_key(&board)) {
+            if !(cmd_arc.boards.read().unwrap().contains_key(&board)) {
                 let _ = writeln!(
                     &mut stdout.lock(),
                     "Invalid board '{board}', does not exist."
@@ -766,7 +1110,9 @@ pub fn execute_command(
                 &mut *state,
                 Some(backend::User {
                     board: board,
-                    write: true,
+                    scopes: Scopes::all(),
+                    boards: HashMap::new(),
+                    player: None,
                 }),
             );
         }
@@ -799,8 +1145,8 @@ pub fn execute_command(
                 },
                 None => {
                     let interaction = create_interaction(current_user, cmd_arc);
-                    let mut binding = interaction.state.boards.lock().unwrap();
-                    let board = binding.get_mut(&interaction.user.board).unwrap();
+                    let binding = interaction.state.get_board(&interaction.user.board).unwrap();
+                    let board = binding.read().unwrap();
 
                     if let Some(cap) = board.get_size_cap() {
                         let _ = writeln!(&mut stdout.lock(), "Current size cap: {}.", cap);
@@ -815,8 +1161,8 @@ pub fn execute_command(
             cmd_arc.set_board_cap(&board_name, cap);
 
             let interaction = create_interaction(current_user, cmd_arc);
-            let mut binding = interaction.state.boards.lock().unwrap();
-            let board = binding.get_mut(&interaction.user.board).unwrap();
+            let binding = interaction.state.get_board(&interaction.user.board).unwrap();
+            let mut board = binding.write().unwrap();
 
             let proceed = board.get_size() <= cap;
 
@@ -830,57 +1176,85 @@ pub fn execute_command(
 
             if proceed || confirm_action() {
                 let _ = writeln!(&mut stdout.lock(), "Trimming entries...");
-                board.trim_after_cap();
+                let trimmed = board.trim_after_cap();
+                cmd_arc.journal_removed(&board_name, &trimmed);
             }
         }
-        "new_board" => {
-            let usage_msg = "Usage: new_board <name>";
+        "rank_tracking" => {
+            let usage_msg = "Usage: rank_tracking <off/reset/save/minutes>";
 
             if params.len() > 2 {
                 let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
                 return;
             }
 
-            let name = match params.get(1) {
-                Some(&"") | None => {
-                    let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+            if current_user.lock().unwrap().is_none() {
+                let _ = writeln!(&mut stdout.lock(), "{SET_BOARD_PROMPT}");
+                return;
+            }
+
+            let board_name = current_user.lock().unwrap().as_ref().unwrap().board.clone();
+
+            let mode = match params.get(1) {
+                Some(&"off") => None,
+                Some(&"reset") => Some(RankCheckpoint::Reset),
+                Some(&"save") => Some(RankCheckpoint::Save),
+                Some(b) => match b.parse::<u64>() {
+                    Ok(v) if v > 0 => Some(RankCheckpoint::Interval(v)),
+                    _ => {
+                        let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                        return;
+                    }
+                },
+                None => {
+                    let board = cmd_arc.get_board(&board_name).unwrap();
+                    let board = board.read().unwrap();
+
+                    match board.get_rank_checkpoint() {
+                        None => {
+                            let _ = writeln!(&mut stdout.lock(), "Rank tracking is off.");
+                        }
+                        Some(RankCheckpoint::Reset) => {
+                            let _ = writeln!(
+                                &mut stdout.lock(),
+                                "Previous ranks are recorded whenever the board is cleared."
+                            );
+                        }
+                        Some(RankCheckpoint::Save) => {
+                            let _ = writeln!(
+                                &mut stdout.lock(),
+                                "Previous ranks are recorded whenever the board is saved."
+                            );
+                        }
+                        Some(RankCheckpoint::Interval(minutes)) => {
+                            let _ = writeln!(
+                                &mut stdout.lock(),
+                                "Previous ranks are recorded every {minutes} minutes."
+                            );
+                        }
+                    }
                     return;
                 }
-                Some(b) => b,
             };
 
-            for c in name.chars() {
-                let ascii = c.to_ascii_uppercase() as u8;
-                if (ascii >= 65 && ascii <= 90)
-                    || (ascii == 95)
-                    || (ascii == 45)
-                    || (ascii == 46)
-                    || (ascii >= 48 && ascii <= 57)
-                {
-                    continue;
+            cmd_arc.set_board_rank_checkpoint(&board_name, mode);
+
+            if mode.is_some() {
+                if let Some(board) = cmd_arc.get_board(&board_name) {
+                    board.write().unwrap().take_rank_checkpoint();
                 }
                 let _ = writeln!(
                     &mut stdout.lock(),
-                    "Invalid character \"{}\" in board name.",
-                    c
+                    "Rank tracking enabled, current ranks recorded as the first checkpoint."
                 );
-                return;
-            }
-
-            if cmd_arc.create_board(name.to_string()) {
-                let _ = writeln!(&mut stdout.lock(), "Created board \"{}\".", name);
             } else {
-                let _ = writeln!(
-                    &mut stdout.lock(),
-                    "Board already exists with name \"{}\".",
-                    name
-                );
+                let _ = writeln!(&mut stdout.lock(), "Rank tracking disabled.");
             }
         }
-        "del_board" => {
-            let usage_msg = "Usage: del_board";
+        "index" => {
+            let usage_msg = "Usage: index <avl/skip_list>";
 
-            if params.len() > 1 {
+            if params.len() > 2 {
                 let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
                 return;
             }
@@ -891,32 +1265,116 @@ pub fn execute_command(
             }
 
             let board_name = current_user.lock().unwrap().as_ref().unwrap().board.clone();
+
+            let kind = match params.get(1) {
+                Some(&"avl") => IndexKind::Avl,
+                Some(&"skip_list") => IndexKind::SkipList,
+                Some(_) => {
+                    let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                    return;
+                }
+                None => {
+                    let board = cmd_arc.get_board(&board_name).unwrap();
+                    let kind = board.read().unwrap().get_index_kind();
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "Entries are stored in {}.",
+                        index_kind_name(kind)
+                    );
+                    return;
+                }
+            };
+
+            let start = Instant::now();
+            cmd_arc.set_board_index_kind(&board_name, kind);
             let _ = writeln!(
                 &mut stdout.lock(),
-                "Are you want to delete board {} and all of its contents and data? This data cannot be retrieved.",
-                board_name
+                "Entries moved to {} in {:.4} seconds.",
+                index_kind_name(kind),
+                start.elapsed().as_secs_f64()
             );
-
-            if confirm_action() {
-                let _ = writeln!(&mut stdout.lock(), "Deleting....");
-                cmd_arc.delete_board(&board_name);
-
-                let mut state = current_user.lock().unwrap();
-                let _ = std::mem::replace(&mut *state, None);
-            }
         }
-        "keys" => {
-            let usage_msg = "Usage: keys";
+        "new_board" => {
+            let usage_msg = "Usage: new_board <name>";
 
-            if params.len() > 1 {
+            if params.len() > 2 {
                 let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
                 return;
             }
 
-            let keys = cmd_arc.api_keys.lock().unwrap();
+            let name = match params.get(1) {
+                Some(&"") | None => {
+                    let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                    return;
+                }
+                Some(b) => b,
+            };
 
-            if current_user.lock().unwrap().is_none() {
-                let _ = writeln!(&mut stdout.lock(), "{SET_BOARD_PROMPT}");
+            if let Some(c) = app_state::invalid_name_char(name) {
+                let _ = writeln!(
+                    &mut stdout.lock(),
+                    "Invalid character \"{}\" in board name.",
+                    c
+                );
+                return;
+            }
+
+            match cmd_arc.create_board(name.to_string()) {
+                Ok(true) => {
+                    let _ = writeln!(&mut stdout.lock(), "Created board \"{}\".", name);
+                }
+                Ok(false) => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "Board already exists with name \"{}\".",
+                        name
+                    );
+                }
+                Err(err) => {
+                    let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                }
+            }
+        }
+        "del_board" => {
+            let usage_msg = "Usage: del_board";
+
+            if params.len() > 1 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            if current_user.lock().unwrap().is_none() {
+                let _ = writeln!(&mut stdout.lock(), "{SET_BOARD_PROMPT}");
+                return;
+            }
+
+            let board_name = current_user.lock().unwrap().as_ref().unwrap().board.clone();
+            let _ = writeln!(
+                &mut stdout.lock(),
+                "Are you want to delete board {} and all of its contents and data? This data cannot be retrieved.",
+                board_name
+            );
+
+            if confirm_action() {
+                let _ = writeln!(&mut stdout.lock(), "Deleting....");
+                cmd_arc.delete_board(&board_name);
+
+                let mut state = current_user.lock().unwrap();
+                let _ = std::mem::replace(&mut *state, None);
+            }
+        }
+        "keys" => {
+            let usage_msg = "Usage: keys";
+
+            if params.len() > 1 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            let keys = cmd_arc.api_keys.lock().unwrap();
+
+            if current_user.lock().unwrap().is_none() {
+                let _ = writeln!(&mut stdout.lock(), "{SET_BOARD_PROMPT}");
                 return;
             }
 
@@ -924,14 +1382,20 @@ pub fn execute_command(
             let mut ind = 0;
 
             for (key, user) in keys.iter() {
-                if user.board != board_name {
-                    continue;
-                }
+                let scopes = match user.access(&board_name) {
+                    Some(v) => v,
+                    None => continue,
+                };
                 let _ = writeln!(
                     &mut stdout.lock(),
-                    "{}:\t\t{}",
+                    "{}:\t\t{}{}",
                     key,
-                    if user.write { "write" } else { "read" }
+                    scopes.names(),
+                    if user.board != board_name {
+                        format!("\t(from board {})", user.board)
+                    } else {
+                        String::new()
+                    }
                 );
                 ind += 1;
             }
@@ -957,15 +1421,77 @@ pub fn execute_command(
             for (key, user) in keys.iter() {
                 let _ = writeln!(
                     &mut stdout.lock(),
-                    "{}:\t\t{}\t{}",
+                    "{}:\t\t{}\t{}{}",
                     key,
                     user.board,
-                    if user.write { "write" } else { "read" }
+                    user.scopes.names(),
+                    match user.player {
+                        Some(id) => format!("\t(user {id})"),
+                        None => String::new(),
+                    }
+                );
+                for (board, scopes) in user.boards.iter() {
+                    let _ = writeln!(&mut stdout.lock(), "\t\t\t{}\t{}", board, scopes.names());
+                }
+            }
+        }
+        "grant" => {
+            let usage_msg =
+                "Usage: grant <api_key> <board_name or pattern> <scopes or write y/n, or none>";
+
+            if params.len() != 4 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            let key = params[1].to_string();
+            let board = params[2].to_string();
+            if board.is_empty() {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+            if let Some(c) = app_state::invalid_pattern_char(&board) {
+                let _ = writeln!(
+                    &mut stdout.lock(),
+                    "Invalid character \"{}\" in board pattern.",
+                    c
                 );
+                return;
+            }
+
+            let scopes = match params[3] {
+                "none" => None,
+                list => match Scopes::parse(list) {
+                    Ok(v) => Some(v),
+                    Err(err) => {
+                        let _ = writeln!(&mut stdout.lock(), "{err}\n{usage_msg}");
+                        return;
+                    }
+                },
+            };
+
+            if !cmd_arc.set_key_board_access(&key, &board, scopes) {
+                let _ = writeln!(&mut stdout.lock(), "API Key {key} does not exist!");
+                return;
+            }
+            match scopes {
+                Some(scopes) => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "API Key {key} can use {board} with scopes {}.",
+                        scopes.names()
+                    );
+                }
+                None => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "Removed {board} from the boards API Key {key} can use."
+                    );
+                }
             }
         }
         "new_key" => {
-            let usage_msg = "Usage: new_key <api_key> <write y/n>";
+            let usage_msg = "Usage: new_key <api_key> <scopes or write y/n>";
 
             if params.len() > 3 {
                 let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
@@ -985,10 +1511,13 @@ pub fn execute_command(
                 Some(b) => b,
             };
 
-            let w = match params.get(2) {
-                Some(&"y") | Some(&"Y") => true,
-                Some(&"n") | Some(&"N") => false,
-                _v => {
+            let scopes = match params.get(2).map(|v| Scopes::parse(v)) {
+                Some(Ok(v)) => v,
+                Some(Err(err)) => {
+                    let _ = writeln!(&mut stdout.lock(), "{err}\n{usage_msg}");
+                    return;
+                }
+                None => {
                     let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
                     return;
                 }
@@ -996,11 +1525,11 @@ pub fn execute_command(
 
             let board_name = current_user.lock().unwrap().as_ref().unwrap().board.clone();
 
-            if cmd_arc.create_key(key.to_string(), board_name.clone(), w) {
+            if cmd_arc.create_key(key.to_string(), board_name.clone(), scopes) {
                 let _ = writeln!(
                     &mut stdout.lock(),
-                    "Added API Key {key} to board {board_name} {} write access.",
-                    if w { "with" } else { "without" }
+                    "Added API Key {key} to board {board_name} with scopes {}.",
+                    scopes.names()
                 );
             } else {
                 let _ = writeln!(&mut stdout.lock(), "API Key {key} already in use!");
@@ -1029,7 +1558,7 @@ pub fn execute_command(
             }
         }
         "set_write" => {
-            let usage_msg = "Usage: set_write <api_key> <y/n>";
+            let usage_msg = "Usage: set_write <api_key> <scopes or write y/n>";
 
             if params.len() > 3 {
                 let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
@@ -1044,25 +1573,75 @@ pub fn execute_command(
                 Some(b) => b,
             };
 
-            let write = match params.get(2) {
-                Some(&"y") | Some(&"Y") => true,
-                Some(&"n") | Some(&"N") => false,
-                _v => {
+            let scopes = match params.get(2).map(|v| Scopes::parse(v)) {
+                Some(Ok(v)) => v,
+                Some(Err(err)) => {
+                    let _ = writeln!(&mut stdout.lock(), "{err}\n{usage_msg}");
+                    return;
+                }
+                None => {
                     let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
                     return;
                 }
             };
 
-            if cmd_arc.set_key_write_perms(&key.to_string(), write) {
+            if cmd_arc.set_key_scopes(&key.to_string(), scopes) {
                 let _ = writeln!(
                     &mut stdout.lock(),
-                    "Set API Key {key} to {} write access on its board.",
-                    if write { "have" } else { "not have" }
+                    "Set API Key {key} to scopes {} on its board.",
+                    scopes.names()
                 );
             } else {
                 let _ = writeln!(&mut stdout.lock(), "No API Key {key}!");
             }
         }
+        "set_player" => {
+            let usage_msg = "Usage: set_player <api_key> <user_id or none>";
+
+            if params.len() != 3 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            let key = match params.get(1) {
+                Some(&"") | None => {
+                    let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                    return;
+                }
+                Some(b) => b,
+            };
+
+            let player = match params.get(2) {
+                Some(&"none") => None,
+                Some(b) => match b.parse::<Key>() {
+                    Ok(id) => Some(id),
+                    Err(_) => {
+                        let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                        return;
+                    }
+                },
+                None => {
+                    let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                    return;
+                }
+            };
+
+            if !cmd_arc.set_key_player(&key.to_string(), player) {
+                let _ = writeln!(&mut stdout.lock(), "No API Key {key}!");
+                return;
+            }
+            match player {
+                Some(id) => {
+                    let _ = writeln!(&mut stdout.lock(), "API Key {key} now reads user {id}.");
+                }
+                None => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "API Key {key} is no longer bound to a user."
+                    );
+                }
+            }
+        }
         "trim" => {
             let usage_msg = "Usage: trim";
 
@@ -1077,11 +1656,12 @@ pub fn execute_command(
             }
 
             let interaction = create_interaction(current_user, cmd_arc);
-            let mut binding = interaction.state.boards.lock().unwrap();
-            let board = binding.get_mut(&interaction.user.board).unwrap();
+            let binding = interaction.state.get_board(&interaction.user.board).unwrap();
+            let mut board = binding.write().unwrap();
 
             if board.get_size_cap().is_none() || board.get_size() <= board.get_size_cap().unwrap() {
                 let _ = writeln!(&mut stdout.lock(), "Nothing to trim.");
+                return;
             }
 
             let _ = writeln!(
@@ -1092,7 +1672,282 @@ pub fn execute_command(
 
             if confirm_action() {
                 let _ = writeln!(&mut stdout.lock(), "Trimming entries...");
-                board.trim_after_cap();
+                let trimmed = board.trim_after_cap();
+                cmd_arc.journal_removed(&interaction.user.board, &trimmed);
+            }
+        }
+        "trim_rank" => {
+            let usage_msg = "Usage: trim_rank <rank>";
+
+            if params.len() != 2 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            if current_user.lock().unwrap().is_none() {
+                let _ = writeln!(&mut stdout.lock(), "{SET_BOARD_PROMPT}");
+                return;
+            }
+
+            let rank = match params[1].parse::<usize>() {
+                Ok(v) if v > 0 => v,
+                _ => {
+                    let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                    return;
+                }
+            };
+
+            let interaction = create_interaction(current_user, cmd_arc);
+            let binding = interaction.state.get_board(&interaction.user.board).unwrap();
+            let mut board = binding.write().unwrap();
+
+            if board.get_size() < rank {
+                let _ = writeln!(&mut stdout.lock(), "Nothing to trim.");
+                return;
+            }
+
+            let _ = writeln!(
+                &mut stdout.lock(),
+                "Do you want to remove all {} entries from rank {rank} down? This data will not be retrievable.",
+                board.get_size() - rank + 1
+            );
+
+            if confirm_action() {
+                let removed = board.split_at_rank(rank);
+                cmd_arc.journal_removed(&interaction.user.board, &removed);
+                let _ = writeln!(
+                    &mut stdout.lock(),
+                    "Removed {} entries.",
+                    removed.get_size()
+                );
+            }
+        }
+        "trim_points" => {
+            let usage_msg = "Usage: trim_points <points>";
+
+            if params.len() != 2 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            if current_user.lock().unwrap().is_none() {
+                let _ = writeln!(&mut stdout.lock(), "{SET_BOARD_PROMPT}");
+                return;
+            }
+
+            let points = match params[1].parse::<Val>() {
+                Ok(v) => v,
+                Err(_) => {
+                    let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                    return;
+                }
+            };
+
+            let _ = writeln!(
+                &mut stdout.lock(),
+                "Do you want to remove all entries with fewer than {points} points? This data will not be retrievable."
+            );
+
+            if confirm_action() {
+                let interaction = create_interaction(current_user, cmd_arc);
+                let binding = interaction.state.get_board(&interaction.user.board).unwrap();
+                let mut board = binding.write().unwrap();
+                let removed = board.split_at_points(points);
+                cmd_arc.journal_removed(&interaction.user.board, &removed);
+                let _ = writeln!(
+                    &mut stdout.lock(),
+                    "Removed {} entries.",
+                    removed.get_size()
+                );
+            }
+        }
+        "export" => {
+            let usage_msg = "Usage: export <csv/json/ndjson> <path>";
+
+            if params.len() != 3 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            if current_user.lock().unwrap().is_none() {
+                let _ = writeln!(&mut stdout.lock(), "{SET_BOARD_PROMPT}");
+                return;
+            }
+
+            let format = match TransferFormat::parse(params[1]) {
+                Some(v) => v,
+                None => {
+                    let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                    return;
+                }
+            };
+
+            let file = match File::create(params[2]) {
+                Ok(v) => v,
+                Err(err) => {
+                    let _ = writeln!(
+                        &mut io::stderr().lock(),
+                        "Failed to create {}.\n{err}",
+                        params[2]
+                    );
+                    return;
+                }
+            };
+
+            let interaction = create_interaction(current_user, cmd_arc);
+            match backend::export_board(&interaction, format, &mut BufWriter::new(file)) {
+                Ok(count) => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "Exported {count} entries to {}.",
+                        params[2]
+                    );
+                }
+                Err(err) => {
+                    let _ = writeln!(&mut io::stderr().lock(), "Failed to export.\n{err}");
+                }
+            }
+        }
+        "import" => {
+            let usage_msg = "Usage: import <path> <replace/skip/keep_better> [csv/json/ndjson]";
+
+            if params.len() != 3 && params.len() != 4 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            if current_user.lock().unwrap().is_none() {
+                let _ = writeln!(&mut stdout.lock(), "{SET_BOARD_PROMPT}");
+                return;
+            }
+
+            let path = PathBuf::from(params[1]);
+            let policy = match ConflictPolicy::parse(params[2]) {
+                Some(v) => v,
+                None => {
+                    let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                    return;
+                }
+            };
+
+            // the file's extension unless it is given
+            let format = match params.get(3) {
+                Some(name) => TransferFormat::parse(name),
+                None => path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .and_then(TransferFormat::parse),
+            };
+            let format = match format {
+                Some(v) => v,
+                None => {
+                    let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                    return;
+                }
+            };
+
+            let file = match File::open(&path) {
+                Ok(v) => v,
+                Err(err) => {
+                    let _ = writeln!(
+                        &mut io::stderr().lock(),
+                        "Failed to open {}.\n{err}",
+                        params[1]
+                    );
+                    return;
+                }
+            };
+
+            let interaction = create_interaction(current_user, cmd_arc);
+            let start = Instant::now();
+            match backend::import_board(&interaction, BufReader::new(file), format, policy) {
+                Ok(summary) => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "Imported in {:.2}s: {} added, {} replaced, {} skipped, {} rejected.",
+                        start.elapsed().as_secs_f64(),
+                        summary.added,
+                        summary.replaced,
+                        summary.skipped,
+                        summary.rejected
+                    );
+                }
+                Err(err) => {
+                    let _ = writeln!(&mut io::stderr().lock(), "Failed to import.\n{err}");
+                }
+            }
+        }
+        "merge" => {
+            let usage_msg = "Usage: merge <board_name>";
+
+            if params.len() != 2 {
+                let _ = writeln!(&mut stdout.lock(), "{usage_msg}");
+                return;
+            }
+
+            if current_user.lock().unwrap().is_none() {
+                let _ = writeln!(&mut stdout.lock(), "{SET_BOARD_PROMPT}");
+                return;
+            }
+
+            let board_name = current_user.lock().unwrap().as_ref().unwrap().board.clone();
+            let other_name = params[1].to_string();
+
+            if other_name == board_name {
+                let _ = writeln!(&mut stdout.lock(), "Cannot merge a board into itself.");
+                return;
+            }
+
+            let other_board = match cmd_arc.get_board(&other_name) {
+                Some(v) => v,
+                None => {
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "Invalid board '{other_name}', does not exist."
+                    );
+                    return;
+                }
+            };
+
+            let _ = writeln!(
+                &mut stdout.lock(),
+                "Do you want to move every entry of '{other_name}' into '{board_name}'? '{other_name}' will be left empty."
+            );
+
+            if !confirm_action() {
+                return;
+            }
+
+            let board = cmd_arc.get_board(&board_name).unwrap();
+            let mut board = board.write().unwrap();
+            let mut other_board = other_board.write().unwrap();
+
+            let entries = other_board.split_at_rank(1);
+            let size = entries.get_size();
+            let moved: Vec<JournalRecord<Key, Val>> = entries
+                .iter()
+                .rev()
+                .map(|(_, entry)| JournalRecord::Update(entry.clone()))
+                .collect();
+            let removed: Vec<JournalRecord<Key, Val>> = entries
+                .iter()
+                .map(|(_, entry)| JournalRecord::Remove(entry.key))
+                .collect();
+            match board.join(entries) {
+                Ok(trimmed) => {
+                    // journaled with both boards still locked, so a crash can't keep half a merge
+                    cmd_arc.journal(&other_name, &removed);
+                    cmd_arc.journal(&board_name, &moved);
+                    cmd_arc.journal_removed(&board_name, &trimmed);
+                    let _ = writeln!(&mut stdout.lock(), "Merged {size} entries.");
+                }
+                Err(entries) => {
+                    let _ = other_board.join(*entries);
+                    let _ = writeln!(
+                        &mut stdout.lock(),
+                        "Boards can only be merged when every entry of one ranks above every entry of the other and they share no keys."
+                    );
+                }
             }
         }
         "boards" => {
@@ -1102,7 +1957,7 @@ pub fn execute_command(
             }
 
             let mut ind = 0;
-            for (name, _board) in cmd_arc.boards.lock().unwrap().iter() {
+            for (name, _board) in cmd_arc.boards.read().unwrap().iter() {
                 let _ = writeln!(&mut stdout.lock(), "{ind}: {name}");
                 ind += 1;
             }
@@ -1153,8 +2008,8 @@ pub fn execute_command(
             let board_name = current_user.lock().unwrap().as_ref().unwrap().board.clone();
 
             let interaction = create_interaction(current_user, cmd_arc);
-            let mut binding = interaction.state.boards.lock().unwrap();
-            let board = binding.get_mut(&interaction.user.board).unwrap();
+            let binding = interaction.state.get_board(&interaction.user.board).unwrap();
+            let mut board = binding.write().unwrap();
             let empty = board.get_size() == 0;
 
             if !empty {
@@ -1167,33 +2022,33 @@ pub fn execute_command(
                     let _ = writeln!(&mut stdout.lock(), "Clearing existing data...");
 
                     board.clear();
+                    cmd_arc.journal(&board_name, &[JournalRecord::Clear]);
                 }
             }
 
-            let _ = drop(binding);
+            let _ = drop(board);
 
             let _ = writeln!(&mut stdout.lock(), "Capping entries...");
             cmd_arc.set_board_cap(&board_name, usize_size);
 
-            let mut binding = interaction.state.boards.lock().unwrap();
-            let board = binding.get_mut(&interaction.user.board).unwrap();
+            let binding = interaction.state.get_board(&interaction.user.board).unwrap();
+            let mut board = binding.write().unwrap();
 
             let _ = writeln!(&mut stdout.lock(), "Populating with dummy entries...");
             for i in 0..size {
                 let _ = board.update_entry(i, i as Val);
             }
 
-            let _ = drop(binding);
+            let _ = drop(board);
 
             let mut snapshot = None;
 
-            if !cmd_arc.lock_save {
+            if !cmd_arc.settings().lock_save {
                 snapshot = Some(
                     cmd_arc
-                        .boards
-                        .lock()
+                        .get_board(&board_name)
                         .unwrap()
-                        .get(&board_name)
+                        .read()
                         .unwrap()
                         .get_map_snapshot(),
                 ); // just so things are slowed down
@@ -1232,10 +2087,9 @@ pub fn execute_command(
             let _ = writeln!(&mut stdout.lock(), "Preparing...");
 
             let ids: Vec<Key> = cmd_arc
-                .boards
-                .lock()
+                .get_board(&board_name)
                 .unwrap()
-                .get(&board_name)
+                .read()
                 .unwrap()
                 .get_ids();
             let ind_range = Uniform::try_from(0..ids.len()).unwrap();
@@ -1324,118 +2178,151 @@ pub fn execute_command(
             let mut snapshot_clone_time = None;
             let mut snapshot_clone = None;
 
-            if !cmd_arc.lock_save {
+            if !cmd_arc.settings().lock_save {
                 let snapshot = cmd_arc
-                    .boards
-                    .lock()
+                    .get_board(&board_name)
                     .unwrap()
-                    .get(&board_name)
+                    .read()
                     .unwrap()
                     .get_map_snapshot();
 
-                snapshot_clone = Some(snapshot.get_lock().clone());
+                snapshot_clone = Some(SavedEntries::from_map(&snapshot.get_lock()));
                 snapshot_clone_time = Some(start.elapsed().as_secs_f64());
                 start = Instant::now();
 
                 let _ = drop(snapshot);
             }
 
-            match File::create(&temp_path) {
-                Ok(handle) => {
-                    let mut buf_writer = BufWriter::new(handle);
+            let compression = cmd_arc.board_compression(&board_name);
+            let result;
+            let mut sorted = None;
 
-                    let result;
+            if cmd_arc.settings().lock_save {
+                let board = cmd_arc.get_board(&board_name).unwrap();
+                let board = board.read().unwrap();
 
-                    if cmd_arc.lock_save {
-                        let boards = cmd_arc.boards.lock().unwrap();
-                        let board = boards.get(&board_name);
+                result = write_test_file(&*board, board.get_size(), compression, &temp_path);
 
-                        result = bincode::encode_into_std_write(
-                            &board,
-                            &mut buf_writer,
-                            bincode::config::standard(),
-                        );
+                let _ = drop(board);
+            } else {
+                start = Instant::now();
 
-                        let _ = drop(boards);
-                    } else {
-                        start = Instant::now();
+                let mut saved = snapshot_clone.unwrap();
+                saved.sort();
 
-                        result = bincode::encode_into_std_write(
-                            &snapshot_clone.unwrap(),
-                            &mut buf_writer,
-                            bincode::config::standard(),
-                        );
-                    }
+                result = write_test_file(&saved, saved.0.len(), compression, &temp_path);
+                sorted = Some(saved);
+            }
 
-                    if let Err(e) = result {
-                        let _ = writeln!(
-                            &mut io::stderr().lock(),
-                            "Failed to serialize leaderboard:\n{e}"
-                        );
-                    }
-                }
+            // just the encode and write, without taking the snapshot or sorting it
+            let encode_time = match result {
+                Ok(time) => time,
                 Err(err) => {
-                    let _ = writeln!(
-                        &mut io::stderr().lock(),
-                        "Failed to open temp file to save board.\n{}",
-                        err
-                    );
+                    let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                    0.0
                 }
             };
 
             let write_file_time = start.elapsed().as_secs_f64();
+            let file_size = file_len(&temp_path);
+
+            // the same entries again without compression, to show what compressing costs
+            let plain_path = cmd_arc
+                .saves_path
+                .join(format!("{board_name}_saving_plain.test"));
+            let mut plain_write_time = None;
+
+            if compression != Compression::None {
+                let _ = writeln!(
+                    &mut stdout.lock(),
+                    "Writing to file without compression to compare..."
+                );
+
+                let board = cmd_arc.get_board(&board_name).unwrap();
+                let board = board.read().unwrap();
+
+                let result = match &sorted {
+                    Some(saved) => {
+                        write_test_file(saved, saved.0.len(), Compression::None, &plain_path)
+                    }
+                    None => {
+                        write_test_file(&*board, board.get_size(), Compression::None, &plain_path)
+                    }
+                };
+                match result {
+                    Ok(time) => plain_write_time = Some(time),
+                    Err(err) => {
+                        let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                    }
+                }
+
+                let _ = drop(board);
+            }
+            let _ = drop(sorted);
 
             let _ = writeln!(&mut stdout.lock(), "Preparing to read from file...");
 
-            let mut binding = cmd_arc.boards.lock().unwrap();
-            let board = binding.get_mut(&interaction.user.board).unwrap();
+            let binding = cmd_arc.get_board(&interaction.user.board).unwrap();
+            let mut board = binding.write().unwrap();
             board.clear();
+            cmd_arc.journal(&interaction.user.board, &[JournalRecord::Clear]);
 
-            let _ = drop(binding);
+            let _ = drop(board);
 
             let _ = writeln!(&mut stdout.lock(), "Reading from file...");
             start = Instant::now();
 
-            let board: Board<Key, Val>;
-
-            match File::open(temp_path.clone()) {
-                Err(e) => {
-                    let _ = writeln!(
-                        &mut io::stderr().lock(),
-                        "Failed to read file for leaderboard:\n{e}",
-                    );
-                    board = Board::new();
-                }
-                Ok(save_file) => {
-                    let mut buf_reader = BufReader::new(save_file);
-
-                    board = match bincode::decode_from_std_read(
-                        &mut buf_reader,
-                        bincode::config::standard(),
-                    ) {
-                        Err(e) => {
-                            let _ = writeln!(
-                                &mut io::stderr().lock(),
-                                "Failed to parse file for leaderboard:\n{e}",
-                            );
-                            Board::new()
-                        }
-                        Ok(b) => b,
-                    };
+            let board = match read_test_file(&temp_path) {
+                Ok(board) => board,
+                Err(err) => {
+                    let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                    Board::new()
                 }
             };
 
             let read_file_time = start.elapsed().as_secs_f64();
+            let _ = drop(board);
 
-            let _ = writeln!(&mut stdout.lock(), "Cleaning up...");
+            let mut compression_report = format!("The save takes {file_size} bytes.");
 
-            let _ = drop(board);
+            if let Some(plain_write_time) = plain_write_time {
+                let _ = writeln!(
+                    &mut stdout.lock(),
+                    "Reading from file without compression to compare..."
+                );
+                start = Instant::now();
+
+                match read_test_file(&plain_path) {
+                    Ok(board) => {
+                        let plain_read_time = start.elapsed().as_secs_f64();
+                        let _ = drop(board);
+                        let plain_size = file_len(&plain_path);
+
+                        compression_report = format!(
+                            "With {} compression the save takes {file_size} bytes, {:.1}% of the {plain_size} bytes without it.\n\
+                            Compressing adds {:.4} seconds to writing and decompressing adds {:.4} seconds to reading.",
+                            compression.name(),
+                            file_size as f64 / plain_size.max(1) as f64 * 100.0,
+                            encode_time - plain_write_time,
+                            read_file_time - plain_read_time
+                        );
+                    }
+                    Err(err) => {
+                        let _ = writeln!(&mut io::stderr().lock(), "{err}");
+                    }
+                }
+            }
+
+            let _ = writeln!(&mut stdout.lock(), "Cleaning up...");
 
             if temp_path.exists() {
                 let _ = std::fs::remove_file(temp_path);
             }
+            if plain_path.exists() {
+                let _ = std::fs::remove_file(plain_path);
+            }
 
-            if cmd_arc.lock_save {
+            if cmd_arc.settings().lock_save {
                 let _ = writeln!(
                     &mut stdout.lock(),
                     "\nRESULTS:\n\
@@ -1447,6 +2334,7 @@ pub fn execute_command(
                 Getting the bottom 50 entries takes roughly {:.4} ms.\n\
                 Saving to file will take {:.4} seconds. All operations on the board will halt while this is in progress.\n\
                 Reading from file will take {:.4} seconds.\n\
+                {compression_report}\n\
                 *Please note that these tests do not factor things in like parsing HTTP requests and thus should not be trusted entirely. These lengths were calculated by directly performing these operations and should only serve as a benchmark or rough reference. The read write operation tests should be completely accurate in terms of length, however.",
                     write_time * 1000.0,
                     read_time * 1000.0,
@@ -1472,6 +2360,7 @@ pub fn execute_command(
                 Before writing to file, everything will slow for ROUGHLY {:.4} seconds.\n\
                 Writing to file will take {:.4} seconds in total.\n\
                 Reading from file will take {:.4} seconds.\n\
+                {compression_report}\n\
                 *Please note that these tests do not factor things in like parsing HTTP requests and thus should not be trusted entirely. These lengths were calculated by directly performing these operations and should only serve as a benchmark or rough reference. The read write operation tests should be completely accurate in terms of length, however.",
                     write_time * 1000.0,
                     read_time * 1000.0,
@@ -1501,9 +2390,11 @@ pub fn execute_command(
             \n\
             keys:\t\t\t\tList all API Keys on the current board.\n\
             all_keys:\t\t\tList all API Keys on all boards.\n\
-            new_key <api_key> <write y/n>:\tCreates a new API Key on the current board with specified permissions.\n\
+            new_key <api_key> <scopes or write y/n>:\tCreates a new API Key on the current board with the given scopes, a comma separated list of read, read_own, submit, remove, clear and admin, which is all scopes on this board. y gives read,submit,remove and n gives read.\n\
             del_key <api_key>:\t\tRemoves the specified API Key from the current board.\n\
-            set_write <api_key> <y/n>:\tSets whether or not a specific API Key on the current board has write permissions.\n\
+            set_write <api_key> <scopes or write y/n>:\tSets the scopes a specific API Key has on its board, the same way as new_key.\n\
+            set_player <api_key> <user_id or none>:\tBinds an API Key to the one user it can look up with read_own, 'none' unbinds it.\n\
+            grant <api_key> <board> <scopes or y/n, or none>:\tLets an API Key name another board, or every board matching a pattern with *, in /boards/<board_name>/... requests, with the given scopes. 'none' takes it back.\n\
             \n\
             get <user_id>:\t\t\tGets the number of points the specified user has on the current board.\n\
             rank <user_id>:\t\t\tGets the rank of the specified user in the leaderboard.\n\
@@ -1527,8 +2418,22 @@ pub fn execute_command(
             cap:\t\t\t\tGet the size cap of the current leaderboard.\n\
             cap <size>:\t\t\tSet the size cap of the current leaderboard. Set to -1 to remove cap.\n\
             trim:\t\t\t\tTrims off elements from the end of the current leaderboard until it's size is under the cap.\n\
+            trim_rank <rank>:\t\tRemoves every entry from <rank> down on the current leaderboard.\n\
+            trim_points <points>:\t\tRemoves every entry with fewer than <points> points on the current leaderboard.\n\
+            export <format> <path>:\t\tWrites the current leaderboard to a csv, json or ndjson file in rank order.\n\
+            import <path> <policy> [format]:\tReads entries from a file into the current leaderboard. Existing entries are kept on 'skip', replaced on 'replace', or whichever has more points on 'keep_better'.\n\
+            merge <board_name>:\t\tMoves every entry of another board into the current one, when their ranges do not overlap.\n\
+            rank_tracking:\t\t\tGet when previous ranks are recorded for rank movement on the current leaderboard.\n\
+            rank_tracking <mode>:\t\tRecord previous ranks on 'reset', on 'save', every <minutes>, or turn it 'off'.\n\
+            index:\t\t\t\tGet the structure entries on the current leaderboard are stored in.\n\
+            index <kind>:\t\t\tStore entries on the current leaderboard in an 'avl' tree or a 'skip_list'.\n\
             \n\
             save:\t\t\t\tSaves all boards to file.\n\
+            reload:\t\t\t\tApplies changes made to config.json and boards.json since they were last read.\n\
+            backups <board_name>:\t\tLists the backups kept of a board, newest first.\n\
+            restore <board_name> <backup>:\tReplaces every entry of a board with those in one of its backups.\n\
+            quarantine:\t\t\tLists the saves that couldn't be read and were moved to saves/quarantine.\n\
+            retry <board_name> [id]:\tLoads a quarantined save back into its board, the newest if no id is given.\n\
             Ctrl+C:\t\t\t\tSave all boards, stop the program, and shut down the server."
             );
         }
@@ -1567,6 +2472,14 @@ pub fn exec_cli(cmd_arc: Arc<AppState>, cmd_saves_path: PathBuf) {
             );
             break;
         }
+        // without a terminal stdin ends at once, and reading on would spin
+        if let Ok(0) = res {
+            let _ = writeln!(
+                &mut stdout.lock(),
+                "CLI input closed, the server keeps running. Boards can be managed through /admin."
+            );
+            break;
+        }
 
         let params: Vec<&str> = s.trim().split(" ").collect();
 
@@ -0,0 +1,31 @@
+// The board engine and its save format build on their own. The HTTP server, the CLI and the
+// loading progress bars are behind the rocket, cli and indicatif features.
+
+#[cfg(feature = "rocket")]
+pub mod admin;
+#[cfg(feature = "rocket")]
+pub mod app_state;
+#[cfg(feature = "rocket")]
+pub mod backend;
+pub mod board;
+#[cfg(feature = "cli")]
+pub mod cli;
+#[cfg(feature = "rocket")]
+pub mod options;
+pub mod persist;
+#[cfg(feature = "rocket")]
+pub mod scope;
+#[cfg(feature = "rocket")]
+pub mod stream;
+#[cfg(feature = "rocket")]
+pub mod transfer;
+#[cfg(feature = "rocket")]
+pub mod util;
+#[cfg(feature = "rocket")]
+pub mod webhook;
+
+pub use board::{Board, Entry, Tree};
+pub use persist::{load_board, load_entries, save_board, save_entries};
+
+pub type Key = i64;
+pub type Val = f64;
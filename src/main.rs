@@ -1,97 +1,230 @@
 extern crate fs2;
 
-use crate::app_state::AppState;
-use crate::backend::*;
 use fs2::FileExt;
+use leaderboard::admin::{self, Admin};
+use leaderboard::app_state::{self, AppState};
+use leaderboard::backend::{self, *};
+use leaderboard::options::{Options, USAGE};
+use leaderboard::scope::Scope;
+use leaderboard::transfer::{ConflictPolicy, TransferFormat};
+use leaderboard::{Key, stream};
+use rocket::data::{Data, Limits, ToByteUnit};
+use rocket::http::ContentType;
 use rocket::tokio::fs;
-use rocket::{
-    State,
-    fairing::AdHoc,
-    http::Status,
-    request::{FromRequest, Outcome, Request},
-    tokio,
-};
+use rocket::{Shutdown, fairing::AdHoc, http::Status, response::stream::EventStream, tokio};
 use serde::{Deserialize, Serialize};
 use std::io::{Write, stdout};
 use std::{fs::OpenOptions, sync::Arc};
 
-pub mod app_state;
-pub mod backend;
-pub mod board;
-mod cli;
-pub mod util;
-
 #[macro_use]
 extern crate rocket;
 
-pub type Key = i64;
-pub type Val = f64;
-
 #[derive(Debug)]
 pub enum RequestError {
     InvalidResult,
 }
 
 #[post("/update", format = "json", data = "<data>")]
-fn update(interaction: Interaction, data: String) -> Result<String, Status> {
+fn update(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_update(&interaction, data)
 }
 
 #[post("/remove", format = "json", data = "<data>")]
-fn remove(interaction: Interaction, data: String) -> Result<String, Status> {
+fn remove(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_remove(&interaction, data)
 }
 
 #[post("/get", format = "json", data = "<data>")]
-fn get(interaction: Interaction, data: String) -> Result<String, Status> {
+fn get(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_get(&interaction, data)
 }
 
 #[post("/info", format = "json", data = "<data>")]
-fn info(interaction: Interaction, data: String) -> Result<String, Status> {
+fn info(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_info(&interaction, data)
 }
 
 #[post("/board", format = "json", data = "<data>")]
-fn board_info(interaction: Interaction, data: String) -> Result<String, Status> {
+fn board_info(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_board(&interaction, data)
 }
 
 #[post("/atrank", format = "json", data = "<data>")]
-fn at_rank(interaction: Interaction, data: String) -> Result<String, Status> {
+fn at_rank(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_at_rank(&interaction, data)
 }
 
 #[post("/top", format = "json", data = "<data>")]
-fn top(interaction: Interaction, data: String) -> Result<String, Status> {
+fn top(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_top(&interaction, data)
 }
 
 #[post("/bottom", format = "json", data = "<data>")]
-fn bottom(interaction: Interaction, data: String) -> Result<String, Status> {
+fn bottom(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_bottom(&interaction, data)
 }
 
 #[post("/after", format = "json", data = "<data>")]
-fn after(interaction: Interaction, data: String) -> Result<String, Status> {
+fn after(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_after(&interaction, data)
 }
 
 #[post("/before", format = "json", data = "<data>")]
-fn before(interaction: Interaction, data: String) -> Result<String, Status> {
+fn before(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_before(&interaction, data)
 }
 
 #[post("/around", format = "json", data = "<data>")]
-fn around(interaction: Interaction, data: String) -> Result<String, Status> {
+fn around(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_around(&interaction, data)
 }
 
 #[post("/range", format = "json", data = "<data>")]
-fn range(interaction: Interaction, data: String) -> Result<String, Status> {
+fn range(interaction: Interaction, data: String) -> Result<String, ApiError> {
     execute_range(&interaction, data)
 }
 
+#[post("/clear", format = "json", data = "<data>")]
+fn clear(interaction: Interaction, data: String) -> Result<String, ApiError> {
+    execute_clear(&interaction, data)
+}
+
+#[get("/stream/top?<count>")]
+fn stream_top(
+    interaction: Interaction,
+    count: usize,
+    shutdown: Shutdown,
+) -> Result<EventStream![], ApiError> {
+    require_scope(&interaction, Scope::Read)?;
+    Ok(stream::stream_top(
+        interaction.state.inner().clone(),
+        interaction.user,
+        count,
+        shutdown,
+    ))
+}
+
+#[get("/stream/rank?<id>")]
+fn stream_rank(
+    interaction: Interaction,
+    id: Key,
+    shutdown: Shutdown,
+) -> Result<EventStream![], ApiError> {
+    require_scope(&interaction, Scope::ReadOwn)?;
+    require_player(&interaction, id)?;
+    Ok(stream::stream_rank(
+        interaction.state.inner().clone(),
+        interaction.user,
+        id,
+        shutdown,
+    ))
+}
+
+#[get("/export?<board>&<format>")]
+fn export(admin: Admin, board: &str, format: &str) -> Result<(ContentType, Vec<u8>), Status> {
+    let format = TransferFormat::parse(format).ok_or(Status::BadRequest)?;
+    let body = admin::execute_export(&admin, board, format)?;
+    Ok((
+        ContentType::parse_flexible(format.content_type()).unwrap(),
+        body,
+    ))
+}
+
+// The body is read whole before any of it is applied, up to the "import" limit.
+#[post("/import?<board>&<format>&<policy>", data = "<data>")]
+async fn import(
+    admin: Admin<'_>,
+    board: &str,
+    format: &str,
+    policy: &str,
+    limits: &Limits,
+    data: Data<'_>,
+) -> Result<String, Status> {
+    let format = TransferFormat::parse(format).ok_or(Status::BadRequest)?;
+    let policy = ConflictPolicy::parse(policy).ok_or(Status::BadRequest)?;
+
+    let limit = limits.get("import").unwrap_or(1.gibibytes());
+    let body = match data.open(limit).into_bytes().await {
+        Ok(v) => v,
+        Err(_) => return Err(Status::BadRequest),
+    };
+    if !body.is_complete() {
+        return Err(Status::PayloadTooLarge);
+    }
+
+    tokio::task::block_in_place(|| admin::execute_import(&admin, board, &body, format, policy))
+}
+
+#[post("/boards", format = "json", data = "<data>")]
+fn admin_boards(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_boards(&admin, data)
+}
+
+#[post("/new_board", format = "json", data = "<data>")]
+fn admin_new_board(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_new_board(&admin, data)
+}
+
+#[post("/del_board", format = "json", data = "<data>")]
+fn admin_del_board(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_del_board(&admin, data)
+}
+
+#[post("/keys", format = "json", data = "<data>")]
+fn admin_keys(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_keys(&admin, data)
+}
+
+#[post("/new_key", format = "json", data = "<data>")]
+fn admin_new_key(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_new_key(&admin, data)
+}
+
+#[post("/del_key", format = "json", data = "<data>")]
+fn admin_del_key(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_del_key(&admin, data)
+}
+
+#[post("/set_write", format = "json", data = "<data>")]
+fn admin_set_write(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_set_write(&admin, data)
+}
+
+#[post("/set_player", format = "json", data = "<data>")]
+fn admin_set_player(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_set_player(&admin, data)
+}
+
+#[post("/grant", format = "json", data = "<data>")]
+fn admin_grant(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_grant(&admin, data)
+}
+
+#[post("/cap", format = "json", data = "<data>")]
+fn admin_cap(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_cap(&admin, data)
+}
+
+#[post("/trim", format = "json", data = "<data>")]
+fn admin_trim(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_trim(&admin, data)
+}
+
+#[post("/clear", format = "json", data = "<data>")]
+fn admin_clear(admin: Admin, data: String) -> Result<String, Status> {
+    admin::execute_clear(&admin, data)
+}
+
+#[post("/save", format = "json", data = "<data>")]
+async fn admin_save(admin: Admin<'_>, data: String) -> Result<String, Status> {
+    tokio::task::block_in_place(|| admin::execute_save(&admin, data))
+}
+
+#[post("/reload", format = "json", data = "<data>")]
+async fn admin_reload(admin: Admin<'_>, data: String) -> Result<String, Status> {
+    tokio::task::block_in_place(|| admin::execute_reload(&admin, data))
+}
+
 #[derive(Serialize, Deserialize)]
 struct BatchRequest {
     req_type: backend::ActionType,
@@ -99,10 +232,10 @@ struct BatchRequest {
 }
 
 #[post("/batch", format = "json", data = "<data>")]
-fn batch(interaction: Interaction, data: String) -> Result<String, Status> {
+fn batch(interaction: Interaction, data: String) -> Result<String, ApiError> {
     let json_res = serde_json::from_str::<Vec<BatchRequest>>(data.as_str());
     if json_res.is_err() {
-        return Err(Status::BadRequest);
+        return Err(Status::BadRequest.into());
     }
 
     let json = json_res.unwrap();
@@ -116,57 +249,48 @@ fn batch(interaction: Interaction, data: String) -> Result<String, Status> {
     return Ok(serde_json::to_string(&result).unwrap());
 }
 
-#[derive(Debug)]
-pub enum ApiKeyError {
-    Missing,
-    Invalid,
-}
-
-#[rocket::async_trait]
-impl<'r> FromRequest<'r> for Interaction<'r> {
-    type Error = ApiKeyError;
-
-    async fn from_request(req: &'r Request<'_>) -> Outcome<Self, Self::Error> {
-        let state = req.guard::<&State<Arc<AppState>>>().await;
-        if let Some(state) = state.succeeded() {
-            let keys = state.api_keys.lock().unwrap();
-
-            return match req.headers().get_one("x-api-key") {
-                None => Outcome::Error((Status::BadRequest, ApiKeyError::Missing)),
-                Some(key) if keys.contains_key(key) => Outcome::Success(Interaction {
-                    user: keys.get(key).unwrap().clone(),
-                    state: state,
-                }),
-                Some(_) => Outcome::Error((Status::Unauthorized, ApiKeyError::Invalid)),
-            };
-        }
-
-        return Outcome::Error((Status::InternalServerError, ApiKeyError::Invalid));
-    }
-}
-
 #[rocket::main]
 async fn main() -> Result<(), rocket::Error> {
+    let options = match Options::parse(std::env::args().skip(1), |var| std::env::var(var).ok()) {
+        Ok(Some(v)) => v,
+        Ok(None) => {
+            let _ = writeln!(stdout().lock(), "{USAGE}");
+            return Ok(());
+        }
+        Err(err) => {
+            let _ = writeln!(std::io::stderr().lock(), "{err}");
+            std::process::exit(2);
+        }
+    };
+
     let executable_path = std::env::current_exe().unwrap();
     let main_path = executable_path.parent().unwrap().to_path_buf();
-    let saves_path = main_path.join("saves").to_path_buf();
-    let cmd_saves_path = saves_path.clone();
-    let shutdown_saves_path = saves_path.clone();
+    let config_path = options.config_path(&main_path);
     let file: std::fs::File = OpenOptions::new()
         .read(true)
         .write(true)
         .create(true)
-        .open(main_path.join("config.json"))
+        .open(&config_path)
         .expect("Failed to open config file.");
+    let config = app_state::read_config(&file);
+
+    drop(file);
+
+    let saves_path = options.saves_path(&config, &config_path, &main_path);
+    let cmd_saves_path = saves_path.clone();
+    let shutdown_saves_path = saves_path.clone();
+    let watch_saves_path = saves_path.clone();
+    let address = options.address(&config);
+    let boards_path = options.boards_path(&config, &config_path, &main_path);
     let boards_file: std::fs::File = OpenOptions::new()
         .read(true)
         .write(true)
         .create(true)
-        .open(main_path.join("boards.json"))
+        .open(&boards_path)
         .expect("Failed to open boards file.");
 
     if !saves_path.exists() {
-        fs::create_dir(&saves_path)
+        fs::create_dir_all(&saves_path)
             .await
             .expect("Could not create saves folder.");
     }
@@ -175,27 +299,40 @@ async fn main() -> Result<(), rocket::Error> {
         .try_lock_exclusive()
         .expect("Boards file in use by another program, could not lock.");
 
-    let state = AppState::new(&file, boards_file, &saves_path);
-
-    drop(file);
+    let state = AppState::new(config, &config_path, boards_file, &boards_path, &saves_path);
 
     let port_arc = Arc::new(state);
     let state_arc = port_arc.clone();
     let loop_arc = port_arc.clone();
+    let checkpoint_arc = port_arc.clone();
+    let watch_arc = port_arc.clone();
     let cmd_arc = port_arc.clone();
     let shutdown_arc = port_arc.clone();
     let port = port_arc.port;
 
-    let r = rocket::build()
-        .configure(rocket::Config::figment().merge(("port", port)).merge(("address", "0.0.0.0")))
+    let build = rocket::build()
+        .configure(rocket::Config::figment().merge(("port", port)).merge(("address", address)))
         .manage(state_arc)
         .mount(
             "/",
             routes![
                 update, remove, get, info, board_info, at_rank, top, bottom, after, before, around,
-                range, batch
+                range, clear, batch, stream_top, stream_rank
             ],
         )
+        .mount(
+            "/admin",
+            routes![
+                export, import, admin_boards, admin_new_board, admin_del_board, admin_keys,
+                admin_new_key, admin_del_key, admin_set_write, admin_set_player, admin_grant,
+                admin_cap, admin_trim, admin_clear, admin_save, admin_reload
+            ],
+        )
+        .attach(AdHoc::on_request("Board Paths", |req, _| {
+            Box::pin(async move {
+                backend::route_board_path(req);
+            })
+        }))
         .attach(AdHoc::on_liftoff("Save Loop", |_r| {
             Box::pin(async move {
                 tokio::spawn(async move {
@@ -203,15 +340,33 @@ async fn main() -> Result<(), rocket::Error> {
                 });
             })
         }))
-        .attach(AdHoc::on_liftoff("CLI", |_r| {
+        .attach(AdHoc::on_liftoff("Checkpoint Loop", |_r| {
             Box::pin(async move {
                 tokio::spawn(async move {
-                    cli::exec_cli(cmd_arc, cmd_saves_path);
+                    backend::checkpoint_loop(checkpoint_arc).await;
                 });
             })
         }))
-        .ignite()
-        .await?;
+        .attach(AdHoc::on_liftoff("Watch Loop", |_r| {
+            Box::pin(async move {
+                tokio::spawn(async move {
+                    backend::watch_loop(watch_arc, &watch_saves_path).await;
+                });
+            })
+        }));
+
+    #[cfg(feature = "cli")]
+    let build = build.attach(AdHoc::on_liftoff("CLI", |_r| {
+        Box::pin(async move {
+            tokio::spawn(async move {
+                leaderboard::cli::exec_cli(cmd_arc, cmd_saves_path);
+            });
+        })
+    }));
+    #[cfg(not(feature = "cli"))]
+    let _ = (cmd_arc, cmd_saves_path);
+
+    let r = build.ignite().await?;
 
     r.launch().await?;
 
@@ -0,0 +1,129 @@
+use std::path::{Path, PathBuf};
+
+use crate::app_state::Config;
+
+// Where the server keeps its files and the address it binds to. Each is taken from the command
+// line, then the environment, then config.json. Files default to sitting beside the executable.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Options {
+    pub config: Option<PathBuf>,
+    pub boards: Option<PathBuf>,
+    pub saves: Option<PathBuf>,
+    pub address: Option<String>,
+}
+
+pub const USAGE: &str = "Usage: Leaderboard [options]\n\
+    \n\
+    --config <file>\t\tThe config file, also LEADERBOARD_CONFIG.\n\
+    --boards <file>\t\tThe boards file, also LEADERBOARD_BOARDS or \"boards\" in the config.\n\
+    --saves <folder>\tThe saves folder, also LEADERBOARD_SAVES or \"saves\" in the config.\n\
+    --address <ip>\t\tThe address to bind to, also LEADERBOARD_ADDRESS or \"address\" in the config.\n\
+    --help\t\t\tThis.";
+
+// The variable each option is read from when it isn't given as an argument.
+const ENV: [(&str, &str); 4] = [
+    ("--config", "LEADERBOARD_CONFIG"),
+    ("--boards", "LEADERBOARD_BOARDS"),
+    ("--saves", "LEADERBOARD_SAVES"),
+    ("--address", "LEADERBOARD_ADDRESS"),
+];
+
+impl Options {
+    // None if the usage was asked for. Values can follow their flag or be joined to it by '='.
+    pub fn parse(
+        args: impl IntoIterator<Item = String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Option<Self>, String> {
+        let mut options = Options::default();
+        for (flag, var) in ENV {
+            if let Some(value) = env(var).filter(|value| !value.is_empty()) {
+                options.set(flag, value)?;
+            }
+        }
+
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            if arg == "--help" || arg == "-h" {
+                return Ok(None);
+            }
+
+            let (flag, value) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg, None),
+            };
+            // a flag followed by another flag was given no value, --saves=--x still sets one
+            let value = match value.or_else(|| args.next_if(|v| !v.starts_with("--"))) {
+                Some(v) => v,
+                None => return Err(format!("{flag} needs a value.\n\n{USAGE}")),
+            };
+            options.set(&flag, value)?;
+        }
+
+        Ok(Some(options))
+    }
+
+    fn set(&mut self, flag: &str, value: String) -> Result<(), String> {
+        match flag {
+            "--config" => self.config = Some(PathBuf::from(value)),
+            "--boards" => self.boards = Some(PathBuf::from(value)),
+            "--saves" => self.saves = Some(PathBuf::from(value)),
+            "--address" => self.address = Some(value),
+            _ => return Err(format!("Unknown option '{flag}'.\n\n{USAGE}")),
+        }
+        Ok(())
+    }
+
+    pub fn config_path(&self, default_dir: &Path) -> PathBuf {
+        match &self.config {
+            Some(path) => path.clone(),
+            None => default_dir.join("config.json"),
+        }
+    }
+
+    pub fn boards_path(&self, config: &Config, config_path: &Path, default_dir: &Path) -> PathBuf {
+        choose(
+            &self.boards,
+            &config.boards,
+            config_path,
+            default_dir.join("boards.json"),
+        )
+    }
+
+    pub fn saves_path(&self, config: &Config, config_path: &Path, default_dir: &Path) -> PathBuf {
+        choose(
+            &self.saves,
+            &config.saves,
+            config_path,
+            default_dir.join("saves"),
+        )
+    }
+
+    pub fn address(&self, config: &Config) -> String {
+        match self.address.as_ref().or(config.address.as_ref()) {
+            Some(address) => address.clone(),
+            None => "0.0.0.0".to_string(),
+        }
+    }
+}
+
+// Paths in config.json are relative to the folder it is in.
+fn choose(
+    given: &Option<PathBuf>,
+    configured: &Option<PathBuf>,
+    config_path: &Path,
+    default: PathBuf,
+) -> PathBuf {
+    if let Some(path) = given {
+        return path.clone();
+    }
+    match configured {
+        Some(path) => config_path
+            .parent()
+            .map(|dir| dir.join(path))
+            .unwrap_or(path.clone()),
+        None => default,
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use super::*;
+
+fn parse(args: &[&str], env: &[(&str, &str)]) -> Result<Option<Options>, String> {
+    let env: HashMap<String, String> = env
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    Options::parse(args.iter().map(|arg| arg.to_string()), |var| {
+        env.get(var).cloned()
+    })
+}
+
+fn config(json: &str) -> Config {
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(parse(&[], &[]), Ok(Some(Options::default())));
+    assert_eq!(parse(&["--help"], &[]), Ok(None));
+
+    let options = parse(
+        &[
+            "--config",
+            "/etc/lb/config.json",
+            "--saves=/var/lb",
+            "--address",
+            "::1",
+        ],
+        &[],
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(options.config, Some(PathBuf::from("/etc/lb/config.json")));
+    assert_eq!(options.saves, Some(PathBuf::from("/var/lb")));
+    assert_eq!(options.address, Some("::1".to_string()));
+    assert_eq!(options.boards, None);
+
+    // arguments win over the environment, empty variables are ignored
+    let options = parse(
+        &["--boards", "args.json"],
+        &[
+            ("LEADERBOARD_BOARDS", "env.json"),
+            ("LEADERBOARD_ADDRESS", "127.0.0.1"),
+            ("LEADERBOARD_SAVES", ""),
+        ],
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(options.boards, Some(PathBuf::from("args.json")));
+    assert_eq!(options.address, Some("127.0.0.1".to_string()));
+    assert_eq!(options.saves, None);
+
+    assert!(
+        parse(&["--saves"], &[])
+            .unwrap_err()
+            .contains("needs a value")
+    );
+    // the next flag isn't taken as the value
+    assert!(
+        parse(&["--saves", "--config", "c.json"], &[])
+            .unwrap_err()
+            .starts_with("--saves needs a value")
+    );
+    let options = parse(&["--saves=--x"], &[]).unwrap().unwrap();
+    assert_eq!(options.saves, Some(PathBuf::from("--x")));
+    assert!(
+        parse(&["--port", "1"], &[])
+            .unwrap_err()
+            .contains("Unknown option")
+    );
+}
+
+#[test]
+fn test_resolve() {
+    let exe_dir = Path::new("/opt/lb");
+    let bare = config(r#"{"port": 1, "save_interval": 1, "lock_save": null, "cache_len": 1}"#);
+    let set = config(
+        r#"{"port": 1, "save_interval": 1, "lock_save": null, "cache_len": 1,
+            "address": "10.0.0.1", "boards": "boards/main.json", "saves": "/data/saves"}"#,
+    );
+
+    // defaults sit beside the executable
+    let options = Options::default();
+    let config_path = options.config_path(exe_dir);
+    assert_eq!(config_path, PathBuf::from("/opt/lb/config.json"));
+    assert_eq!(
+        options.boards_path(&bare, &config_path, exe_dir),
+        PathBuf::from("/opt/lb/boards.json")
+    );
+    assert_eq!(
+        options.saves_path(&bare, &config_path, exe_dir),
+        PathBuf::from("/opt/lb/saves")
+    );
+    assert_eq!(options.address(&bare), "0.0.0.0");
+
+    // config.json paths are relative to it
+    let options = parse(&["--config", "/etc/lb/config.json"], &[])
+        .unwrap()
+        .unwrap();
+    let config_path = options.config_path(exe_dir);
+    assert_eq!(
+        options.boards_path(&set, &config_path, exe_dir),
+        PathBuf::from("/etc/lb/boards/main.json")
+    );
+    assert_eq!(
+        options.saves_path(&set, &config_path, exe_dir),
+        PathBuf::from("/data/saves")
+    );
+    assert_eq!(options.address(&set), "10.0.0.1");
+
+    // and given options win over it
+    let options = parse(
+        &["--saves", "here"],
+        &[("LEADERBOARD_ADDRESS", "127.0.0.1")],
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        options.saves_path(&set, &config_path, exe_dir),
+        PathBuf::from("here")
+    );
+    assert_eq!(options.address(&set), "127.0.0.1");
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::board_path;
+
+// Every full save of a board is kept as backups/{name}/{millis}.board, named for when it was
+// taken. Backups are hard links to the save where the filesystem allows, so they only take up
+// space once the save they match is replaced.
+pub fn backups_path(saves_path: &Path, name: &str) -> PathBuf {
+    saves_path.join("backups").join(name)
+}
+
+// How many backups are kept. Unset limits don't apply.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct BackupRetention {
+    // the most backups kept for each board
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub count: Option<usize>,
+    // seconds a backup is kept for, the newest is kept whatever its age
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age: Option<u64>,
+}
+
+pub struct Backup {
+    // the name it is restored by
+    pub id: String,
+    pub created: SystemTime,
+    pub size: u64,
+    pub path: PathBuf,
+}
+
+// Copies the board's current save into its backups. None if it has no save yet.
+pub fn backup_board(saves_path: &Path, name: &str) -> Result<Option<Backup>, String> {
+    let path = board_path(saves_path, name);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let dir = backups_path(saves_path, name);
+    fs::create_dir_all(&dir)
+        .map_err(|err| format!("Failed to create backup folder for {name}.\n{err}"))?;
+
+    let mut created = SystemTime::now();
+    let mut backup_path;
+    // two saves within a millisecond get the next free name
+    loop {
+        backup_path = dir.join(format!("{}.board", millis(created)));
+        if !backup_path.exists() {
+            break;
+        }
+        created += Duration::from_millis(1);
+    }
+
+    if fs::hard_link(&path, &backup_path).is_err() {
+        fs::copy(&path, &backup_path)
+            .map_err(|err| format!("Failed to back up {name}.board.\n{err}"))?;
+    }
+
+    Ok(Some(Backup {
+        id: millis(created).to_string(),
+        created: created,
+        size: fs::metadata(&backup_path)
+            .map(|meta| meta.len())
+            .unwrap_or(0),
+        path: backup_path,
+    }))
+}
+
+// The board's backups, newest first.
+pub fn list_backups(saves_path: &Path, name: &str) -> Result<Vec<Backup>, String> {
+    let dir = backups_path(saves_path, name);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let read_dir =
+        fs::read_dir(&dir).map_err(|err| format!("Failed to list backups of {name}.\n{err}"))?;
+
+    let mut backups = Vec::new();
+    for file in read_dir.flatten() {
+        let path = file.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("board") {
+            continue;
+        }
+        let stamp = match path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.parse::<u64>().ok())
+        {
+            Some(v) => v,
+            None => continue,
+        };
+
+        backups.push(Backup {
+            id: stamp.to_string(),
+            created: UNIX_EPOCH + Duration::from_millis(stamp),
+            size: file.metadata().map(|meta| meta.len()).unwrap_or(0),
+            path: path,
+        });
+    }
+
+    backups.sort_unstable_by_key(|backup| Reverse(backup.created));
+    Ok(backups)
+}
+
+pub fn find_backup(saves_path: &Path, name: &str, id: &str) -> Result<Option<Backup>, String> {
+    Ok(list_backups(saves_path, name)?
+        .into_iter()
+        .find(|backup| backup.id == id))
+}
+
+// Removes the backups past the retention limits and returns how many there were.
+pub fn prune_backups(
+    saves_path: &Path,
+    name: &str,
+    retention: BackupRetention,
+) -> Result<usize, String> {
+    let backups = list_backups(saves_path, name)?;
+    let now = SystemTime::now();
+
+    let mut removed = 0;
+    for (i, backup) in backups.iter().enumerate() {
+        let over_count = retention.count.is_some_and(|count| i >= count);
+        let too_old = i > 0
+            && retention.max_age.is_some_and(|max_age| {
+                now.duration_since(backup.created).unwrap_or_default()
+                    > Duration::from_secs(max_age)
+            });
+
+        if over_count || too_old {
+            fs::remove_file(&backup.path).map_err(|err| {
+                format!("Failed to remove backup {} of {name}.\n{err}", backup.id)
+            })?;
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+pub fn remove_backups(saves_path: &Path, name: &str) -> Result<(), String> {
+    let dir = backups_path(saves_path, name);
+    if !dir.exists() {
+        return Ok(());
+    }
+    fs::remove_dir_all(dir).map_err(|err| format!("Failed to remove backups of {name}.\n{err}"))
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,127 @@
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+// How the entries of a .board file are compressed. The codec is recorded in the header, so a
+// file is read back the same way whatever the setting is now.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Lz4,
+    // the level, 1 (fastest) to 22 (smallest), 3 is a good default
+    Zstd(i32),
+}
+
+impl Compression {
+    pub(super) fn tag(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Lz4 => 1,
+            Compression::Zstd(_) => 2,
+        }
+    }
+
+    // The level isn't needed to decompress, so the one read back is only a placeholder.
+    pub(super) fn from_tag(tag: u8) -> Result<Self, String> {
+        match tag {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Lz4),
+            2 => Ok(Compression::Zstd(0)),
+            _ => Err(format!("Unknown compression {tag}.")),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Lz4 => "lz4",
+            Compression::Zstd(_) => "zstd",
+        }
+    }
+}
+
+fn unsupported(compression: Compression) -> String {
+    format!(
+        "Built without {} support, enable the {} feature.",
+        compression.name(),
+        compression.name()
+    )
+}
+
+// Encodes value into writer through the codec.
+pub(super) fn encode_compressed<T: Encode, W: Write>(
+    value: &T,
+    compression: Compression,
+    writer: &mut W,
+) -> Result<(), String> {
+    let config = bincode::config::standard();
+    match compression {
+        Compression::None => bincode::encode_into_std_write(value, writer, config)
+            .map(|_| ())
+            .map_err(|err| err.to_string()),
+        #[cfg(feature = "lz4")]
+        Compression::Lz4 => {
+            let mut encoder = lz4_flex::frame::FrameEncoder::new(writer);
+            bincode::encode_into_std_write(value, &mut encoder, config)
+                .map_err(|err| err.to_string())?;
+            encoder.finish().map(|_| ()).map_err(|err| err.to_string())
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(level) => {
+            let mut encoder = zstd::Encoder::new(writer, level).map_err(|err| err.to_string())?;
+            bincode::encode_into_std_write(value, &mut encoder, config)
+                .map_err(|err| err.to_string())?;
+            encoder.finish().map(|_| ()).map_err(|err| err.to_string())
+        }
+        #[allow(unreachable_patterns)]
+        _ => Err(unsupported(compression)),
+    }
+}
+
+// Decodes a value from the whole of bytes, which were written by encode_compressed.
+pub(super) fn decode_compressed<T: Decode<()>>(
+    bytes: &[u8],
+    compression: Compression,
+) -> Result<T, String> {
+    match compression {
+        Compression::None => {
+            let (value, read) = bincode::decode_from_slice(bytes, bincode::config::standard())
+                .map_err(|err| err.to_string())?;
+            // leftover bytes mean the file isn't what it claims to be
+            if read != bytes.len() {
+                return Err(format!(
+                    "Entries end after {read} of {} bytes.",
+                    bytes.len()
+                ));
+            }
+            Ok(value)
+        }
+        #[cfg(feature = "lz4")]
+        Compression::Lz4 => decode_stream(std::io::BufReader::new(
+            lz4_flex::frame::FrameDecoder::new(bytes),
+        )),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(_) => {
+            let decoder = zstd::Decoder::with_buffer(bytes).map_err(|err| err.to_string())?;
+            decode_stream(std::io::BufReader::new(decoder))
+        }
+        #[allow(unreachable_patterns)]
+        _ => Err(unsupported(compression)),
+    }
+}
+
+// Decompresses as it decodes, so the whole board is never held uncompressed twice.
+#[cfg(any(feature = "lz4", feature = "zstd"))]
+fn decode_stream<T: Decode<()>, R: std::io::Read>(mut reader: R) -> Result<T, String> {
+    let value = bincode::decode_from_std_read(&mut reader, bincode::config::standard())
+        .map_err(|err| err.to_string())?;
+
+    let mut rest = [0u8; 1];
+    match reader.read(&mut rest) {
+        Ok(0) => Ok(value),
+        Ok(_) => Err("Unexpected data after the entries.".to_string()),
+        Err(err) => Err(err.to_string()),
+    }
+}
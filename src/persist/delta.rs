@@ -0,0 +1,88 @@
+use bincode::{Decode, Encode};
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::replay_records;
+use crate::board::BoardDelta;
+
+// Saves of a board that only changed a little append a delta to {name}.delta instead of
+// rewriting {name}.board. Loading applies the deltas on top of the .board in order, and a full
+// save folds them back in and removes the file.
+pub fn delta_path(saves_path: &Path, name: &str) -> PathBuf {
+    saves_path.join(format!("{name}.delta"))
+}
+
+pub fn append_delta<K, V>(
+    delta: &BoardDelta<K, V>,
+    saves_path: &Path,
+    name: &str,
+) -> Result<(), String>
+where
+    K: PartialOrd + Default + Encode,
+    V: PartialOrd + Default + Encode,
+{
+    let path = delta_path(saves_path, name);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|err| format!("Failed to open delta file to save {name}.\n{err}"))?;
+    let len = file
+        .metadata()
+        .map_err(|err| format!("Failed to open delta file to save {name}.\n{err}"))?
+        .len();
+
+    let mut writer = BufWriter::new(file);
+    let result = bincode::encode_into_std_write(delta, &mut writer, bincode::config::standard())
+        .map_err(|err| err.to_string())
+        .and_then(|_| writer.flush().map_err(|err| err.to_string()))
+        .and_then(|_| writer.get_ref().sync_data().map_err(|err| err.to_string()));
+
+    if let Err(err) = result {
+        // a partial delta would hide every delta appended after it
+        let _ = writer.get_ref().set_len(len);
+        return Err(format!("Failed to write delta to save {name}.\n{err}"));
+    }
+    // the file is new, so it is only there after a power cut once the folder is synced too
+    if len == 0 {
+        super::sync_parent(&path)?;
+    }
+
+    Ok(())
+}
+
+// Hands every saved delta to apply, oldest first, and returns how many there were.
+pub fn load_deltas<K, V>(
+    saves_path: &Path,
+    name: &str,
+    apply: impl FnMut(BoardDelta<K, V>),
+) -> Result<usize, String>
+where
+    K: PartialOrd + Default + Decode<()>,
+    V: PartialOrd + Default + Decode<()>,
+{
+    read_deltas_at(&delta_path(saves_path, name), name, apply)
+}
+
+// Like load_deltas for any delta file, such as one in quarantine.
+pub fn read_deltas_at<K, V>(
+    path: &Path,
+    name: &str,
+    apply: impl FnMut(BoardDelta<K, V>),
+) -> Result<usize, String>
+where
+    K: PartialOrd + Default + Decode<()>,
+    V: PartialOrd + Default + Decode<()>,
+{
+    replay_records(path, name, apply)
+}
+
+// Called once a full save holds everything the deltas did.
+pub fn remove_deltas(saves_path: &Path, name: &str) -> Result<(), String> {
+    let path = delta_path(saves_path, name);
+    if !path.exists() {
+        return Ok(());
+    }
+    fs::remove_file(path).map_err(|err| format!("Failed to remove deltas of {name}.\n{err}"))
+}
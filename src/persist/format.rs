@@ -0,0 +1,323 @@
+use bincode::{Decode, Encode};
+use std::hash::Hash;
+use std::io::{self, Seek, SeekFrom, Write};
+
+use super::compression::{Compression, decode_compressed, encode_compressed};
+use crate::board::SavedEntries;
+
+// A .board file starts with a fixed size header followed by the bincode of the entries:
+//
+//   magic    4 bytes  "LBRD"
+//   version  u16
+//   key tag  u8
+//   val tag  u8
+//   codec    u8       how the payload is compressed, since version 2
+//   count    u64      entries in the payload
+//   length   u64      bytes in the payload as stored
+//   checksum u32      CRC-32 of the payload as stored
+//
+// Numbers are little endian. Files written before the header existed are plain bincode of the
+// entries, which is read as version 0. Version 1 had no codec and was never compressed.
+pub const MAGIC: [u8; 4] = *b"LBRD";
+pub const FORMAT_VERSION: u16 = 2;
+
+fn header_len(version: u16) -> usize {
+    match version {
+        1 => 28,
+        _ => 29,
+    }
+}
+
+// Identifies the key and value types a file was written with, so a board is never read as the
+// wrong type. Tags must never be reused for another type.
+pub trait TypeTag {
+    const TAG: u8;
+    const NAME: &'static str;
+}
+
+macro_rules! type_tags {
+    ($($ty:ty => $tag:expr),* $(,)?) => {
+        $(
+            impl TypeTag for $ty {
+                const TAG: u8 = $tag;
+                const NAME: &'static str = stringify!($ty);
+            }
+        )*
+    };
+}
+
+type_tags! {
+    i8 => 1, i16 => 2, i32 => 3, i64 => 4,
+    u8 => 5, u16 => 6, u32 => 7, u64 => 8,
+    f32 => 9, f64 => 10,
+}
+
+fn type_name(tag: u8) -> String {
+    let name = match tag {
+        1 => i8::NAME,
+        2 => i16::NAME,
+        3 => i32::NAME,
+        4 => i64::NAME,
+        5 => u8::NAME,
+        6 => u16::NAME,
+        7 => u32::NAME,
+        8 => u64::NAME,
+        9 => f32::NAME,
+        10 => f64::NAME,
+        _ => return format!("unknown type {tag}"),
+    };
+    name.to_string()
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Header {
+    pub version: u16,
+    pub key_tag: u8,
+    pub val_tag: u8,
+    pub compression: Compression,
+    pub count: u64,
+    pub length: u64,
+    pub checksum: u32,
+}
+
+impl Header {
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(header_len(FORMAT_VERSION));
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        bytes.push(self.key_tag);
+        bytes.push(self.val_tag);
+        bytes.push(self.compression.tag());
+        bytes.extend_from_slice(&self.count.to_le_bytes());
+        bytes.extend_from_slice(&self.length.to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+        bytes
+    }
+
+    // None if the bytes don't start with the magic, so the file predates the header. Headers of
+    // older versions are read into the current layout.
+    fn from_bytes(bytes: &[u8]) -> Option<Result<Self, String>> {
+        if bytes.len() < MAGIC.len() || bytes[0..4] != MAGIC {
+            return None;
+        }
+        if bytes.len() < 6 {
+            return Some(Err("Header is cut short before the version.".to_string()));
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version == 0 || version > FORMAT_VERSION {
+            return Some(Err(format!(
+                "Written in format version {version}, this build reads up to version {FORMAT_VERSION}."
+            )));
+        }
+        let len = header_len(version);
+        if bytes.len() < len {
+            return Some(Err(format!(
+                "Header is cut short, {} of {len} bytes.",
+                bytes.len()
+            )));
+        }
+
+        let (compression, rest) = match version {
+            1 => (Ok(Compression::None), &bytes[8..len]),
+            _ => (Compression::from_tag(bytes[8]), &bytes[9..len]),
+        };
+        let compression = match compression {
+            Ok(compression) => compression,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let u64_at = |at: usize| u64::from_le_bytes(rest[at..at + 8].try_into().unwrap());
+        Some(Ok(Self {
+            version: version,
+            key_tag: bytes[6],
+            val_tag: bytes[7],
+            compression: compression,
+            count: u64_at(0),
+            length: u64_at(8),
+            checksum: u32::from_le_bytes(rest[16..20].try_into().unwrap()),
+        }))
+    }
+}
+
+// Writes the header and entries. value must encode as count saved entries.
+pub fn write_board_file<K, V, T, W>(
+    value: &T,
+    count: usize,
+    compression: Compression,
+    writer: &mut W,
+) -> Result<(), String>
+where
+    K: TypeTag,
+    V: TypeTag,
+    T: Encode,
+    W: Write + Seek,
+{
+    let len = header_len(FORMAT_VERSION);
+    // the header is filled in once the payload's length and checksum are known
+    let start = writer
+        .stream_position()
+        .and_then(|start| writer.write_all(&vec![0u8; len]).map(|_| start))
+        .map_err(|err| err.to_string())?;
+
+    let mut payload = ChecksumWriter {
+        inner: &mut *writer,
+        crc: CRC_INIT,
+        length: 0,
+    };
+    encode_compressed(value, compression, &mut payload)?;
+
+    let header = Header {
+        version: FORMAT_VERSION,
+        key_tag: K::TAG,
+        val_tag: V::TAG,
+        compression: compression,
+        count: count as u64,
+        length: payload.length,
+        checksum: !payload.crc,
+    };
+    writer
+        .seek(SeekFrom::Start(start))
+        .and_then(|_| writer.write_all(&header.to_bytes()))
+        .and_then(|_| writer.seek(SeekFrom::End(0)))
+        .and_then(|_| writer.flush())
+        .map_err(|err| err.to_string())
+}
+
+// Checks the whole file and decodes its entries, along with the header they were read with. The
+// header of a file from before there was one is made up, with version 0.
+pub fn read_board_file<K, V>(bytes: &[u8]) -> Result<(SavedEntries<K, V>, Header), String>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone + Decode<()> + TypeTag,
+    V: PartialOrd + Default + Clone + Decode<()> + TypeTag,
+{
+    let header = match Header::from_bytes(bytes) {
+        Some(header) => header?,
+        None => {
+            let saved: SavedEntries<K, V> = decode_compressed(bytes, Compression::None)?;
+            let header = Header {
+                version: 0,
+                key_tag: K::TAG,
+                val_tag: V::TAG,
+                compression: Compression::None,
+                count: saved.0.len() as u64,
+                length: bytes.len() as u64,
+                checksum: 0,
+            };
+            return Ok((saved, header));
+        }
+    };
+
+    if header.key_tag != K::TAG || header.val_tag != V::TAG {
+        return Err(format!(
+            "Holds {} keys and {} values, expected {} keys and {} values.",
+            type_name(header.key_tag),
+            type_name(header.val_tag),
+            K::NAME,
+            V::NAME
+        ));
+    }
+
+    let payload = checked_payload(&header, bytes)?;
+    let saved: SavedEntries<K, V> = decode_compressed(payload, header.compression)?;
+    if saved.0.len() as u64 != header.count {
+        return Err(format!(
+            "Header lists {} entries but the payload holds {}.",
+            header.count,
+            saved.0.len()
+        ));
+    }
+
+    Ok((saved, header))
+}
+
+// Checks the header, length and checksum of a file without decoding it. Files from before the
+// header existed have nothing to check them by, so they fail.
+pub fn check_board_file(bytes: &[u8]) -> Result<Header, String> {
+    let header = match Header::from_bytes(bytes) {
+        Some(header) => header?,
+        None => return Err("Has no header to check it by.".to_string()),
+    };
+    checked_payload(&header, bytes)?;
+    Ok(header)
+}
+
+// The payload after the header, once it is the length the header says with the same checksum.
+fn checked_payload<'a>(header: &Header, bytes: &'a [u8]) -> Result<&'a [u8], String> {
+    let payload = &bytes[header_len(header.version)..];
+    if (payload.len() as u64) < header.length {
+        return Err(format!(
+            "File is truncated, {} of {} payload bytes.",
+            payload.len(),
+            header.length
+        ));
+    }
+    if payload.len() as u64 > header.length {
+        return Err(format!(
+            "File has {} unexpected bytes after the payload.",
+            payload.len() as u64 - header.length
+        ));
+    }
+
+    let checksum = crc32(payload);
+    if checksum != header.checksum {
+        return Err(format!(
+            "Checksum mismatch, expected {:08x} but the payload has {checksum:08x}.",
+            header.checksum
+        ));
+    }
+
+    Ok(payload)
+}
+
+const CRC_INIT: u32 = 0xffff_ffff;
+
+// CRC-32 (IEEE), the same as zlib and PNG use.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+fn crc32_update(mut crc: u32, bytes: &[u8]) -> u32 {
+    for byte in bytes {
+        crc = CRC_TABLE[((crc ^ *byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc
+}
+
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !crc32_update(CRC_INIT, bytes)
+}
+
+struct ChecksumWriter<'a, W: Write> {
+    inner: &'a mut W,
+    crc: u32,
+    length: u64,
+}
+
+impl<'a, W: Write> Write for ChecksumWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.crc = crc32_update(self.crc, &buf[..written]);
+        self.length += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
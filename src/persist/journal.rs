@@ -0,0 +1,279 @@
+use bincode::de::Decoder;
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hash;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::replay_records;
+use crate::board::{Board, Entry, RankedIndex};
+
+// Every change to a board between saves is appended to {name}.journal, so a crash only loses
+// what the fsync policy hadn't flushed to disk yet. The journal is replayed on top of the last
+// save at startup and cut back after each save.
+pub fn journal_path(saves_path: &Path, name: &str) -> PathBuf {
+    saves_path.join(format!("{name}.journal"))
+}
+
+fn journal_part_path(saves_path: &Path, name: &str) -> PathBuf {
+    saves_path.join(format!("{name}_journal.part"))
+}
+
+// How often appended records are forced to disk. Records always reach the OS straight away,
+// so the policy only matters if the machine itself goes down.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FsyncPolicy {
+    Always,
+    // seconds between syncs
+    Interval(u64),
+    Never,
+}
+
+impl Default for FsyncPolicy {
+    fn default() -> Self {
+        FsyncPolicy::Interval(1)
+    }
+}
+
+// Records hold the whole entry so replaying keeps the original timestamps and tie order.
+#[derive(Clone, PartialEq)]
+pub enum JournalRecord<K, V>
+where
+    K: PartialOrd + Default,
+    V: PartialOrd + Default,
+{
+    Update(Entry<K, V>),
+    Remove(K),
+    Clear,
+}
+
+impl<K, V> JournalRecord<K, V>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone,
+    V: PartialOrd + Default + Clone,
+{
+    pub fn apply<I: RankedIndex<Entry<K, V>>>(self, board: &mut Board<K, V, I>) {
+        match self {
+            JournalRecord::Update(entry) => {
+                let _ = board.set_entry(entry);
+            }
+            JournalRecord::Remove(key) => {
+                let _ = board.remove_entry(&key);
+            }
+            JournalRecord::Clear => board.clear(),
+        }
+    }
+}
+
+pub struct Journal {
+    path: PathBuf,
+    part_path: PathBuf,
+    writer: BufWriter<File>,
+    policy: FsyncPolicy,
+    last_sync: Instant,
+    // whether records were written since the last sync
+    unsynced: bool,
+    // bytes written, including any not flushed yet
+    len: u64,
+}
+
+impl Journal {
+    pub fn open(saves_path: &Path, name: &str, policy: FsyncPolicy) -> Result<Self, String> {
+        let path = journal_path(saves_path, name);
+        let file = open_append(&path)?;
+        let len = file
+            .metadata()
+            .map_err(|err| format!("Failed to open journal for {name}.\n{err}"))?
+            .len();
+
+        Ok(Self {
+            path: path,
+            part_path: journal_part_path(saves_path, name),
+            writer: BufWriter::new(file),
+            policy: policy,
+            last_sync: Instant::now(),
+            unsynced: false,
+            len: len,
+        })
+    }
+
+    pub fn append<K, V>(&mut self, record: &JournalRecord<K, V>) -> Result<(), String>
+    where
+        K: PartialOrd + Default + Encode,
+        V: PartialOrd + Default + Encode,
+    {
+        self.append_all(std::slice::from_ref(record))
+    }
+
+    // Writes the records together, syncing at most once for all of them.
+    pub fn append_all<K, V>(&mut self, records: &[JournalRecord<K, V>]) -> Result<(), String>
+    where
+        K: PartialOrd + Default + Encode,
+        V: PartialOrd + Default + Encode,
+    {
+        for record in records.iter() {
+            self.len += bincode::encode_into_std_write(
+                record,
+                &mut self.writer,
+                bincode::config::standard(),
+            )
+            .map_err(|err| format!("Failed to write to journal.\n{err}"))?
+                as u64;
+        }
+
+        self.writer
+            .flush()
+            .map_err(|err| format!("Failed to write to journal.\n{err}"))?;
+        self.unsynced = true;
+
+        if self.policy == FsyncPolicy::Always || self.is_sync_due() {
+            self.sync()?;
+        }
+
+        Ok(())
+    }
+
+    // Syncs records an interval policy has held back for the whole interval. Called on a timer,
+    // so the last records before a quiet spell don't wait for the next append.
+    pub fn sync_due(&mut self) -> Result<(), String> {
+        if self.unsynced && self.is_sync_due() {
+            return self.sync();
+        }
+        Ok(())
+    }
+
+    fn is_sync_due(&self) -> bool {
+        match self.policy {
+            FsyncPolicy::Interval(secs) => self.last_sync.elapsed() >= Duration::from_secs(secs),
+            FsyncPolicy::Always | FsyncPolicy::Never => false,
+        }
+    }
+
+    pub fn sync(&mut self) -> Result<(), String> {
+        self.writer
+            .flush()
+            .and_then(|_| self.writer.get_ref().sync_data())
+            .map_err(|err| format!("Failed to sync journal.\n{err}"))?;
+        self.last_sync = Instant::now();
+        self.unsynced = false;
+        Ok(())
+    }
+
+    // Where the journal ends now. Taken while the board is locked for a save, everything
+    // before it is covered by that save.
+    pub fn mark(&self) -> u64 {
+        self.len
+    }
+
+    // Drops the records before mark once the save holding them is on disk. Records appended
+    // since the mark are moved into a fresh journal.
+    pub fn truncate_to(&mut self, mark: u64) -> Result<(), String> {
+        self.writer
+            .flush()
+            .map_err(|err| format!("Failed to write to journal.\n{err}"))?;
+
+        if mark >= self.len {
+            self.writer
+                .get_ref()
+                .set_len(0)
+                .map_err(|err| format!("Failed to truncate journal.\n{err}"))?;
+            self.len = 0;
+            return Ok(());
+        }
+
+        let mut rest = Vec::with_capacity((self.len - mark) as usize);
+        let mut file = File::open(&self.path)
+            .map_err(|err| format!("Failed to read journal to truncate it.\n{err}"))?;
+        file.seek(SeekFrom::Start(mark))
+            .and_then(|_| file.read_to_end(&mut rest))
+            .map_err(|err| format!("Failed to read journal to truncate it.\n{err}"))?;
+
+        let mut part = File::create(&self.part_path)
+            .map_err(|err| format!("Failed to open temp file to truncate journal.\n{err}"))?;
+        part.write_all(&rest)
+            .and_then(|_| part.sync_data())
+            .map_err(|err| format!("Failed to write temp file to truncate journal.\n{err}"))?;
+        fs::rename(&self.part_path, &self.path)
+            .map_err(|err| format!("Failed to rename temp file into journal.\n{err}"))?;
+        super::sync_parent(&self.path)?;
+
+        self.writer = BufWriter::new(open_append(&self.path)?);
+        self.len = rest.len() as u64;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> Result<File, String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| format!("Failed to open journal ({}).\n{err}", path.display()))
+}
+
+// Hands every journaled change to apply, oldest first, and returns how many there were.
+pub fn replay_journal<K, V>(
+    saves_path: &Path,
+    name: &str,
+    apply: impl FnMut(JournalRecord<K, V>),
+) -> Result<usize, String>
+where
+    K: PartialOrd + Default + Decode<()>,
+    V: PartialOrd + Default + Decode<()>,
+{
+    let path = journal_path(saves_path, name);
+    let _ = fs::remove_file(journal_part_path(saves_path, name));
+
+    replay_records(&path, name, apply)
+}
+
+impl<K, V> Encode for JournalRecord<K, V>
+where
+    K: PartialOrd + Default + Encode,
+    V: PartialOrd + Default + Encode,
+{
+    fn encode<E: bincode::enc::Encoder>(
+        &self,
+        encoder: &mut E,
+    ) -> Result<(), bincode::error::EncodeError> {
+        match self {
+            JournalRecord::Update(entry) => {
+                bincode::Encode::encode(&0u8, encoder)?;
+                bincode::Encode::encode(entry, encoder)?;
+            }
+            JournalRecord::Remove(key) => {
+                bincode::Encode::encode(&1u8, encoder)?;
+                bincode::Encode::encode(key, encoder)?;
+            }
+            JournalRecord::Clear => {
+                bincode::Encode::encode(&2u8, encoder)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<K, V, Context> Decode<Context> for JournalRecord<K, V>
+where
+    K: PartialOrd + Default + Decode<Context>,
+    V: PartialOrd + Default + Decode<Context>,
+{
+    fn decode<D: Decoder<Context = Context>>(
+        decoder: &mut D,
+    ) -> Result<Self, bincode::error::DecodeError> {
+        let tag: u8 = bincode::Decode::decode(decoder)?;
+        match tag {
+            0 => Ok(JournalRecord::Update(bincode::Decode::decode(decoder)?)),
+            1 => Ok(JournalRecord::Remove(bincode::Decode::decode(decoder)?)),
+            2 => Ok(JournalRecord::Clear),
+            found => Err(bincode::error::DecodeError::UnexpectedVariant {
+                type_name: "JournalRecord",
+                allowed: &bincode::error::AllowedEnumVariants::Range { min: 0, max: 2 },
+                found: found as u32,
+            }),
+        }
+    }
+}
@@ -0,0 +1,317 @@
+use bincode::{Decode, Encode};
+use std::collections::HashMap;
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+
+use crate::board::{Board, Entry, RankedIndex, SavedEntries};
+
+mod backup;
+mod compression;
+mod delta;
+mod format;
+mod journal;
+mod quarantine;
+
+pub use backup::{
+    Backup, BackupRetention, backup_board, backups_path, find_backup, list_backups,
+    prune_backups, remove_backups,
+};
+pub use compression::Compression;
+pub use delta::{append_delta, delta_path, load_deltas, read_deltas_at, remove_deltas};
+pub use format::{
+    FORMAT_VERSION, Header, TypeTag, check_board_file, crc32, read_board_file, write_board_file,
+};
+pub use journal::{FsyncPolicy, Journal, JournalRecord, journal_path, replay_journal};
+pub use quarantine::{
+    QuarantineMode, Quarantined, find_quarantined, list_quarantined, quarantine_path,
+    quarantine_save, release_quarantined,
+};
+
+// Boards are saved to {name}.board in the saves folder. Saves are written to {name}_saving.part
+// first and renamed over the old file, so a crash mid save leaves the previous save in place.
+pub fn board_path(saves_path: &Path, name: &str) -> PathBuf {
+    saves_path.join(format!("{name}.board"))
+}
+
+pub fn part_path(saves_path: &Path, name: &str) -> PathBuf {
+    saves_path.join(format!("{name}_saving.part"))
+}
+
+pub fn ranks_path(saves_path: &Path, name: &str) -> PathBuf {
+    saves_path.join(format!("{name}.ranks"))
+}
+
+pub fn save_board<K, V, I>(
+    board: &Board<K, V, I>,
+    saves_path: &Path,
+    name: &str,
+    compression: Compression,
+) -> Result<(), String>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone + Encode + TypeTag,
+    V: PartialOrd + Default + Clone + Encode + TypeTag,
+    I: RankedIndex<Entry<K, V>>,
+{
+    write_board_then_rename::<K, V, _>(board, board.get_size(), saves_path, name, compression)
+}
+
+// Same file format as save_board, for entries taken from a snapshot without holding the board.
+pub fn save_entries<K, V>(
+    entries: &SavedEntries<K, V>,
+    saves_path: &Path,
+    name: &str,
+    compression: Compression,
+) -> Result<(), String>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone + Encode + TypeTag,
+    V: PartialOrd + Default + Clone + Encode + TypeTag,
+{
+    write_board_then_rename::<K, V, _>(entries, entries.0.len(), saves_path, name, compression)
+}
+
+fn write_board_then_rename<K: TypeTag, V: TypeTag, T: Encode>(
+    value: &T,
+    count: usize,
+    saves_path: &Path,
+    name: &str,
+    compression: Compression,
+) -> Result<(), String> {
+    write_durably(
+        &part_path(saves_path, name),
+        &board_path(saves_path, name),
+        |writer| write_board_file::<K, V, _, _>(value, count, compression, writer),
+    )
+}
+
+fn write_then_rename<T: Encode>(value: &T, temp_path: &Path, path: &Path) -> Result<(), String> {
+    write_durably(temp_path, path, |writer| {
+        bincode::encode_into_std_write(value, writer, bincode::config::standard())
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    })
+}
+
+// The temp file is flushed and synced before it is renamed over path, and the folder is synced
+// after, so a power cut leaves either the old file or the whole new one.
+fn write_durably(
+    temp_path: &Path,
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), String>,
+) -> Result<(), String> {
+    let handle = File::create(temp_path)
+        .map_err(|err| format!("Failed to open temp file to save leaderboard backup.\n{err}"))?;
+
+    let mut writer = BufWriter::new(handle);
+    write(&mut writer).map_err(|err| {
+        format!("Failed to write to temp file to save leaderboard backup.\n{err}")
+    })?;
+    let handle = writer.into_inner().map_err(|err| {
+        format!(
+            "Failed to write to temp file to save leaderboard backup.\n{}",
+            err.error()
+        )
+    })?;
+    handle
+        .sync_all()
+        .map_err(|err| format!("Failed to sync temp file to save leaderboard backup.\n{err}"))?;
+    let _ = drop(handle);
+
+    std::fs::rename(temp_path, path)
+        .map_err(|err| format!("Failed to rename temp file into save.\n{err}"))?;
+    sync_parent(path)
+}
+
+// Makes a rename or a new file in path's folder survive a power cut. Folders can't be opened to
+// sync them on Windows, where a rename is already durable once it returns.
+pub fn sync_parent(path: &Path) -> Result<(), String> {
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        File::open(dir)
+            .and_then(|dir| dir.sync_all())
+            .map_err(|err| format!("Failed to sync folder ({}).\n{err}", dir.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+// A leftover .part with no .board means the rename was interrupted, so the .part is the save if
+// it is whole. One that isn't is left for the load to fail on.
+pub fn recover_part(saves_path: &Path, name: &str) -> Result<(), String> {
+    let path = board_path(saves_path, name);
+    let temp_path = part_path(saves_path, name);
+
+    if !path.exists() && temp_path.exists() {
+        std::fs::read(&temp_path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| check_board_file(&bytes))
+            .map_err(|err| {
+                format!(
+                    "Found an interrupted save of {name} ({}) that is damaged.\n{err}",
+                    temp_path.display()
+                )
+            })?;
+
+        std::fs::rename(&temp_path, &path)
+            .map_err(|err| format!("Failed to recover save file.\n{err}"))?;
+        sync_parent(&path)?;
+    }
+
+    Ok(())
+}
+
+// Reads the saved entries of a board, None if it has never been saved. The header, types and
+// checksum are all verified first, and the compression is whatever the header says. Saves from
+// older format versions are rewritten in the current one once they have been read.
+pub fn load_entries<K, V>(
+    saves_path: &Path,
+    name: &str,
+) -> Result<Option<SavedEntries<K, V>>, String>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone + Encode + Decode<()> + TypeTag,
+    V: PartialOrd + Default + Clone + Encode + Decode<()> + TypeTag,
+{
+    recover_part(saves_path, name)?;
+
+    let path = board_path(saves_path, name);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let (saved, header) = read_entries_at(&path, name)?;
+
+    // the save is good, so a .part left beside it is stale
+    let _ = std::fs::remove_file(part_path(saves_path, name));
+
+    if header.version < FORMAT_VERSION {
+        save_entries(&saved, saves_path, name, header.compression).map_err(|err| {
+            format!(
+                "Failed to migrate {name}.board from format version {}.\n{err}",
+                header.version
+            )
+        })?;
+    }
+
+    Ok(Some(saved))
+}
+
+// Reads and verifies any board file, such as a backup, without migrating it.
+pub fn read_entries_at<K, V>(
+    path: &Path,
+    name: &str,
+) -> Result<(SavedEntries<K, V>, Header), String>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone + Decode<()> + TypeTag,
+    V: PartialOrd + Default + Clone + Decode<()> + TypeTag,
+{
+    let bytes = std::fs::read(path).map_err(|err| {
+        format!(
+            "Failed to read file ({}) for leaderboard {name}\n{err}",
+            path.display()
+        )
+    })?;
+
+    read_board_file(&bytes).map_err(|err| {
+        format!(
+            "Failed to parse file ({}) for leaderboard {name}\n{err}",
+            path.display()
+        )
+    })
+}
+
+pub fn load_board<K, V, I>(
+    saves_path: &Path,
+    name: &str,
+) -> Result<Option<Board<K, V, I>>, String>
+where
+    K: PartialOrd + Eq + Hash + Sized + Default + Clone + Encode + Decode<()> + TypeTag,
+    V: PartialOrd + Default + Clone + Encode + Decode<()> + TypeTag,
+    I: RankedIndex<Entry<K, V>>,
+{
+    Ok(load_entries(saves_path, name)?.map(Board::from_saved))
+}
+
+pub fn save_previous_ranks<K>(
+    ranks: &HashMap<K, usize>,
+    saves_path: &Path,
+    name: &str,
+) -> Result<(), String>
+where
+    K: Eq + Hash + Encode,
+{
+    write_then_rename(
+        ranks,
+        &saves_path.join(format!("{name}_ranks.part")),
+        &ranks_path(saves_path, name),
+    )
+}
+
+// None if the board has no saved ranks.
+pub fn load_previous_ranks<K>(
+    saves_path: &Path,
+    name: &str,
+) -> Result<Option<HashMap<K, usize>>, String>
+where
+    K: Eq + Hash + Decode<()>,
+{
+    let path = ranks_path(saves_path, name);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    File::open(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|file| {
+            bincode::decode_from_std_read(&mut BufReader::new(file), bincode::config::standard())
+                .map_err(|err| err.to_string())
+        })
+        .map(Some)
+}
+
+// Hands every record in an append-only file to apply, oldest first, and returns how many there
+// were. A record cut short by a crash ends the file and is trimmed off so later appends follow
+// the last whole record.
+fn replay_records<T: Decode<()>>(
+    path: &Path,
+    name: &str,
+    mut apply: impl FnMut(T),
+) -> Result<usize, String> {
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let bytes = std::fs::read(path).map_err(|err| {
+        format!(
+            "Failed to read file ({}) for leaderboard {name}\n{err}",
+            path.display()
+        )
+    })?;
+
+    let mut offset = 0;
+    let mut count = 0;
+    while offset < bytes.len() {
+        match bincode::decode_from_slice(&bytes[offset..], bincode::config::standard()) {
+            Ok((record, read)) => {
+                apply(record);
+                offset += read;
+                count += 1;
+            }
+            Err(_) => break,
+        }
+    }
+
+    if offset < bytes.len() {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(path)
+            .and_then(|file| file.set_len(offset as u64))
+            .map_err(|err| format!("Failed to trim ({}).\n{err}", path.display()))?;
+    }
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod test;
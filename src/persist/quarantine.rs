@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{board_path, delta_path, part_path};
+
+// Saves that can't be read are moved to quarantine/{name}/ so the board can start without them,
+// as {millis}.board and {millis}.delta with why in {millis}.reason. Nothing is ever removed from
+// quarantine except by retrying it.
+pub fn quarantine_path(saves_path: &Path, name: &str) -> PathBuf {
+    saves_path.join("quarantine").join(name)
+}
+
+// How a board starts when its save is quarantined.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuarantineMode {
+    #[default]
+    Empty,
+    // empty, and API keys can't change it until the save is retried
+    ReadOnly,
+}
+
+pub struct Quarantined {
+    pub name: String,
+    // the name it is retried by
+    pub id: String,
+    pub created: SystemTime,
+    pub reason: String,
+    pub board: Option<PathBuf>,
+    pub delta: Option<PathBuf>,
+}
+
+// Moves the board's save and the deltas on top of it into quarantine.
+pub fn quarantine_save(saves_path: &Path, name: &str, reason: &str) -> Result<Quarantined, String> {
+    let dir = quarantine_path(saves_path, name);
+    fs::create_dir_all(&dir)
+        .map_err(|err| format!("Failed to create quarantine folder for {name}.\n{err}"))?;
+
+    let mut created = SystemTime::now();
+    let mut id;
+    loop {
+        id = millis(created).to_string();
+        if !dir.join(format!("{id}.reason")).exists() {
+            break;
+        }
+        created += Duration::from_millis(1);
+    }
+
+    let move_file = |from: PathBuf, ext: &str| -> Result<Option<PathBuf>, String> {
+        if !from.exists() {
+            return Ok(None);
+        }
+        let to = dir.join(format!("{id}.{ext}"));
+        fs::rename(&from, &to).map_err(|err| {
+            format!(
+                "Failed to move ({}) into quarantine.\n{err}",
+                from.display()
+            )
+        })?;
+        Ok(Some(to))
+    };
+
+    // the reason goes first, files without one aren't listed
+    fs::write(dir.join(format!("{id}.reason")), reason)
+        .map_err(|err| format!("Failed to quarantine the save of {name}.\n{err}"))?;
+    // with no .board, a .part left by an interrupted save is the save
+    let board = match move_file(board_path(saves_path, name), "board")? {
+        Some(v) => Some(v),
+        None => move_file(part_path(saves_path, name), "board")?,
+    };
+    let delta = move_file(delta_path(saves_path, name), "delta")?;
+
+    Ok(Quarantined {
+        name: name.to_string(),
+        id: id,
+        created: created,
+        reason: reason.to_string(),
+        board: board,
+        delta: delta,
+    })
+}
+
+// Everything in quarantine, newest first.
+pub fn list_quarantined(saves_path: &Path) -> Result<Vec<Quarantined>, String> {
+    let root = saves_path.join("quarantine");
+    if !root.exists() {
+        return Ok(Vec::new());
+    }
+
+    let read_root =
+        fs::read_dir(&root).map_err(|err| format!("Failed to list quarantine.\n{err}"))?;
+
+    let mut quarantined = Vec::new();
+    for board_dir in read_root.flatten() {
+        let name = match board_dir.file_name().to_str() {
+            Some(v) => v.to_string(),
+            None => continue,
+        };
+        let read_dir = match fs::read_dir(board_dir.path()) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        for file in read_dir.flatten() {
+            let path = file.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("reason") {
+                continue;
+            }
+            let stamp = match path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<u64>().ok())
+            {
+                Some(v) => v,
+                None => continue,
+            };
+            let existing = |ext: &str| Some(path.with_extension(ext)).filter(|path| path.exists());
+
+            quarantined.push(Quarantined {
+                name: name.clone(),
+                id: stamp.to_string(),
+                created: UNIX_EPOCH + Duration::from_millis(stamp),
+                reason: fs::read_to_string(&path).unwrap_or_default(),
+                board: existing("board"),
+                delta: existing("delta"),
+            });
+        }
+    }
+
+    quarantined.sort_unstable_by_key(|quarantined| Reverse(quarantined.created));
+    Ok(quarantined)
+}
+
+pub fn find_quarantined(
+    saves_path: &Path,
+    name: &str,
+    id: &str,
+) -> Result<Option<Quarantined>, String> {
+    Ok(list_quarantined(saves_path)?
+        .into_iter()
+        .find(|quarantined| quarantined.name == name && quarantined.id == id))
+}
+
+// Called once a retry has taken everything it needs from the files.
+pub fn release_quarantined(saves_path: &Path, quarantined: &Quarantined) -> Result<(), String> {
+    let dir = quarantine_path(saves_path, &quarantined.name);
+    // the reason goes last, so a failed release is still listed
+    for ext in ["board", "delta", "reason"] {
+        let path = dir.join(format!("{}.{ext}", quarantined.id));
+        if path.exists() {
+            fs::remove_file(&path).map_err(|err| {
+                format!(
+                    "Failed to remove ({}) from quarantine.\n{err}",
+                    path.display()
+                )
+            })?;
+        }
+    }
+
+    // only goes once it is empty
+    let _ = fs::remove_dir(dir);
+    Ok(())
+}
+
+fn millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,446 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+
+use super::*;
+use crate::board::Tree;
+
+fn temp_saves(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("leaderboard_{name}_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+    path
+}
+
+#[test]
+fn test_save_load() {
+    let saves = temp_saves("save_load");
+
+    let mut board: Board<i64, f64> = Board::new();
+    for i in 0..500 {
+        let _ = board.update_entry(i, (i % 37) as f64);
+    }
+
+    assert!(
+        load_board::<i64, f64, Tree<Entry<i64, f64>>>(&saves, "main")
+            .unwrap()
+            .is_none()
+    );
+
+    save_board(&board, &saves, "main", Compression::None).unwrap();
+    assert!(!part_path(&saves, "main").exists());
+    let loaded: Board<i64, f64> = load_board(&saves, "main").unwrap().unwrap();
+    assert!(loaded.get_top_cacheless(500) == board.get_top_cacheless(500));
+
+    // snapshot saves use the same format
+    let mut saved = SavedEntries::from_map(&board.get_map_snapshot().get_lock());
+    saved.sort();
+    save_entries(&saved, &saves, "snapshot", Compression::None).unwrap();
+    let loaded: Board<i64, f64> = load_board(&saves, "snapshot").unwrap().unwrap();
+    assert!(loaded.get_ids() == board.get_ids());
+
+    let mut ranks = HashMap::new();
+    ranks.insert(5i64, 3usize);
+    save_previous_ranks(&ranks, &saves, "main").unwrap();
+    assert!(load_previous_ranks::<i64>(&saves, "main").unwrap() == Some(ranks));
+    assert!(
+        load_previous_ranks::<i64>(&saves, "snapshot")
+            .unwrap()
+            .is_none()
+    );
+
+    let _ = fs::remove_dir_all(&saves);
+}
+
+#[test]
+fn test_recover_part() {
+    let saves = temp_saves("recover_part");
+
+    let mut board: Board<i64, f64> = Board::new();
+    let _ = board.update_entry(1, 10.0);
+    save_board(&board, &saves, "main", Compression::None).unwrap();
+
+    // an interrupted rename leaves only the .part behind
+    fs::rename(board_path(&saves, "main"), part_path(&saves, "main")).unwrap();
+    let loaded: Board<i64, f64> = load_board(&saves, "main").unwrap().unwrap();
+    assert_eq!(loaded.get_size(), 1);
+    assert!(board_path(&saves, "main").exists());
+
+    // a .part beside a good save is stale and removed
+    fs::write(part_path(&saves, "main"), b"partial").unwrap();
+    assert!(
+        load_board::<i64, f64, Tree<Entry<i64, f64>>>(&saves, "main")
+            .unwrap()
+            .is_some()
+    );
+    assert!(!part_path(&saves, "main").exists());
+
+    // a .part cut short isn't promoted, and is quarantined as the save
+    let bytes = fs::read(board_path(&saves, "main")).unwrap();
+    assert!(check_board_file(&bytes).is_ok());
+    fs::remove_file(board_path(&saves, "main")).unwrap();
+    fs::write(part_path(&saves, "main"), &bytes[..bytes.len() - 1]).unwrap();
+    let err = load_board::<i64, f64, Tree<Entry<i64, f64>>>(&saves, "main")
+        .err()
+        .unwrap();
+    assert!(err.contains("truncated"));
+    assert!(!board_path(&saves, "main").exists());
+    let quarantined = quarantine_save(&saves, "main", &err).unwrap();
+    assert!(quarantined.board.is_some());
+    assert!(!part_path(&saves, "main").exists());
+    assert!(check_board_file(b"partial").is_err());
+
+    fs::write(board_path(&saves, "broken"), b"\xff\xff\xff").unwrap();
+    assert!(load_board::<i64, f64, Tree<Entry<i64, f64>>>(&saves, "broken").is_err());
+
+    let _ = fs::remove_dir_all(&saves);
+}
+
+#[test]
+fn test_board_format() {
+    let saves = temp_saves("board_format");
+
+    let mut board: Board<i64, f64> = Board::new();
+    for i in 0..200 {
+        let _ = board.update_entry(i, (i % 13) as f64);
+    }
+    save_board(&board, &saves, "main", Compression::None).unwrap();
+    let bytes = fs::read(board_path(&saves, "main")).unwrap();
+    assert!(bytes.starts_with(b"LBRD"));
+
+    let load = |bytes: &[u8]| read_board_file::<i64, f64>(bytes).map(|(saved, _)| saved.0.len());
+    assert_eq!(load(&bytes), Ok(200));
+
+    // every corruption is caught before the entries are used
+    let mut flipped = bytes.clone();
+    let last = flipped.len() - 1;
+    flipped[last] ^= 1;
+    assert!(load(&flipped).unwrap_err().contains("Checksum"));
+    assert!(
+        load(&bytes[..bytes.len() - 10])
+            .unwrap_err()
+            .contains("truncated")
+    );
+    assert!(load(&bytes[..10]).unwrap_err().contains("Header"));
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert!(load(&longer).is_err());
+
+    let mut newer = bytes.clone();
+    newer[4] = 0xff;
+    assert!(load(&newer).unwrap_err().contains("version"));
+
+    let err = read_board_file::<u64, f64>(&bytes).map(|_| ()).unwrap_err();
+    assert!(err.contains("i64 keys"));
+    assert!(read_board_file::<i64, f32>(&bytes).is_err());
+
+    // a corrupt save is reported instead of loaded
+    fs::write(board_path(&saves, "main"), &flipped).unwrap();
+    assert!(load_board::<i64, f64, Tree<Entry<i64, f64>>>(&saves, "main").is_err());
+
+    let _ = fs::remove_dir_all(&saves);
+}
+
+#[test]
+fn test_legacy_board() {
+    let saves = temp_saves("legacy_board");
+
+    let mut board: Board<i64, f64> = Board::new();
+    for i in 0..100 {
+        let _ = board.update_entry(i, i as f64);
+    }
+
+    // saves from before the header were the bare bincode of the entries
+    let legacy = bincode::encode_to_vec(&board, bincode::config::standard()).unwrap();
+    fs::write(board_path(&saves, "main"), &legacy).unwrap();
+    let (_, header) = read_board_file::<i64, f64>(&legacy).unwrap();
+    assert_eq!(header.version, 0);
+
+    // loading one migrates it to the current version
+    let loaded: Board<i64, f64> = load_board(&saves, "main").unwrap().unwrap();
+    assert!(loaded.get_top_cacheless(100) == board.get_top_cacheless(100));
+    let bytes = fs::read(board_path(&saves, "main")).unwrap();
+    let (saved, header) = read_board_file::<i64, f64>(&bytes).unwrap();
+    assert_eq!(header.version, FORMAT_VERSION);
+    assert_eq!(saved.0.len(), 100);
+
+    // trailing bytes on a legacy save mean it isn't what it claims to be
+    let mut longer = legacy.clone();
+    longer.extend_from_slice(&[1, 2, 3]);
+    assert!(read_board_file::<i64, f64>(&longer).is_err());
+    assert!(read_board_file::<i64, f64>(&legacy[..legacy.len() - 3]).is_err());
+
+    assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+
+    let _ = fs::remove_dir_all(&saves);
+}
+
+#[test]
+fn test_compression() {
+    let saves = temp_saves("compression");
+
+    let mut board: Board<i64, f64> = Board::new();
+    for i in 0..2000 {
+        let _ = board.update_entry(i, (i % 10) as f64);
+    }
+    save_board(&board, &saves, "plain", Compression::None).unwrap();
+    let plain = fs::read(board_path(&saves, "plain")).unwrap();
+
+    #[cfg(all(feature = "lz4", feature = "zstd"))]
+    for compression in [Compression::Lz4, Compression::Zstd(3)] {
+        save_board(&board, &saves, "main", compression).unwrap();
+        let bytes = fs::read(board_path(&saves, "main")).unwrap();
+        assert!(bytes.len() < plain.len());
+
+        // the codec is read from the header, whatever the board is set to now
+        let (_, header) = read_board_file::<i64, f64>(&bytes).unwrap();
+        assert_eq!(header.compression.name(), compression.name());
+        let loaded: Board<i64, f64> = load_board(&saves, "main").unwrap().unwrap();
+        assert!(loaded.get_top_cacheless(2000) == board.get_top_cacheless(2000));
+
+        let mut flipped = bytes.clone();
+        flipped[40] ^= 1;
+        assert!(read_board_file::<i64, f64>(&flipped).is_err());
+    }
+
+    // version 1 saves had no codec byte and are migrated uncompressed
+    let mut old = plain[..8].to_vec();
+    old[4] = 1;
+    old.extend_from_slice(&plain[9..]);
+    fs::write(board_path(&saves, "old"), &old).unwrap();
+    let loaded: Board<i64, f64> = load_board(&saves, "old").unwrap().unwrap();
+    assert_eq!(loaded.get_size(), 2000);
+    let (_, header) =
+        read_board_file::<i64, f64>(&fs::read(board_path(&saves, "old")).unwrap()).unwrap();
+    assert_eq!(header.version, FORMAT_VERSION);
+
+    let _ = fs::remove_dir_all(&saves);
+}
+
+#[test]
+fn test_backups() {
+    let saves = temp_saves("backups");
+
+    let mut board: Board<i64, f64> = Board::new();
+    assert!(backup_board(&saves, "main").unwrap().is_none());
+
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        let _ = board.update_entry(i, i as f64);
+        save_board(&board, &saves, "main", Compression::None).unwrap();
+        ids.push(backup_board(&saves, "main").unwrap().unwrap().id);
+    }
+
+    // newest first, each holding the board as it was saved
+    let backups = list_backups(&saves, "main").unwrap();
+    assert!(
+        backups
+            .iter()
+            .map(|b| b.id.clone())
+            .eq(ids.iter().rev().cloned())
+    );
+    let (saved, _) = read_entries_at::<i64, f64>(&backups[4].path, "main").unwrap();
+    assert_eq!(saved.0.len(), 1);
+    let found = find_backup(&saves, "main", &ids[2]).unwrap().unwrap();
+    let (saved, _) = read_entries_at::<i64, f64>(&found.path, "main").unwrap();
+    assert_eq!(saved.0.len(), 3);
+    assert!(find_backup(&saves, "main", "1").unwrap().is_none());
+
+    let by_count = BackupRetention {
+        count: Some(3),
+        max_age: None,
+    };
+    assert_eq!(prune_backups(&saves, "main", by_count).unwrap(), 2);
+    assert_eq!(list_backups(&saves, "main").unwrap().len(), 3);
+
+    // the newest is kept however old it is
+    let by_age = BackupRetention {
+        count: None,
+        max_age: Some(0),
+    };
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    assert_eq!(prune_backups(&saves, "main", by_age).unwrap(), 2);
+    let backups = list_backups(&saves, "main").unwrap();
+    assert!(backups.len() == 1 && backups[0].id == ids[4]);
+
+    remove_backups(&saves, "main").unwrap();
+    assert!(list_backups(&saves, "main").unwrap().is_empty());
+
+    let _ = fs::remove_dir_all(&saves);
+}
+
+#[test]
+fn test_journal() {
+    let saves = temp_saves("journal");
+    let entry = |key: i64, points: f64| Entry {
+        key: key,
+        points: points,
+        timestamp: key as f64,
+    };
+
+    let mut journal = Journal::open(&saves, "main", FsyncPolicy::Always).unwrap();
+    journal
+        .append_all(&[
+            JournalRecord::Update(entry(1, 10.0)),
+            JournalRecord::Update(entry(2, 20.0)),
+            JournalRecord::Clear,
+            JournalRecord::Update(entry(3, 30.0)),
+            JournalRecord::Update(entry(4, 40.0)),
+        ])
+        .unwrap();
+    let mark = journal.mark();
+    journal
+        .append(&JournalRecord::<i64, f64>::Remove(3))
+        .unwrap();
+    journal
+        .append(&JournalRecord::Update(entry(5, 5.0)))
+        .unwrap();
+    let _ = drop(journal);
+
+    let replay = |saves: &Path| {
+        let mut board: Board<i64, f64> = Board::new();
+        let count = replay_journal(saves, "main", |record| record.apply(&mut board)).unwrap();
+        (count, board)
+    };
+
+    let (count, board) = replay(&saves);
+    assert_eq!(count, 7);
+    assert!(board.get_ids() == vec![5, 4]);
+    // replayed entries keep their timestamps
+    assert!(board.get_entry(&4) == Some(entry(4, 40.0)));
+
+    // a record cut short by a crash is dropped and trimmed off
+    let full_len = fs::metadata(journal_path(&saves, "main")).unwrap().len();
+    let mut file = fs::OpenOptions::new()
+        .append(true)
+        .open(journal_path(&saves, "main"))
+        .unwrap();
+    file.write_all(&[0, 6]).unwrap();
+    let _ = drop(file);
+    assert_eq!(replay(&saves).0, 7);
+    assert_eq!(
+        fs::metadata(journal_path(&saves, "main")).unwrap().len(),
+        full_len
+    );
+
+    // records appended after the mark outlive the truncation
+    let mut journal = Journal::open(&saves, "main", FsyncPolicy::Never).unwrap();
+    journal.truncate_to(mark).unwrap();
+    let (count, board) = replay(&saves);
+    assert_eq!(count, 2);
+    assert!(board.get_ids() == vec![5]);
+
+    journal
+        .append(&JournalRecord::Update(entry(6, 6.0)))
+        .unwrap();
+    let mark = journal.mark();
+    journal.truncate_to(mark).unwrap();
+    assert_eq!(replay(&saves).0, 0);
+
+    let _ = fs::remove_dir_all(&saves);
+}
+
+#[test]
+fn test_deltas() {
+    let saves = temp_saves("deltas");
+
+    let mut board: Board<i64, f64> = Board::new();
+    for i in 0..100 {
+        let _ = board.update_entry(i, i as f64);
+    }
+    save_board(&board, &saves, "main", Compression::None).unwrap();
+    let _ = board.take_delta();
+
+    let _ = board.update_entry(5, 500.0);
+    let _ = board.remove_entry(&6);
+    append_delta(&board.take_delta(), &saves, "main").unwrap();
+    let _ = board.update_entry(6, 600.0);
+    let _ = board.update_entry(200, 1.0);
+    append_delta(&board.take_delta(), &saves, "main").unwrap();
+
+    // a delta cut short by a crash is dropped
+    let mut file = fs::OpenOptions::new()
+        .append(true)
+        .open(delta_path(&saves, "main"))
+        .unwrap();
+    file.write_all(&[0, 9, 9]).unwrap();
+    let _ = drop(file);
+
+    let mut loaded: Board<i64, f64> = load_board(&saves, "main").unwrap().unwrap();
+    let count = load_deltas(&saves, "main", |delta| loaded.apply_delta(delta)).unwrap();
+    assert_eq!(count, 2);
+    assert!(loaded.get_top_cacheless(200) == board.get_top_cacheless(200));
+
+    remove_deltas(&saves, "main").unwrap();
+    assert!(!delta_path(&saves, "main").exists());
+    assert!(remove_deltas(&saves, "main").is_ok());
+
+    let _ = fs::remove_dir_all(&saves);
+}
+
+#[test]
+fn test_quarantine() {
+    let saves = temp_saves("quarantine");
+
+    let mut board: Board<i64, f64> = Board::new();
+    for i in 0..20 {
+        let _ = board.update_entry(i, i as f64);
+    }
+    save_board(&board, &saves, "main", Compression::None).unwrap();
+    let _ = board.take_delta();
+    let _ = board.update_entry(30, 30.0);
+    append_delta(&board.take_delta(), &saves, "main").unwrap();
+
+    // a corrupt save fails to load until it is moved out of the way
+    let mut bytes = fs::read(board_path(&saves, "main")).unwrap();
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    fs::write(board_path(&saves, "main"), &bytes).unwrap();
+    let err = load_board::<i64, f64, Tree<Entry<i64, f64>>>(&saves, "main")
+        .err()
+        .unwrap();
+
+    let quarantined = quarantine_save(&saves, "main", &err).unwrap();
+    assert!(!board_path(&saves, "main").exists());
+    assert!(!delta_path(&saves, "main").exists());
+    assert!(
+        load_board::<i64, f64, Tree<Entry<i64, f64>>>(&saves, "main")
+            .unwrap()
+            .is_none()
+    );
+
+    let listed = list_quarantined(&saves).unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].name, "main");
+    assert_eq!(listed[0].reason, err);
+    assert_eq!(listed[0].board, quarantined.board);
+    assert_eq!(listed[0].delta, quarantined.delta);
+
+    // once put right, the files are read from quarantine as they were saved
+    fs::write(quarantined.board.as_ref().unwrap(), {
+        bytes[last] ^= 0xff;
+        &bytes
+    })
+    .unwrap();
+    let found = find_quarantined(&saves, "main", &quarantined.id)
+        .unwrap()
+        .unwrap();
+    let (saved, _) = read_entries_at::<i64, f64>(found.board.as_ref().unwrap(), "main").unwrap();
+    let mut loaded: Board<i64, f64> = Board::from_saved(saved);
+    read_deltas_at(found.delta.as_ref().unwrap(), "main", |delta| {
+        loaded.apply_delta(delta)
+    })
+    .unwrap();
+    assert!(loaded.get_top_cacheless(30) == board.get_top_cacheless(30));
+    assert!(
+        find_quarantined(&saves, "other", &quarantined.id)
+            .unwrap()
+            .is_none()
+    );
+
+    release_quarantined(&saves, &found).unwrap();
+    assert!(list_quarantined(&saves).unwrap().is_empty());
+    assert!(!quarantine_path(&saves, "main").exists());
+
+    let _ = fs::remove_dir_all(&saves);
+}
@@ -0,0 +1,137 @@
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(test)]
+mod test;
+
+// What an API key can do on a board. read_own only reads the player the key is bound to, so a
+// client can look itself up without listing the board, and admin is all scopes on this board.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    Read,
+    ReadOwn,
+    Submit,
+    Remove,
+    Clear,
+    Admin,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 6] = [
+        Scope::Read,
+        Scope::ReadOwn,
+        Scope::Submit,
+        Scope::Remove,
+        Scope::Clear,
+        Scope::Admin,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::ReadOwn => "read_own",
+            Scope::Submit => "submit",
+            Scope::Remove => "remove",
+            Scope::Clear => "clear",
+            Scope::Admin => "admin",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Scope::ALL
+            .into_iter()
+            .find(|scope| scope.name().eq_ignore_ascii_case(name))
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+// A set of scopes, kept in boards.json as a list of their names.
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct Scopes(u8);
+
+impl Scopes {
+    pub const NONE: Scopes = Scopes(0);
+
+    // What the older "write" setting allowed, before there were scopes. It never gave admin.
+    pub fn from_write(write: bool) -> Self {
+        match write {
+            true => Scopes::of(&[Scope::Read, Scope::Submit, Scope::Remove]),
+            false => Scopes::of(&[Scope::Read]),
+        }
+    }
+
+    pub fn of(scopes: &[Scope]) -> Self {
+        Scopes(scopes.iter().fold(0, |bits, scope| bits | scope.bit()))
+    }
+
+    pub fn all() -> Self {
+        Scopes::of(&Scope::ALL)
+    }
+
+    // Whether the set allows what the scope does, which admin always does and read does for
+    // read_own.
+    pub fn allows(self, scope: Scope) -> bool {
+        self.contains(Scope::Admin)
+            || self.contains(scope)
+            || (scope == Scope::ReadOwn && self.contains(Scope::Read))
+    }
+
+    pub fn contains(self, scope: Scope) -> bool {
+        self.0 & scope.bit() != 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = Scope> {
+        Scope::ALL
+            .into_iter()
+            .filter(move |scope| self.contains(*scope))
+    }
+
+    // The scopes as a comma separated list, "none" if there are none.
+    pub fn names(self) -> String {
+        if self == Scopes::NONE {
+            return "none".to_string();
+        }
+        self.iter().map(Scope::name).collect::<Vec<_>>().join(",")
+    }
+
+    // Reads a comma separated list of scopes, or y and n for what write did.
+    pub fn parse(list: &str) -> Result<Self, String> {
+        match list {
+            "y" | "Y" => return Ok(Scopes::from_write(true)),
+            "n" | "N" => return Ok(Scopes::from_write(false)),
+            "none" => return Ok(Scopes::NONE),
+            _ => {}
+        }
+
+        let mut scopes = Scopes::NONE;
+        for name in list.split(',').map(str::trim) {
+            match Scope::parse(name) {
+                Some(scope) => scopes.0 |= scope.bit(),
+                None => {
+                    return Err(format!(
+                        "Unknown scope '{name}', expected some of {}.",
+                        Scopes::all().names()
+                    ));
+                }
+            }
+        }
+        Ok(scopes)
+    }
+}
+
+impl Serialize for Scopes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Scopes {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let scopes = Vec::<Scope>::deserialize(deserializer).map_err(D::Error::custom)?;
+        Ok(Scopes::of(&scopes))
+    }
+}
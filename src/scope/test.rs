@@ -0,0 +1,61 @@
+use super::*;
+
+#[test]
+fn test_allows() {
+    let submit = Scopes::of(&[Scope::Submit]);
+    assert!(submit.allows(Scope::Submit));
+    assert!(!submit.allows(Scope::Remove));
+    assert!(!submit.allows(Scope::Read));
+    assert!(!submit.allows(Scope::ReadOwn));
+
+    // read covers read_own, but not the other way around
+    assert!(Scopes::of(&[Scope::Read]).allows(Scope::ReadOwn));
+    assert!(!Scopes::of(&[Scope::ReadOwn]).allows(Scope::Read));
+
+    let admin = Scopes::of(&[Scope::Admin]);
+    for scope in Scope::ALL {
+        assert!(admin.allows(scope));
+        assert!(!Scopes::NONE.allows(scope));
+    }
+
+    // write keys keep what they could do before scopes, and nothing more
+    let write = Scopes::from_write(true);
+    assert_eq!(
+        write,
+        Scopes::of(&[Scope::Read, Scope::Submit, Scope::Remove])
+    );
+    for scope in [Scope::Read, Scope::ReadOwn, Scope::Submit, Scope::Remove] {
+        assert!(write.allows(scope));
+    }
+    assert!(!write.allows(Scope::Clear));
+    assert!(!write.allows(Scope::Admin));
+    assert_eq!(Scopes::from_write(false), Scopes::of(&[Scope::Read]));
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        Scopes::parse("read_own, submit"),
+        Ok(Scopes::of(&[Scope::ReadOwn, Scope::Submit]))
+    );
+    assert_eq!(Scopes::parse("CLEAR"), Ok(Scopes::of(&[Scope::Clear])));
+    assert_eq!(Scopes::parse("y"), Ok(Scopes::from_write(true)));
+    assert_eq!(Scopes::parse("none"), Ok(Scopes::NONE));
+    assert!(Scopes::parse("read,write").unwrap_err().contains("'write'"));
+    assert!(Scopes::parse("").is_err());
+
+    assert_eq!(
+        Scopes::of(&[Scope::Submit, Scope::Read]).names(),
+        "read,submit"
+    );
+    assert_eq!(Scopes::NONE.names(), "none");
+}
+
+#[test]
+fn test_serde() {
+    let scopes = Scopes::of(&[Scope::ReadOwn, Scope::Admin]);
+    let json = serde_json::to_string(&scopes).unwrap();
+    assert_eq!(json, r#"["read_own","admin"]"#);
+    assert_eq!(serde_json::from_str::<Scopes>(&json).unwrap(), scopes);
+    assert!(serde_json::from_str::<Scopes>(r#"["read","write"]"#).is_err());
+}
@@ -0,0 +1,148 @@
+use rocket::Shutdown;
+use rocket::response::stream::{Event, EventStream};
+use rocket::tokio::select;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::app_state::AppState;
+use crate::backend::{self, Interaction, RankedEntry, User};
+use crate::board::Entry;
+use crate::{Key, Val};
+
+#[cfg(test)]
+mod test;
+
+#[derive(Serialize)]
+struct TopSnapshot<'a> {
+    entries: &'a [RankedEntry],
+}
+
+#[derive(Serialize)]
+struct TopDiff<'a> {
+    changed: Vec<&'a RankedEntry>,
+    // ranks that no longer hold an entry, because the board shrank
+    removed: Vec<usize>,
+}
+
+#[derive(Serialize)]
+struct RankUpdate {
+    id: Key,
+    rank: Option<usize>,
+    entry: Option<Entry<Key, Val>>,
+}
+
+fn is_same(a: &RankedEntry, b: &RankedEntry) -> bool {
+    a.0 == b.0 && a.1 == b.1
+}
+
+fn top_diff<'a>(old: &[RankedEntry], new: &'a [RankedEntry]) -> Option<TopDiff<'a>> {
+    let changed: Vec<&RankedEntry> = new
+        .iter()
+        .enumerate()
+        .filter(|(i, v)| old.get(*i).is_none_or(|o| !is_same(o, v)))
+        .map(|(_, v)| v)
+        .collect();
+    let removed: Vec<usize> = old[new.len().min(old.len())..]
+        .iter()
+        .map(|v| v.0)
+        .collect();
+
+    if changed.is_empty() && removed.is_empty() {
+        return None;
+    }
+
+    Some(TopDiff {
+        changed: changed,
+        removed: removed,
+    })
+}
+
+// Streams the board's top `count` entries: a snapshot first, then only the ranks that changed.
+pub fn stream_top(
+    state: Arc<AppState>,
+    user: User,
+    count: usize,
+    mut shutdown: Shutdown,
+) -> EventStream![] {
+    EventStream! {
+        let mut changes = state.subscribe_changes(&user.board);
+        let interaction = Interaction { user: user.clone(), state: (&state).into() };
+
+        let Ok(mut current) = backend::get_top(&interaction, count, false) else {
+            return;
+        };
+        yield Event::data(serde_json::to_string(&TopSnapshot { entries: &current }).unwrap()).event("snapshot");
+
+        loop {
+            select! {
+                res = changes.changed() => {
+                    if res.is_err() {
+                        break;
+                    }
+                },
+                _ = &mut shutdown => break,
+            };
+
+            // the board was unloaded, a reload or /admin/del_board removed it
+            let Ok(next) = backend::get_top(&interaction, count, false) else {
+                break;
+            };
+            if let Some(diff) = top_diff(&current, &next) {
+                yield Event::data(serde_json::to_string(&diff).unwrap()).event("diff");
+            }
+            current = next;
+        }
+    }
+}
+
+// Streams the rank and entry of a single key, sending an update whenever either changes.
+pub fn stream_rank(
+    state: Arc<AppState>,
+    user: User,
+    id: Key,
+    mut shutdown: Shutdown,
+) -> EventStream![] {
+    EventStream! {
+        let mut changes = state.subscribe_changes(&user.board);
+        let interaction = Interaction { user: user.clone(), state: (&state).into() };
+
+        let Ok(mut current) = backend::get_entry_and_rank(&interaction, &id) else {
+            return;
+        };
+        let update = RankUpdate {
+            id: id,
+            rank: current.as_ref().map(|v| v.0),
+            entry: current.as_ref().map(|v| v.1.clone()),
+        };
+        yield Event::data(serde_json::to_string(&update).unwrap()).event("rank");
+
+        loop {
+            select! {
+                res = changes.changed() => {
+                    if res.is_err() {
+                        break;
+                    }
+                },
+                _ = &mut shutdown => break,
+            };
+
+            let Ok(next) = backend::get_entry_and_rank(&interaction, &id) else {
+                break;
+            };
+            let unchanged = match (&current, &next) {
+                (Some(a), Some(b)) => is_same(a, b),
+                (None, None) => true,
+                _ => false,
+            };
+            if !unchanged {
+                let update = RankUpdate {
+                    id: id,
+                    rank: next.as_ref().map(|v| v.0),
+                    entry: next.as_ref().map(|v| v.1.clone()),
+                };
+                yield Event::data(serde_json::to_string(&update).unwrap()).event("rank");
+            }
+            current = next;
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use rocket::http::Header;
+use rocket::local::asynchronous::{Client, LocalResponse};
+use rocket::tokio::io::AsyncReadExt;
+
+use super::*;
+use crate::app_state::test::{TEST_CONFIG, temp_state, update};
+
+fn ranked(rank: usize, key: Key, points: Val) -> RankedEntry {
+    RankedEntry(
+        rank,
+        Entry {
+            key: key,
+            points: points,
+            timestamp: 0.0,
+        },
+        None,
+    )
+}
+
+#[test]
+fn test_top_diff() {
+    let old = vec![ranked(1, 4, 40.0), ranked(2, 3, 30.0), ranked(3, 2, 20.0)];
+
+    assert!(top_diff(&old, &old).is_none());
+
+    // 5 moves into second, pushing the rest down
+    let new = vec![ranked(1, 4, 40.0), ranked(2, 5, 35.0), ranked(3, 3, 30.0)];
+    let diff = top_diff(&old, &new).unwrap();
+    assert_eq!(
+        diff.changed
+            .iter()
+            .map(|v| (v.0, v.1.key))
+            .collect::<Vec<_>>(),
+        vec![(2, 5), (3, 3)]
+    );
+    assert!(diff.removed.is_empty());
+
+    // a new score for the same key at the same rank is a change
+    let new = vec![ranked(1, 4, 45.0), ranked(2, 3, 30.0), ranked(3, 2, 20.0)];
+    let diff = top_diff(&old, &new).unwrap();
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].1.points, 45.0);
+
+    let diff = top_diff(&old, &old[..1]).unwrap();
+    assert!(diff.changed.is_empty());
+    assert_eq!(diff.removed, vec![2, 3]);
+
+    let diff = top_diff(&[], &old).unwrap();
+    assert_eq!(diff.changed.len(), 3);
+    assert!(diff.removed.is_empty());
+}
+
+#[rocket::get("/stream/top?<count>")]
+fn top(interaction: Interaction, count: usize, shutdown: Shutdown) -> EventStream![] {
+    stream_top(
+        interaction.state.inner().clone(),
+        interaction.user,
+        count,
+        shutdown,
+    )
+}
+
+#[rocket::get("/stream/rank?<id>")]
+fn rank(interaction: Interaction, id: Key, shutdown: Shutdown) -> EventStream![] {
+    stream_rank(
+        interaction.state.inner().clone(),
+        interaction.user,
+        id,
+        shutdown,
+    )
+}
+
+async fn client(name: &str) -> (Client, Arc<AppState>) {
+    let (state, _) = temp_state(
+        name,
+        TEST_CONFIG,
+        r#"{"main": {"keys": {"k": {"write": true}}, "cap": null}}"#,
+    );
+    let state = Arc::new(state);
+    let rocket = rocket::build()
+        .manage(state.clone())
+        .mount("/", rocket::routes![top, rank]);
+    (Client::tracked(rocket).await.unwrap(), state)
+}
+
+// Reads up to the end of the next event, returning its name and data.
+async fn next_event(response: &mut LocalResponse<'_>) -> (String, serde_json::Value) {
+    let mut text = String::new();
+    let mut byte = [0u8; 1];
+    while !text.ends_with("\n\n") {
+        assert_eq!(response.read(&mut byte).await.unwrap(), 1);
+        text.push(byte[0] as char);
+    }
+
+    let mut event = String::new();
+    let mut data = String::new();
+    for line in text.lines() {
+        if let Some(v) = line.strip_prefix("event:") {
+            event = v.trim().to_string();
+        } else if let Some(v) = line.strip_prefix("data:") {
+            data += v.trim();
+        }
+    }
+    (event, serde_json::from_str(&data).unwrap())
+}
+
+#[rocket::async_test]
+async fn test_stream_top() {
+    let (client, state) = client("stream_top").await;
+    update(&state, "main", 1, 10.0);
+    update(&state, "main", 2, 20.0);
+
+    let mut response = client
+        .get("/stream/top?count=2")
+        .header(Header::new("x-api-key", "k"))
+        .dispatch()
+        .await;
+
+    let (event, data) = next_event(&mut response).await;
+    assert_eq!(event, "snapshot");
+    assert_eq!(data["entries"][0][1]["key"], 2);
+    assert_eq!(data["entries"][1][1]["key"], 1);
+
+    // a change below the window sends nothing, the next one that reaches it does
+    update(&state, "main", 0, 1.0);
+    update(&state, "main", 3, 15.0);
+
+    let (event, data) = next_event(&mut response).await;
+    assert_eq!(event, "diff");
+    assert_eq!(data["changed"].as_array().unwrap().len(), 1);
+    assert_eq!(data["changed"][0][0], 2);
+    assert_eq!(data["changed"][0][1]["key"], 3);
+    assert_eq!(data["removed"].as_array().unwrap().len(), 0);
+}
+
+#[rocket::async_test]
+async fn test_stream_rank() {
+    let (client, state) = client("stream_rank").await;
+    update(&state, "main", 1, 10.0);
+
+    let mut response = client
+        .get("/stream/rank?id=2")
+        .header(Header::new("x-api-key", "k"))
+        .dispatch()
+        .await;
+
+    let (event, data) = next_event(&mut response).await;
+    assert_eq!(event, "rank");
+    assert_eq!(data["id"], 2);
+    assert!(data["rank"].is_null());
+
+    update(&state, "main", 2, 20.0);
+    let (_, data) = next_event(&mut response).await;
+    assert_eq!(data["rank"], 1);
+    assert_eq!(data["entry"]["points"], 20.0);
+
+    // other keys moving around it don't change its rank
+    update(&state, "main", 1, 5.0);
+    update(&state, "main", 3, 30.0);
+    let (_, data) = next_event(&mut response).await;
+    assert_eq!(data["rank"], 2);
+}
+
+#[rocket::async_test]
+async fn test_stream_board_removed() {
+    let (client, state) = client("stream_board_removed").await;
+    update(&state, "main", 1, 10.0);
+
+    let mut response = client
+        .get("/stream/top?count=2")
+        .header(Header::new("x-api-key", "k"))
+        .dispatch()
+        .await;
+    assert_eq!(next_event(&mut response).await.0, "snapshot");
+
+    // the stream ends instead of reading a board that is gone, at most a heartbeat comes before
+    let board = "main".to_string();
+    assert!(state.delete_board(&board));
+    state.notify_change(&board);
+    let mut rest = String::new();
+    response.read_to_string(&mut rest).await.unwrap();
+    assert!(!rest.contains("data:"), "{rest}");
+}
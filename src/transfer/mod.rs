@@ -0,0 +1,265 @@
+use serde::de::{self, DeserializeSeed, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::io::{BufRead, Write};
+
+use crate::board::Entry;
+use crate::{Key, Val};
+
+// Boards are exported highest first, one row per entry with its rank, key, points and timestamp.
+// Imports read the same rows back, the rank is ignored and the timestamp is optional.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TransferFormat {
+    Csv,
+    Json,
+    // one JSON object per line
+    Ndjson,
+}
+
+impl TransferFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(TransferFormat::Csv),
+            "json" => Some(TransferFormat::Json),
+            "ndjson" | "jsonl" => Some(TransferFormat::Ndjson),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            TransferFormat::Csv => "text/csv",
+            TransferFormat::Json => "application/json",
+            TransferFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+}
+
+// What an import does with a key already on the board.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ConflictPolicy {
+    Replace,
+    Skip,
+    // keeps whichever has more points, the entry on the board on a tie
+    KeepBetter,
+}
+
+impl ConflictPolicy {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "replace" => Some(ConflictPolicy::Replace),
+            "skip" => Some(ConflictPolicy::Skip),
+            "keep_better" => Some(ConflictPolicy::KeepBetter),
+            _ => None,
+        }
+    }
+
+    // Whether an entry already on the board stays instead of the imported row.
+    pub fn keeps(self, existing: &Entry<Key, Val>, row: &ImportRow) -> bool {
+        match self {
+            ConflictPolicy::Replace => false,
+            ConflictPolicy::Skip => true,
+            ConflictPolicy::KeepBetter => existing.points >= row.points,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ExportRow<'a> {
+    rank: usize,
+    key: &'a Key,
+    points: &'a Val,
+    timestamp: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ImportRow {
+    pub key: Key,
+    pub points: Val,
+    // entries without one are timestamped as they are imported
+    #[serde(default)]
+    pub timestamp: Option<f64>,
+}
+
+// Writes entries, already in rank order, and returns how many there were.
+pub fn write_entries<'a, W: Write>(
+    entries: impl Iterator<Item = (usize, &'a Entry<Key, Val>)>,
+    format: TransferFormat,
+    writer: &mut W,
+) -> Result<usize, String> {
+    let mut count = 0;
+    let row = |rank: usize, entry: &'a Entry<Key, Val>| ExportRow {
+        rank: rank,
+        key: &entry.key,
+        points: &entry.points,
+        timestamp: entry.timestamp,
+    };
+
+    match format {
+        TransferFormat::Csv => {
+            writeln!(writer, "rank,key,points,timestamp").map_err(|err| err.to_string())?;
+            for (rank, entry) in entries {
+                writeln!(
+                    writer,
+                    "{rank},{},{},{}",
+                    entry.key, entry.points, entry.timestamp
+                )
+                .map_err(|err| err.to_string())?;
+                count += 1;
+            }
+        }
+        TransferFormat::Json => {
+            write!(writer, "[").map_err(|err| err.to_string())?;
+            for (rank, entry) in entries {
+                if count > 0 {
+                    write!(writer, ",").map_err(|err| err.to_string())?;
+                }
+                serde_json::to_writer(&mut *writer, &row(rank, entry))
+                    .map_err(|err| err.to_string())?;
+                count += 1;
+            }
+            writeln!(writer, "]").map_err(|err| err.to_string())?;
+        }
+        TransferFormat::Ndjson => {
+            for (rank, entry) in entries {
+                serde_json::to_writer(&mut *writer, &row(rank, entry))
+                    .map_err(|err| err.to_string())?;
+                writeln!(writer).map_err(|err| err.to_string())?;
+                count += 1;
+            }
+        }
+    }
+
+    writer.flush().map_err(|err| err.to_string())?;
+    Ok(count)
+}
+
+// Hands each row to apply as it is read, so a file never has to fit in memory. Stops at the
+// first row that can't be read or that apply fails on.
+pub fn read_rows<R: BufRead>(
+    reader: R,
+    format: TransferFormat,
+    mut apply: impl FnMut(ImportRow) -> Result<(), String>,
+) -> Result<(), String> {
+    match format {
+        TransferFormat::Csv => read_csv(reader, apply),
+        TransferFormat::Json => {
+            let mut deserializer = serde_json::Deserializer::from_reader(reader);
+            let mut error = None;
+            let result = deserializer.deserialize_seq(RowVisitor {
+                apply: &mut apply,
+                error: &mut error,
+            });
+            // an error from apply is returned as is, not as a parse error
+            if let Some(err) = error {
+                return Err(err);
+            }
+            result
+                .and_then(|_| deserializer.end())
+                .map_err(|err| format!("Invalid JSON: {err}"))
+        }
+        TransferFormat::Ndjson => {
+            for (i, line) in reader.lines().enumerate() {
+                let line = line.map_err(|err| err.to_string())?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let row = serde_json::from_str(&line)
+                    .map_err(|err| format!("Invalid row on line {}: {err}", i + 1))?;
+                apply(row)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// The header names the columns, so they can be in any order and others are ignored.
+fn read_csv<R: BufRead>(
+    reader: R,
+    mut apply: impl FnMut(ImportRow) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut lines = reader.lines().enumerate();
+    let header = match lines.next() {
+        Some((_, line)) => line.map_err(|err| err.to_string())?,
+        None => return Ok(()),
+    };
+    let columns: Vec<String> = split_csv(&header)
+        .map(|column| column.to_lowercase())
+        .collect();
+    let column = |name: &str| columns.iter().position(|column| column == name);
+
+    let (key_col, points_col) = match (column("key"), column("points")) {
+        (Some(key), Some(points)) => (key, points),
+        _ => {
+            return Err(
+                "The first line of a CSV must name its columns, including key and points."
+                    .to_string(),
+            );
+        }
+    };
+    let timestamp_col = column("timestamp");
+
+    for (i, line) in lines {
+        let line = line.map_err(|err| err.to_string())?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = split_csv(&line).collect();
+        let field = |col: usize| fields.get(col).copied().unwrap_or("");
+        let invalid = |name: &str| format!("Invalid {name} on line {}.", i + 1);
+
+        let row = ImportRow {
+            key: field(key_col).parse().map_err(|_| invalid("key"))?,
+            points: field(points_col).parse().map_err(|_| invalid("points"))?,
+            timestamp: match timestamp_col.map(field) {
+                None | Some("") => None,
+                Some(v) => Some(v.parse().map_err(|_| invalid("timestamp"))?),
+            },
+        };
+        apply(row)?;
+    }
+
+    Ok(())
+}
+
+// Every field is a number or a column name, so quotes are only ever around a whole field.
+fn split_csv(line: &str) -> impl Iterator<Item = &str> {
+    line.split(',').map(|field| field.trim().trim_matches('"'))
+}
+
+struct RowVisitor<'a, F> {
+    apply: &'a mut F,
+    error: &'a mut Option<String>,
+}
+
+impl<'de, 'a, F: FnMut(ImportRow) -> Result<(), String>> Visitor<'de> for RowVisitor<'a, F> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("an array of entries")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(row) = seq.next_element_seed(RowSeed)? {
+            if let Err(err) = (self.apply)(row) {
+                *self.error = Some(err.clone());
+                return Err(de::Error::custom(err));
+            }
+        }
+        Ok(())
+    }
+}
+
+struct RowSeed;
+
+impl<'de> DeserializeSeed<'de> for RowSeed {
+    type Value = ImportRow;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<ImportRow, D::Error> {
+        ImportRow::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,161 @@
+use crate::Board;
+
+use super::*;
+
+fn sample_board() -> Board<Key, Val> {
+    let mut board = Board::new();
+    for i in 0..50 {
+        board
+            .set_entry(Entry {
+                key: i,
+                points: ((i * 37) % 11) as Val + 0.5,
+                timestamp: 1000.0 + i as f64,
+            })
+            .unwrap();
+    }
+    board
+}
+
+fn read_all(bytes: &[u8], format: TransferFormat) -> Result<Vec<ImportRow>, String> {
+    let mut rows = Vec::new();
+    read_rows(bytes, format, |row| {
+        rows.push(row);
+        Ok(())
+    })?;
+    Ok(rows)
+}
+
+#[test]
+fn test_round_trip() {
+    let board = sample_board();
+
+    for format in [
+        TransferFormat::Csv,
+        TransferFormat::Json,
+        TransferFormat::Ndjson,
+    ] {
+        let mut bytes = Vec::new();
+        assert_eq!(write_entries(board.iter(), format, &mut bytes), Ok(50));
+
+        let rows = read_all(&bytes, format).unwrap();
+        assert_eq!(rows.len(), 50);
+        for ((_, entry), row) in board.iter().zip(rows.iter()) {
+            assert_eq!(entry.key, row.key);
+            assert_eq!(entry.points, row.points);
+            assert_eq!(Some(entry.timestamp), row.timestamp);
+        }
+
+        let mut imported: Board<Key, Val> = Board::new();
+        for row in rows {
+            imported
+                .set_entry(Entry {
+                    key: row.key,
+                    points: row.points,
+                    timestamp: row.timestamp.unwrap(),
+                })
+                .unwrap();
+        }
+        assert!(board.iter().eq(imported.iter()));
+    }
+
+    // ranks are written from the top down
+    let mut bytes = Vec::new();
+    write_entries(board.iter(), TransferFormat::Ndjson, &mut bytes).unwrap();
+    let first = String::from_utf8(bytes)
+        .unwrap()
+        .lines()
+        .next()
+        .unwrap()
+        .to_string();
+    let (_, top) = board.iter().next().unwrap();
+    assert!(first.starts_with(&format!("{{\"rank\":1,\"key\":{}", top.key)));
+
+    let mut bytes = Vec::new();
+    write_entries(
+        Board::<Key, Val>::new().iter(),
+        TransferFormat::Json,
+        &mut bytes,
+    )
+    .unwrap();
+    assert_eq!(read_all(&bytes, TransferFormat::Json).unwrap().len(), 0);
+}
+
+#[test]
+fn test_read_rows() {
+    // columns in any order, the timestamp optional and anything else ignored
+    let csv = b"points,\"key\",note\n2.5,7,a\n\n-1,8,b\n";
+    let rows = read_all(csv, TransferFormat::Csv).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(
+        (rows[0].key, rows[0].points, rows[0].timestamp),
+        (7, 2.5, None)
+    );
+    assert_eq!((rows[1].key, rows[1].points), (8, -1.0));
+
+    let csv = b"key,points,timestamp\n1,2,\n3,4,5\n";
+    let rows = read_all(csv, TransferFormat::Csv).unwrap();
+    assert_eq!(rows[0].timestamp, None);
+    assert_eq!(rows[1].timestamp, Some(5.0));
+
+    assert!(read_all(b"1,2\n3,4\n", TransferFormat::Csv).is_err());
+    assert!(
+        read_all(b"key,points\n1,2\nx,4\n", TransferFormat::Csv)
+            .unwrap_err()
+            .contains("line 3")
+    );
+    assert_eq!(read_all(b"", TransferFormat::Csv).unwrap().len(), 0);
+
+    let ndjson = b"{\"key\":1,\"points\":2}\n\n{\"key\":3,\"points\":4,\"timestamp\":5}\n";
+    let rows = read_all(ndjson, TransferFormat::Ndjson).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1].timestamp, Some(5.0));
+    assert!(read_all(b"{\"key\":1}\n", TransferFormat::Ndjson).is_err());
+
+    assert!(read_all(b"[{\"key\":1,\"points\":2}", TransferFormat::Json).is_err());
+    assert!(read_all(b"[{\"key\":1,\"points\":2}] 1", TransferFormat::Json).is_err());
+    assert!(read_all(b"{\"key\":1,\"points\":2}", TransferFormat::Json).is_err());
+
+    // an error from apply stops the read and is returned as is
+    let json = b"[{\"key\":1,\"points\":2},{\"key\":2,\"points\":3},{\"key\":3,\"points\":4}]";
+    let mut seen = 0;
+    let result = read_rows(&json[..], TransferFormat::Json, |row| {
+        seen += 1;
+        match row.key {
+            2 => Err("Stop.".to_string()),
+            _ => Ok(()),
+        }
+    });
+    assert_eq!(result, Err("Stop.".to_string()));
+    assert_eq!(seen, 2);
+}
+
+#[test]
+fn test_conflict_policy() {
+    assert_eq!(
+        ConflictPolicy::parse("keep_better"),
+        Some(ConflictPolicy::KeepBetter)
+    );
+    assert_eq!(
+        ConflictPolicy::parse("Replace"),
+        Some(ConflictPolicy::Replace)
+    );
+    assert_eq!(ConflictPolicy::parse("merge"), None);
+    assert_eq!(TransferFormat::parse("jsonl"), Some(TransferFormat::Ndjson));
+
+    let existing = Entry {
+        key: 1,
+        points: 10.0,
+        timestamp: 0.0,
+    };
+    let row = |points| ImportRow {
+        key: 1,
+        points: points,
+        timestamp: None,
+    };
+
+    assert!(!ConflictPolicy::Replace.keeps(&existing, &row(5.0)));
+    assert!(ConflictPolicy::Skip.keeps(&existing, &row(20.0)));
+    assert!(ConflictPolicy::KeepBetter.keeps(&existing, &row(5.0)));
+    assert!(ConflictPolicy::KeepBetter.keeps(&existing, &row(10.0)));
+    assert!(!ConflictPolicy::KeepBetter.keeps(&existing, &row(20.0)));
+}
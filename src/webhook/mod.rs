@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::thread;
+use std::time::Duration;
+
+use crate::board::{BoardEvent, Entry};
+use crate::{Key, Val};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    EnteredTop,
+    NewLeader,
+    Evicted,
+}
+
+fn default_events() -> Vec<WebhookEvent> {
+    vec![
+        WebhookEvent::EnteredTop,
+        WebhookEvent::NewLeader,
+        WebhookEvent::Evicted,
+    ]
+}
+
+fn default_top() -> usize {
+    10
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+fn default_backoff_ms() -> u64 {
+    500
+}
+
+fn default_queue_len() -> usize {
+    1024
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ConfigWebhook {
+    pub url: String,
+    #[serde(default = "default_events")]
+    pub events: Vec<WebhookEvent>,
+    // ranks at or above this count as the top for entered_top
+    #[serde(default = "default_top")]
+    pub top: usize,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    // delay before the first retry, doubled after each failed attempt
+    #[serde(default = "default_backoff_ms")]
+    pub backoff_ms: u64,
+    // notifications waiting beyond this are dropped rather than delaying the board, at least 1
+    #[serde(default = "default_queue_len")]
+    pub queue_len: usize,
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    board: &'a str,
+    event: WebhookEvent,
+    entry: &'a Entry<Key, Val>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_rank: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous: Option<&'a Entry<Key, Val>>,
+}
+
+// A webhook target with its own bounded queue and delivery thread, so a slow or failing
+// endpoint never holds up the board or any other webhook.
+pub struct Webhook {
+    pub config: ConfigWebhook,
+    sender: SyncSender<String>,
+}
+
+impl Webhook {
+    pub fn spawn(board: &str, config: ConfigWebhook) -> Self {
+        // a zero length channel would drop every notification made while a delivery is underway
+        let (sender, receiver) = mpsc::sync_channel(config.queue_len.max(1));
+
+        let thread_config = config.clone();
+        let thread_board = board.to_string();
+        thread::spawn(move || deliver_loop(thread_board, thread_config, receiver));
+
+        Self {
+            config: config,
+            sender: sender,
+        }
+    }
+
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.config.events.contains(&event)
+    }
+
+    // Queue the event if this webhook is interested in it. Never blocks.
+    pub fn notify(&self, board: &str, event: &BoardEvent<Key, Val>) {
+        let payload = match event {
+            BoardEvent::Ranked {
+                entry,
+                rank,
+                previous_rank,
+            } => {
+                let top = self.config.top;
+                if !self.wants(WebhookEvent::EnteredTop)
+                    || *rank > top
+                    || previous_rank.is_some_and(|v| v <= top)
+                {
+                    return;
+                }
+                Payload {
+                    board: board,
+                    event: WebhookEvent::EnteredTop,
+                    entry: entry,
+                    rank: Some(*rank),
+                    previous_rank: *previous_rank,
+                    previous: None,
+                }
+            }
+            BoardEvent::NewLeader { entry, previous } => {
+                if !self.wants(WebhookEvent::NewLeader) {
+                    return;
+                }
+                Payload {
+                    board: board,
+                    event: WebhookEvent::NewLeader,
+                    entry: entry,
+                    rank: Some(1),
+                    previous_rank: None,
+                    previous: previous.as_ref(),
+                }
+            }
+            BoardEvent::Evicted { entry } => {
+                if !self.wants(WebhookEvent::Evicted) {
+                    return;
+                }
+                Payload {
+                    board: board,
+                    event: WebhookEvent::Evicted,
+                    entry: entry,
+                    rank: None,
+                    previous_rank: None,
+                    previous: None,
+                }
+            }
+        };
+
+        let body = serde_json::to_string(&payload).unwrap();
+
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(body) {
+            let _ = writeln!(
+                &mut io::stderr().lock(),
+                "Webhook queue for {} is full, dropping notification.",
+                self.config.url
+            );
+        }
+    }
+}
+
+// The deepest rank any of the webhooks needs to hear about, or None when no events are needed.
+pub fn event_depth(webhooks: &[Webhook]) -> Option<usize> {
+    if webhooks.is_empty() {
+        return None;
+    }
+
+    Some(
+        webhooks
+            .iter()
+            .filter(|v| v.wants(WebhookEvent::EnteredTop))
+            .map(|v| v.config.top)
+            .max()
+            .unwrap_or(0),
+    )
+}
+
+fn deliver_loop(board: String, config: ConfigWebhook, receiver: Receiver<String>) {
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(10)))
+        .build()
+        .into();
+
+    // ends once the Webhook, and with it the sender, is dropped
+    while let Ok(body) = receiver.recv() {
+        let mut backoff = Duration::from_millis(config.backoff_ms);
+        let mut attempt = 0;
+
+        loop {
+            let result = agent
+                .post(&config.url)
+                .header("Content-Type", "application/json")
+                .send(&body);
+
+            match result {
+                Ok(_) => break,
+                Err(_) if attempt < config.retries => {
+                    attempt += 1;
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                }
+                Err(err) => {
+                    let _ = writeln!(
+                        &mut io::stderr().lock(),
+                        "Failed to deliver webhook for board {board} to {} after {} attempts.\n{err}",
+                        config.url,
+                        attempt + 1
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,128 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+use super::*;
+
+// Reads a single HTTP request off the stream and answers it with the given status, returning the body.
+fn answer(stream: TcpStream, status: &str) -> String {
+    let mut reader = BufReader::new(stream);
+    let mut content_len = 0;
+
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            content_len = value.trim().parse().unwrap();
+        }
+    }
+
+    let mut body = vec![0; content_len];
+    reader.read_exact(&mut body).unwrap();
+
+    let mut stream = reader.into_inner();
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+    );
+
+    String::from_utf8(body).unwrap()
+}
+
+fn stand_in() -> (TcpListener, ConfigWebhook) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let config = ConfigWebhook {
+        url: format!("http://{}/hook", listener.local_addr().unwrap()),
+        events: default_events(),
+        top: 3,
+        retries: 2,
+        backoff_ms: 10,
+        queue_len: 8,
+    };
+    (listener, config)
+}
+
+fn entry(key: Key, points: Val) -> Entry<Key, Val> {
+    Entry {
+        key: key,
+        points: points,
+        timestamp: 0.0,
+    }
+}
+
+#[test]
+fn test_delivery() {
+    let (listener, config) = stand_in();
+    let webhook = Webhook::spawn("test", config);
+
+    webhook.notify(
+        "test",
+        &BoardEvent::Ranked {
+            entry: entry(7, 50.0),
+            rank: 5,
+            previous_rank: None,
+        },
+    );
+    webhook.notify(
+        "test",
+        &BoardEvent::Ranked {
+            entry: entry(7, 60.0),
+            rank: 2,
+            previous_rank: Some(5),
+        },
+    );
+
+    let body = answer(listener.accept().unwrap().0, "200 OK");
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["board"], "test");
+    assert_eq!(json["event"], "entered_top");
+    assert_eq!(json["rank"], 2);
+    assert_eq!(json["previous_rank"], 5);
+    assert_eq!(json["entry"]["key"], 7);
+}
+
+#[test]
+fn test_retry() {
+    let (listener, config) = stand_in();
+    let webhook = Webhook::spawn("test", config);
+
+    webhook.notify(
+        "test",
+        &BoardEvent::NewLeader {
+            entry: entry(1, 10.0),
+            previous: Some(entry(2, 5.0)),
+        },
+    );
+
+    let first = answer(listener.accept().unwrap().0, "503 Service Unavailable");
+    let second = answer(listener.accept().unwrap().0, "200 OK");
+    assert_eq!(first, second);
+
+    let json: serde_json::Value = serde_json::from_str(&second).unwrap();
+    assert_eq!(json["event"], "new_leader");
+    assert_eq!(json["previous"]["key"], 2);
+}
+
+#[test]
+fn test_empty_queue() {
+    let (listener, mut config) = stand_in();
+    config.queue_len = 0;
+    let webhook = Webhook::spawn("test", config);
+
+    webhook.notify(
+        "test",
+        &BoardEvent::Evicted {
+            entry: entry(3, 1.0),
+        },
+    );
+
+    let body = answer(listener.accept().unwrap().0, "200 OK");
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["event"], "evicted");
+    assert_eq!(json["entry"]["key"], 3);
+}